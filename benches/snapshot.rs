@@ -0,0 +1,102 @@
+use std::collections::{HashMap, HashSet};
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use polymarket_arb_bot::pm::market_data::{assemble_snapshot, MarketDef, MissingBookPolicy};
+use polymarket_arb_bot::testutil::snapshot_builder::outcome;
+use polymarket_arb_bot::types::{OutcomeTop, Polarity, TokenId};
+
+const LEGS: usize = 2;
+
+fn markets(tokens: usize) -> Vec<MarketDef> {
+    (0..tokens / LEGS)
+        .map(|i| MarketDef {
+            market_id: format!("mkt-{}", i),
+            question: format!("Synthetic market {}?", i),
+            polarity: Polarity::Yes,
+            token_ids: (0..LEGS).map(|j| TokenId::from(format!("{:077}", i * LEGS + j))).collect(),
+            event_id: None,
+            event_title: None,
+            category: None,
+            tags: vec![],
+            min_order_size: None,
+            liquidity: None,
+            volume_24h: None,
+            redemption_value: None,
+            shared_by: None,
+        })
+        .collect()
+}
+
+/// Per-cycle token handling with interned ids: dedup, request body, top map, assembly.
+fn interned(markets: &[MarketDef]) -> usize {
+    let mut all: Vec<TokenId> = vec![];
+    let mut seen: HashSet<TokenId> = HashSet::new();
+    for m in markets {
+        for t in &m.token_ids {
+            if seen.insert(t.clone()) {
+                all.push(t.clone());
+            }
+        }
+    }
+    let body: Vec<&str> = all.iter().map(|t| t.as_str()).collect();
+    let mut tops: HashMap<TokenId, OutcomeTop> = HashMap::with_capacity(all.len());
+    for t in &all {
+        let id = seen.get(t.as_str()).cloned().unwrap_or_else(|| TokenId::from(t.as_str()));
+        let mut top = outcome("", dec!(0.49), dec!(0.5), dec!(10));
+        top.token_id = id.clone();
+        tops.insert(id, top);
+    }
+    body.len() + assemble_snapshot(markets, &tops, MissingBookPolicy::DropMarket, 0).markets.len()
+}
+
+/// The same steps with owned `String` ids, as before interning.
+fn owned_strings(markets: &[(String, String, Vec<String>)]) -> usize {
+    struct Top {
+        token_id: String,
+        best_ask_px: Option<Decimal>,
+    }
+    let mut all: Vec<String> = vec![];
+    let mut seen: HashSet<String> = HashSet::new();
+    for (_, _, tokens) in markets {
+        for t in tokens {
+            if seen.insert(t.clone()) {
+                all.push(t.clone());
+            }
+        }
+    }
+    let body: Vec<String> = all.to_vec();
+    let mut tops: HashMap<String, Top> = HashMap::new();
+    for t in &body {
+        tops.insert(t.clone(), Top { token_id: t.clone(), best_ask_px: Some(dec!(0.5)) });
+    }
+    let mut assembled = 0;
+    for (id, q, tokens) in markets {
+        let legs: Vec<Top> = tokens.iter()
+            .filter_map(|t| tops.get(t).map(|x| Top { token_id: x.token_id.clone(), best_ask_px: x.best_ask_px }))
+            .collect();
+        if legs.len() == tokens.len() {
+            black_box((id.clone(), q.clone(), legs));
+            assembled += 1;
+        }
+    }
+    body.len() + assembled
+}
+
+fn bench_snapshot_assembly(c: &mut Criterion) {
+    let mut g = c.benchmark_group("snapshot_assembly");
+    let tokens = 5000;
+    let defs = markets(tokens);
+    let owned: Vec<(String, String, Vec<String>)> = defs.iter()
+        .map(|m| (m.market_id.clone(), m.question.clone(), m.token_ids.iter().map(|t| t.to_string()).collect()))
+        .collect();
+    g.throughput(Throughput::Elements(tokens as u64));
+    g.bench_with_input(BenchmarkId::new("interned", tokens), &defs, |b, d| b.iter(|| interned(black_box(d))));
+    g.bench_with_input(BenchmarkId::new("owned_strings", tokens), &owned, |b, d| b.iter(|| owned_strings(black_box(d))));
+    g.finish();
+}
+
+criterion_group!(benches, bench_snapshot_assembly);
+criterion_main!(benches);
@@ -0,0 +1,117 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use polymarket_arb_bot::stats::Stats;
+use polymarket_arb_bot::strategy::fees::FeeModel;
+use polymarket_arb_bot::strategy::pricing::TopOfBook;
+use polymarket_arb_bot::strategy::sum_arb::{SizeRounding, SumArbStrategy};
+use polymarket_arb_bot::history::PriceHistory;
+use polymarket_arb_bot::strategy::{Strategy, StrategyContext};
+use polymarket_arb_bot::testutil::snapshot_builder::SnapshotBuilder;
+use polymarket_arb_bot::types::TimeInForce;
+
+fn strategy(max_leg_spread: Option<Decimal>, min_leg_size: Option<Decimal>) -> SumArbStrategy {
+    SumArbStrategy {
+        min_edge_bps: 30,
+        min_edge_bps_by_legs: Default::default(),
+        slippage_buffer_bps: 0,
+        warn_edge_bps: 150,
+        fees: std::sync::Arc::new(FeeModel::flat(20)),
+        pricing: std::sync::Arc::new(TopOfBook),
+        max_bundle_shares: dec!(5),
+        size_rounding: SizeRounding { decimals: 2 },
+        max_bundle_notional: None,
+        max_cycle_notional: None,
+        max_leg_spread,
+        max_leg_spread_pct: None,
+        min_leg_size,
+        available_collateral: None,
+        collateral_reserve: Decimal::ZERO,
+        bundle_fixed_cost: Decimal::ZERO,
+        exit_mode: Default::default(),
+        max_book_update_age_ms: None,
+        unknown_book_age_stale: false,
+        max_reference_deviation_bps: None,
+        min_legs: 2,
+        max_legs: 0,
+        max_bundles_per_snapshot: 0,
+        trace_market_ids: Default::default(),
+        log_leg_detail: false,
+        parallel_min_markets: 0,
+        vol_edge_k: None,
+        vol_lookback_ms: 60_000,
+        vol_min_samples: 5,
+        leg_tif: TimeInForce::Fok,
+        leg_post_only: false,
+        sum_ask_ema_alpha: None,
+        sum_ask_ema_band_bps: 300,
+        sum_ask_ema_guard: false,
+        min_plausible_sum_ask: None,
+        trust_extreme_arbs: false,
+        sum_ask_ema: Default::default(),
+        stats: Stats::new(0),
+    }
+}
+
+/// Every market is rejected by the per-leg spread filter on its last leg.
+fn bench_leg_filters(c: &mut Criterion) {
+    let mut g = c.benchmark_group("leg_filters");
+    let history = PriceHistory::new(0, 1);
+    let ctx = StrategyContext { history: &history, meta: None, reference: None };
+    let strat = strategy(Some(dec!(0.05)), Some(dec!(1)));
+    for &(markets, legs) in &[(1000usize, 2usize), (5000, 2), (5000, 8)] {
+        let mut snap = SnapshotBuilder::new().markets(markets).legs(legs).build();
+        for m in &mut snap.markets {
+            if let Some(last) = m.outcomes.last_mut() {
+                last.best_bid_px = Some(Decimal::ZERO);
+            }
+        }
+        g.throughput(Throughput::Elements(markets as u64));
+        g.bench_with_input(BenchmarkId::new(format!("{}legs", legs), markets), &snap, |b, snap| {
+            b.iter(|| strat.on_snapshot(black_box(snap), &ctx))
+        });
+    }
+    g.finish();
+}
+
+/// All markets pass the filters and go through the sum/threshold path.
+fn bench_full_sum(c: &mut Criterion) {
+    let mut g = c.benchmark_group("full_sum");
+    let history = PriceHistory::new(0, 1);
+    let ctx = StrategyContext { history: &history, meta: None, reference: None };
+    let strat = strategy(None, None);
+    for &markets in &[100usize, 1000, 5000] {
+        for &legs in &[2usize, 5] {
+            for &near in &[0.0f64, 0.1] {
+                let snap = SnapshotBuilder::new()
+                    .markets(markets)
+                    .legs(legs)
+                    .near_arb_fraction(near)
+                    .build();
+                g.throughput(Throughput::Elements(markets as u64));
+                let id = BenchmarkId::new(format!("{}legs_near{}", legs, near), markets);
+                g.bench_with_input(id, &snap, |b, snap| b.iter(|| strat.on_snapshot(black_box(snap), &ctx)));
+            }
+        }
+    }
+    g.finish();
+}
+
+/// Serial vs rayon quoting on the same 5000-market snapshot.
+fn bench_parallel(c: &mut Criterion) {
+    let mut g = c.benchmark_group("parallel");
+    let history = PriceHistory::new(0, 1);
+    let ctx = StrategyContext { history: &history, meta: None, reference: None };
+    let snap = SnapshotBuilder::new().markets(5000).legs(5).near_arb_fraction(0.01).build();
+    g.throughput(Throughput::Elements(5000));
+    for &(name, min_markets) in &[("serial", 0usize), ("rayon", 1)] {
+        let mut strat = strategy(None, None);
+        strat.parallel_min_markets = min_markets;
+        g.bench_with_input(BenchmarkId::new(name, 5000), &snap, |b, snap| b.iter(|| strat.on_snapshot(black_box(snap), &ctx)));
+    }
+    g.finish();
+}
+
+criterion_group!(benches, bench_leg_filters, bench_full_sum, bench_parallel);
+criterion_main!(benches);
@@ -0,0 +1,43 @@
+//! Tails the bot's event stream and prints opportunities.
+//!
+//!     cargo run --example tail_events -- 127.0.0.1:9100
+//!     cargo run --example tail_events -- unix:/tmp/arb-events.sock
+//!
+//! Pass `--all` to print every event line as received.
+
+use std::io::{BufRead, BufReader, Read};
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let all = args.iter().any(|a| a == "--all");
+    let addr = args.iter()
+        .find(|a| !a.starts_with("--"))
+        .cloned()
+        .unwrap_or_else(|| "127.0.0.1:9100".to_string());
+
+    let stream: Box<dyn Read> = match addr.strip_prefix("unix:") {
+        #[cfg(unix)]
+        Some(path) => Box::new(std::os::unix::net::UnixStream::connect(path)?),
+        #[cfg(not(unix))]
+        Some(_) => anyhow::bail!("unix sockets are not supported on this platform"),
+        None => Box::new(std::net::TcpStream::connect(&addr)?),
+    };
+    eprintln!("connected to {}", addr);
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if all {
+            println!("{}", line);
+            continue;
+        }
+        let ev: serde_json::Value = serde_json::from_str(&line)?;
+        if ev["type"] == "opportunity" {
+            println!(
+                "{} market={} bundle={} legs={} sum_ask={} size={}",
+                ev["ts_ms"], ev["market_id"], ev["bundle_id"], ev["legs"], ev["sum_ask"], ev["size"]
+            );
+        }
+    }
+    eprintln!("stream closed");
+    Ok(())
+}
@@ -0,0 +1,223 @@
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::hot_markets::HotMarket;
+use crate::refresh::{RefreshOutcome, RefreshTrigger};
+use crate::types::{GlobalSnapshot, LegGauge, OrderIntent};
+
+/// One row of GET /markets.
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketView {
+    pub market_id: String,
+    pub question: String,
+    /// None when some leg has no ask (or bid)
+    pub sum_ask: Option<Decimal>,
+    pub sum_bid: Option<Decimal>,
+    pub legs: usize,
+    /// Some legs had no book; sums are None
+    pub partial: bool,
+    /// Mid and spread per leg, in leg order
+    pub gauges: Vec<LegGauge>,
+}
+
+/// One bundle of GET /opportunities.
+#[derive(Debug, Clone, Serialize)]
+pub struct OpportunityView {
+    pub bundle_id: Uuid,
+    pub market_id: String,
+    pub legs: Vec<OrderIntent>,
+}
+
+/// What the main loop saw on its last evaluated snapshot.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LastCycleState {
+    pub ts_ms: i64,
+    pub markets: Vec<MarketView>,
+    pub opportunities: Vec<OpportunityView>,
+    /// Lifetime top markets by opportunity count, as of this cycle
+    pub hot_markets: Vec<HotMarket>,
+}
+
+impl LastCycleState {
+    pub fn from_cycle(snap: &GlobalSnapshot, intents: &[OrderIntent]) -> Self {
+        let markets = snap.markets.iter()
+            .map(|m| MarketView {
+                market_id: m.market_id.clone(),
+                question: m.question.clone(),
+                sum_ask: if m.partial { None } else { m.outcomes.iter().map(|o| o.best_ask_px).sum() },
+                sum_bid: if m.partial { None } else { m.outcomes.iter().map(|o| o.best_bid_px).sum() },
+                legs: m.outcomes.len(),
+                partial: m.partial,
+                gauges: m.outcomes.iter().map(|o| o.gauge()).collect(),
+            })
+            .collect();
+
+        let mut opportunities: Vec<OpportunityView> = vec![];
+        for i in intents {
+            match opportunities.iter_mut().find(|o| o.bundle_id == i.bundle_id) {
+                Some(o) => o.legs.push(i.clone()),
+                None => opportunities.push(OpportunityView {
+                    bundle_id: i.bundle_id,
+                    market_id: i.market_id.clone(),
+                    legs: vec![i.clone()],
+                }),
+            }
+        }
+
+        Self { ts_ms: snap.ts_ms, markets, opportunities, hot_markets: vec![] }
+    }
+}
+
+pub type SharedState = Arc<RwLock<LastCycleState>>;
+
+/// How long POST /refresh waits for the refresh before answering 202.
+const REFRESH_WAIT: Duration = Duration::from_secs(10);
+
+#[derive(Clone)]
+struct AppState {
+    last: SharedState,
+    refresh: Arc<RefreshTrigger>,
+}
+
+pub fn router(state: SharedState, refresh: Arc<RefreshTrigger>) -> Router {
+    Router::new()
+        .route("/opportunities", get(opportunities))
+        .route("/markets", get(markets))
+        .route("/hotmarkets", get(hot_markets))
+        .route("/refresh", post(refresh_markets))
+        .with_state(AppState { last: state, refresh })
+}
+
+/// Binds 0.0.0.0:`port` and serves the API in a background task.
+pub async fn spawn(port: u16, state: SharedState, refresh: Arc<RefreshTrigger>) -> Result<()> {
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("bind api server on {}", addr))?;
+    tracing::info!(%addr, "api server listening");
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, router(state, refresh)).await {
+            tracing::error!(err = %e, "api server stopped");
+        }
+    });
+    Ok(())
+}
+
+async fn opportunities(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let st = state.last.read().unwrap_or_else(|e| e.into_inner());
+    Json(serde_json::json!({ "ts_ms": st.ts_ms, "opportunities": st.opportunities }))
+}
+
+async fn markets(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let st = state.last.read().unwrap_or_else(|e| e.into_inner());
+    Json(serde_json::json!({ "ts_ms": st.ts_ms, "markets": st.markets }))
+}
+
+async fn hot_markets(State(state): State<AppState>) -> Json<serde_json::Value> {
+    let st = state.last.read().unwrap_or_else(|e| e.into_inner());
+    Json(serde_json::json!({ "ts_ms": st.ts_ms, "hot_markets": st.hot_markets }))
+}
+
+/// Refreshes the market list now instead of at the next MARKETS_REFRESH_SEC tick; the
+/// main loop picks the new list up before its next fetch.
+async fn refresh_markets(State(state): State<AppState>) -> (StatusCode, Json<serde_json::Value>) {
+    tracing::info!("market refresh requested via api");
+    match state.refresh.request_and_wait(REFRESH_WAIT).await {
+        Some(RefreshOutcome::Done(n)) => (StatusCode::OK, Json(serde_json::json!({ "markets": n }))),
+        Some(RefreshOutcome::Failed(err)) => (StatusCode::BAD_GATEWAY, Json(serde_json::json!({ "error": err }))),
+        None => (StatusCode::ACCEPTED, Json(serde_json::json!({ "status": "refresh in progress" }))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::snapshot_builder::SnapshotBuilder;
+    use crate::testutil::strategy::{run, sum_arb};
+
+    /// Serves `router` on an ephemeral local port and returns its base url.
+    async fn serve(state: SharedState, refresh: Arc<RefreshTrigger>) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router(state, refresh)).await });
+        format!("http://{}", addr)
+    }
+
+    async fn get_json(url: String) -> serde_json::Value {
+        reqwest::get(url).await.unwrap().json().await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn opportunities_after_one_cycle() {
+        // every fourth of eight markets at 0.98: two opportunities
+        let snap = SnapshotBuilder::new().markets(8).near_arb_fraction(0.25).ts_ms(1_000).build();
+        let (intents, _) = run(&sum_arb(), &snap);
+        let state = SharedState::default();
+        *state.write().unwrap() = LastCycleState::from_cycle(&snap, &intents);
+        let base = serve(state, Arc::default()).await;
+
+        let body = get_json(format!("{}/opportunities", base)).await;
+        assert_eq!(body["ts_ms"], 1_000);
+        let opps = body["opportunities"].as_array().unwrap();
+        let markets: Vec<&str> = opps.iter().map(|o| o["market_id"].as_str().unwrap()).collect();
+        assert_eq!(markets, ["mkt-3", "mkt-7"]);
+        for o in opps {
+            let legs = o["legs"].as_array().unwrap();
+            assert_eq!(legs.len(), 2);
+            assert!(legs.iter().all(|l| l["bundle_id"] == o["bundle_id"]));
+            assert_eq!(legs[0]["price"], "0.49");
+        }
+    }
+
+    #[tokio::test]
+    async fn markets_summarize_the_snapshot() {
+        let snap = SnapshotBuilder::new().markets(2).build();
+        let state = SharedState::default();
+        *state.write().unwrap() = LastCycleState::from_cycle(&snap, &[]);
+        let base = serve(state, Arc::default()).await;
+
+        let body = get_json(format!("{}/markets", base)).await;
+        let markets = body["markets"].as_array().unwrap();
+        assert_eq!(markets.len(), 2);
+        assert_eq!(markets[0]["market_id"], "mkt-0");
+        assert_eq!(markets[0]["legs"], 2);
+        assert_eq!(markets[0]["sum_ask"], "1.04");
+        assert_eq!(markets[0]["sum_bid"], "1.02");
+        assert!(get_json(format!("{}/opportunities", base)).await["opportunities"].as_array().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn refresh_answers_with_the_new_market_count() {
+        let refresh = Arc::new(RefreshTrigger::default());
+        let base = serve(SharedState::default(), refresh.clone()).await;
+        // stands in for the refresher: one refresh per request
+        let worker = refresh.clone();
+        tokio::spawn(async move {
+            worker.requested().await;
+            worker.finished(RefreshOutcome::Done(3));
+        });
+
+        let resp = reqwest::Client::new().post(format!("{}/refresh", base)).send().await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let body: serde_json::Value = resp.json().await.unwrap();
+        assert_eq!(body["markets"], 3);
+    }
+
+    #[test]
+    fn partial_markets_have_no_sums() {
+        let mut snap = SnapshotBuilder::new().markets(1).build();
+        snap.markets[0].partial = true;
+        let st = LastCycleState::from_cycle(&snap, &[]);
+        assert_eq!((st.markets[0].sum_ask, st.markets[0].sum_bid), (None, None));
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use serde::Serialize;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::episodes::Episode;
+use crate::pm::executor::BundleResult;
+use crate::stats::{Stats, StatsCsv, StatsSnapshot};
+
+/// Events buffered per subscriber; one that falls further behind skips the oldest.
+const BUS_CAPACITY: usize = 1024;
+/// Stats intervals kept for the CSV export.
+const STATS_CSV_MAX_ROWS: usize = 10_000;
+
+/// What happened in the bot, as seen by in-process consumers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// A market stopped clearing the execute threshold
+    OpportunityEpisode(Episode),
+    BundleExecuted(BundleResult),
+    /// The executor failed the whole cycle
+    ExecutionError { ts_ms: i64, error: String },
+    Stats(Box<StatsSnapshot>),
+}
+
+/// In-process fan-out of `Event`s. Publishing never waits; each subscriber has its own
+/// buffer and counts what it missed in `Stats` instead of holding producers back.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Arc<Event>>,
+    stats: Arc<Stats>,
+}
+
+impl EventBus {
+    pub fn new(stats: Arc<Stats>) -> Self {
+        let (tx, _) = broadcast::channel(BUS_CAPACITY);
+        Self { tx, stats }
+    }
+
+    pub fn publish(&self, event: Event) {
+        // only fails when nobody is subscribed
+        let _ = self.tx.send(Arc::new(event));
+    }
+
+    /// Events from now on; `name` labels lag warnings.
+    pub fn subscribe(&self, name: &'static str) -> Subscriber {
+        Subscriber { rx: self.tx.subscribe(), name, stats: self.stats.clone() }
+    }
+}
+
+pub struct Subscriber {
+    rx: broadcast::Receiver<Arc<Event>>,
+    name: &'static str,
+    stats: Arc<Stats>,
+}
+
+impl Subscriber {
+    /// Next event; skips (and counts) events lost to lag. None once every bus handle is
+    /// dropped and the buffer is drained.
+    pub async fn recv(&mut self) -> Option<Arc<Event>> {
+        loop {
+            match self.rx.recv().await {
+                Ok(e) => return Some(e),
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    self.stats.add_bus_events_dropped(missed);
+                    tracing::warn!(subscriber = self.name, missed, "event bus subscriber lagging, events dropped");
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// Appends every `Stats` event to `path` as one JSON line until the bus closes.
+pub fn spawn_stats_journal(bus: &EventBus, path: String) -> JoinHandle<()> {
+    spawn_journal(bus.subscribe("stats_journal"), path, |e| match e {
+        Event::Stats(ss) => serde_json::to_string(ss).ok(),
+        _ => None,
+    })
+}
+
+/// Appends every closed opportunity episode to `path` as one JSON line until the bus closes.
+pub fn spawn_episode_journal(bus: &EventBus, path: String) -> JoinHandle<()> {
+    spawn_journal(bus.subscribe("episode_journal"), path, |e| match e {
+        Event::OpportunityEpisode(ep) => serde_json::to_string(ep).ok(),
+        _ => None,
+    })
+}
+
+/// Appends every bundle execution result to `path` as one JSON line until the bus closes.
+pub fn spawn_execution_journal(bus: &EventBus, path: String) -> JoinHandle<()> {
+    spawn_journal(bus.subscribe("execution_journal"), path, |e| match e {
+        Event::BundleExecuted(r) => serde_json::to_string(r).ok(),
+        _ => None,
+    })
+}
+
+/// Appends the line `select` makes of each event (skipping None) to `path`, reopening
+/// after a failed write.
+fn spawn_journal(mut sub: Subscriber, path: String, select: fn(&Event) -> Option<String>) -> JoinHandle<()> {
+    let name = sub.name;
+    tokio::spawn(async move {
+        let mut file = None;
+        while let Some(e) = sub.recv().await {
+            let Some(line) = select(e.as_ref()) else { continue };
+            if file.is_none() {
+                match tokio::fs::OpenOptions::new().create(true).append(true).open(&path).await {
+                    Ok(f) => file = Some(f),
+                    Err(e) => {
+                        tracing::warn!(journal = name, path, err = %e, "journal open failed");
+                        continue;
+                    }
+                }
+            }
+            let Some(f) = file.as_mut() else { continue };
+            if let Err(e) = async { f.write_all(line.as_bytes()).await?; f.write_all(b"\n").await }.await {
+                tracing::warn!(journal = name, path, err = %e, "journal write failed");
+                file = None;
+            }
+        }
+    })
+}
+
+/// Buffers `Stats` events and rewrites `path` as CSV when the bus closes and, on unix,
+/// whenever the process gets SIGUSR1.
+pub fn spawn_stats_csv(bus: &EventBus, path: String) -> JoinHandle<()> {
+    let mut sub = bus.subscribe("stats_csv");
+    tokio::spawn(async move {
+        let mut csv = StatsCsv::new(STATS_CSV_MAX_ROWS);
+        #[cfg(unix)]
+        let mut usr1 = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1()) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                tracing::warn!(err = %e, "SIGUSR1 handler not installed; stats CSV is written at shutdown only");
+                None
+            }
+        };
+        loop {
+            #[cfg(unix)]
+            let e = tokio::select! {
+                e = sub.recv() => e,
+                Some(()) = async {
+                    match usr1.as_mut() {
+                        Some(s) => s.recv().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    write_stats_csv(&csv, &path).await;
+                    continue;
+                }
+            };
+            #[cfg(not(unix))]
+            let e = sub.recv().await;
+            let Some(e) = e else { break };
+            if let Event::Stats(ss) = e.as_ref() {
+                csv.push(ss);
+            }
+        }
+        write_stats_csv(&csv, &path).await;
+    })
+}
+
+async fn write_stats_csv(csv: &StatsCsv, path: &str) {
+    if csv.is_empty() {
+        return;
+    }
+    match tokio::fs::write(path, csv.render()).await {
+        Ok(()) => tracing::info!(path, intervals = csv.len(), "stats CSV written"),
+        Err(e) => tracing::warn!(path, err = %e, "stats CSV write failed"),
+    }
+}
+
+/// Logs execution outcomes: results at debug, cycle failures as warnings.
+pub fn spawn_execution_log(bus: &EventBus) -> JoinHandle<()> {
+    let mut sub = bus.subscribe("execution_log");
+    tokio::spawn(async move {
+        while let Some(e) = sub.recv().await {
+            match e.as_ref() {
+                Event::BundleExecuted(r) => tracing::debug!(
+                    bundle_id = %r.bundle_id,
+                    market_id = %r.market_id,
+                    status = ?r.status,
+                    legs_filled = r.legs_filled,
+                    legs = r.legs,
+                    result = %serde_json::to_string(r).unwrap_or_default(),
+                    "execution result"
+                ),
+                Event::ExecutionError { error, .. } => tracing::warn!(err = %error, "execution failed"),
+                _ => {}
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::temp_path;
+
+    fn error(n: usize) -> Event {
+        Event::ExecutionError { ts_ms: n as i64, error: format!("e{}", n) }
+    }
+
+    fn stats_event(stats: &Stats, now_ms: u64) -> Event {
+        Event::Stats(Box::new(stats.snapshot(now_ms)))
+    }
+
+    #[tokio::test]
+    async fn a_lagging_subscriber_counts_what_it_missed() {
+        let stats = Stats::new(0);
+        let bus = EventBus::new(stats.clone());
+        let mut sub = bus.subscribe("test");
+        for n in 0..BUS_CAPACITY + 3 {
+            bus.publish(error(n));
+        }
+
+        // the three oldest were overwritten; the next one received is the fourth
+        let e = sub.recv().await.unwrap();
+        assert!(matches!(e.as_ref(), Event::ExecutionError { ts_ms: 3, .. }), "{:?}", e);
+        assert_eq!(stats.snapshot(0).bus_events_dropped, 3);
+    }
+
+    #[tokio::test]
+    async fn dropping_the_bus_drains_buffered_events_then_ends() {
+        let bus = EventBus::new(Stats::new(0));
+        let mut sub = bus.subscribe("test");
+        let path = temp_path("executions.jsonl");
+        let journal = spawn_execution_journal(&bus, path.to_string_lossy().into_owned());
+        for n in 0..3 {
+            bus.publish(error(n));
+        }
+        drop(bus);
+
+        for n in 0..3 {
+            assert!(matches!(sub.recv().await.unwrap().as_ref(), Event::ExecutionError { ts_ms, .. } if *ts_ms == n));
+        }
+        assert!(sub.recv().await.is_none());
+        tokio::time::timeout(std::time::Duration::from_secs(5), journal).await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn stats_journal_writes_one_line_per_stats_event() {
+        let stats = Stats::new(0);
+        let bus = EventBus::new(stats.clone());
+        let path = temp_path("stats.jsonl");
+        let journal = spawn_stats_journal(&bus, path.to_string_lossy().into_owned());
+        bus.publish(stats_event(&stats, 1_000));
+        bus.publish(error(0));
+        bus.publish(stats_event(&stats, 2_000));
+        drop(bus);
+        journal.await.unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let at: Vec<u64> = text.lines()
+            .map(|l| serde_json::from_str::<serde_json::Value>(l).unwrap()["now_ms"].as_u64().unwrap())
+            .collect();
+        assert_eq!(at, [1_000, 2_000]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn stats_csv_is_written_when_the_bus_closes() {
+        let stats = Stats::new(0);
+        let bus = EventBus::new(stats.clone());
+        let path = temp_path("stats.csv");
+        let csv = spawn_stats_csv(&bus, path.to_string_lossy().into_owned());
+        bus.publish(stats_event(&stats, 1_000));
+        bus.publish(stats_event(&stats, 2_000));
+        tokio::task::yield_now().await;
+        assert!(!path.exists());
+        drop(bus);
+        csv.await.unwrap();
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let rows: Vec<&str> = text.lines().map(|l| l.split(',').next().unwrap()).collect();
+        assert_eq!(rows, ["row", "interval", "interval", "summary"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -1,36 +1,691 @@
-use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-#[derive(Debug, Clone, Deserialize)]
+use anyhow::Context;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::pm::executor::{parse_stranded_leg_policies, ExecutionMode, LegOrdering, StrandedLegPolicies};
+use crate::pm::market_data::{parse_redemption_values, MarketsEndpoint, MissingBookPolicy, TokenCollisionPolicy};
+use crate::pm::prefilter::PriceBasis;
+use crate::strategy::economics::ExitMode;
+use crate::strategy::fees::{parse_tiers, FeeTier};
+use crate::strategy::sum_arb::parse_edge_by_legs;
+use crate::strategy::pricing::PricingKind;
+use crate::profile::Profile;
+use crate::secret::Secret;
+use crate::source::replay::ReplayEof;
+use crate::source::DataSource;
+use crate::types::TimeInForce;
+
+/// Debug/Display/Serialize are safe to log: credential fields are `Secret`s and print masked.
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Settings {
     pub clob_host: String,
+    /// clob (default) | replay: markets and snapshots from the replay_* files, no network
+    #[serde(default)]
+    pub data_source: DataSource,
+    /// Replay market list: a JSON array of markets, as in the market cache
+    pub replay_markets_path: Option<String>,
+    /// Replay snapshots, one per line, served one per fetch
+    pub replay_snapshots_path: Option<String>,
+    /// stop (default) | loop | hold: what the replay does after its last snapshot
+    #[serde(default)]
+    pub replay_at_eof: ReplayEof,
     pub poll_ms: u64,
+    /// Adapt the poll interval to activity within [poll_ms_min, poll_ms_max]
+    #[serde(default)]
+    pub poll_adaptive: bool,
+    #[serde(default = "default_poll_ms_min")]
+    pub poll_ms_min: u64,
+    #[serde(default = "default_poll_ms_max")]
+    pub poll_ms_max: u64,
+    /// Quiet cycles (no near-arb, no opportunity) before the interval starts growing
+    #[serde(default = "default_poll_quiet_cycles")]
+    pub poll_quiet_cycles: u32,
+    /// Fetch the next snapshot while the current one is evaluated
+    #[serde(default)]
+    pub pipeline_enabled: bool,
 
     pub max_markets: usize,
+    /// Rank candidate markets by a weighted score and keep the top max_markets, instead of
+    /// the first max_markets the API returns
+    #[serde(default)]
+    pub market_scoring: bool,
+    /// Candidates fetched for scoring (0: every page up to refresh_page_limit)
+    #[serde(default)]
+    pub market_score_candidates: usize,
+    /// Score weights: ln(1 + Gamma liquidity), ln(1 + 24h volume), 1 / legs, ln(1 + past hits)
+    #[serde(default = "default_score_weight")]
+    pub market_score_w_liquidity: f64,
+    #[serde(default = "default_score_weight")]
+    pub market_score_w_volume: f64,
+    #[serde(default = "default_score_weight")]
+    pub market_score_w_legs: f64,
+    #[serde(default = "default_score_weight")]
+    pub market_score_w_hits: f64,
     pub markets_refresh_sec: u64,
+    /// File the market list is saved to after every refresh; on start a saved list is used
+    /// right away and reconciled in the background (empty disables)
+    pub markets_cache_path: Option<String>,
+    /// Max /markets pages walked per refresh (0 = unlimited)
+    #[serde(default = "default_refresh_page_limit")]
+    pub refresh_page_limit: usize,
 
     pub books_chunk_size: usize,
     pub books_concurrency: usize,
+    /// Max tokens per cycle re-fetched via GET /book when missing from /books
+    #[serde(default = "default_book_fallback_max")]
+    pub book_fallback_max: usize,
+    /// A GET /book fallback still pending after this many ms gets a second racing request (0 disables)
+    #[serde(default)]
+    pub book_hedge_after_ms: u64,
+    /// Max hedged requests per cycle
+    #[serde(default = "default_book_hedge_max_per_cycle")]
+    pub book_hedge_max_per_cycle: usize,
+    /// Look up market state (closed / winner) for markets with a leg at or above
+    /// resolved_suspect_px and drop resolved ones before the next refresh
+    #[serde(default = "default_true")]
+    pub resolved_check_enabled: bool,
+    #[serde(default = "default_resolved_suspect_px")]
+    pub resolved_suspect_px: String,
+    #[serde(default = "default_market_meta_ttl_sec")]
+    pub market_meta_ttl_sec: u64,
+    /// Lookups per cycle at most
+    #[serde(default = "default_resolved_max_checks")]
+    pub resolved_max_checks: usize,
+    /// Markets with a missing token book: drop_market | drop_cycle_use_cached | keep_partial_for_logging
+    #[serde(default)]
+    pub missing_book_policy: MissingBookPolicy,
+    /// Oldest cached top drop_cycle_use_cached fills a missing leg with (ms)
+    #[serde(default = "default_cached_top_max_age_ms")]
+    pub cached_top_max_age_ms: u64,
+    /// Markets listing a token another market lists: drop_later | share
+    #[serde(default)]
+    pub token_collision_policy: TokenCollisionPolicy,
+    /// Venue flags a market needs to be scanned; see `MarketEligibility`
+    #[serde(default = "default_true")]
+    pub require_order_book: bool,
+    #[serde(default = "default_true")]
+    pub require_accepting_orders: bool,
+    #[serde(default = "default_true")]
+    pub skip_closed_markets: bool,
+    /// Listing a market refresh walks: full | simplified | sampling
+    #[serde(default)]
+    pub pm_markets_endpoint: MarketsEndpoint,
+
+    // HTTP timeout and retry/backoff, shared by all sources
+    #[serde(default = "default_http_timeout_ms")]
+    pub http_timeout_ms: u64,
+    #[serde(default = "default_http_max_retries")]
+    pub http_max_retries: u32,
+    #[serde(default = "default_http_backoff_ms")]
+    pub http_backoff_ms: u64,
+    #[serde(default = "default_http_max_backoff_ms")]
+    pub http_max_backoff_ms: u64,
+    /// Hard ceiling on simultaneous HTTP requests across every source (0 = unlimited)
+    #[serde(default)]
+    pub http_global_concurrency: usize,
+    /// Average requests per second to the CLOB host, all subsystems together (0 = unlimited)
+    #[serde(default)]
+    pub clob_requests_per_sec: f64,
+    /// Requests the CLOB budget may send back to back after being idle
+    #[serde(default = "default_requests_burst")]
+    pub clob_burst: u32,
+    /// Same for the Gamma host
+    #[serde(default)]
+    pub gamma_requests_per_sec: f64,
+    #[serde(default = "default_requests_burst")]
+    pub gamma_burst: u32,
+    /// Retry waits are spread by up to this percentage either way (0 disables)
+    #[serde(default = "default_http_backoff_jitter_pct")]
+    pub http_backoff_jitter_pct: u32,
+    /// Poll sleeps are spread by up to this percentage either way (0 disables)
+    #[serde(default)]
+    pub poll_jitter_pct: u32,
+    /// Fixed seed for backoff/poll jitter, for reproducible runs; unset seeds from the OS
+    #[serde(default)]
+    pub jitter_seed: Option<String>,
 
     pub fee_bps: i64,
+    /// Volume tiers "volume_usd:bps,..." on rolling 30-day notional; fee_bps applies below the first
+    pub fee_tiers: Option<String>,
+    /// 30-day notional (USD) already traded before start, counted toward the tiers
+    pub fee_seed_volume_usd: Option<String>,
     pub min_edge_bps: i64,
+    /// Per-leg-count min edge as legs:bps pairs, e.g. "2:30,3:60"; other counts use min_edge_bps
+    pub min_edge_bps_by_legs: Option<String>,
+    /// What a complete set pays per market, as market_id:value pairs, where it is not $1
+    /// (YES) or n - 1 (NO basket)
+    pub redemption_values: Option<String>,
+    /// Added to min_edge_bps for the execute decision (expected fill slippage); monitoring uses raw edge
+    #[serde(default)]
+    pub slippage_buffer_bps: i64,
     pub warn_edge_bps: i64,
-    pub max_bundle_size: String,
+    /// Shares per bundle
+    pub max_bundle_shares: Option<String>,
+    /// Deprecated alias of max_bundle_shares, used only when that is unset
+    pub max_bundle_size: Option<String>,
+    /// Decimal places the venue accepts on order sizes; bundle sizes are rounded down to it
+    #[serde(default = "default_size_decimals")]
+    pub size_decimals: u32,
+    /// Cost cap per bundle in USD (size * sum_ask)
+    pub max_bundle_notional_usd: Option<String>,
+    /// Cost cap across all bundles emitted in one cycle, in USD
+    pub max_cycle_notional_usd: Option<String>,
+    /// Fixed cost per bundle in USD (gas / relayer fee for redemption)
+    pub bundle_fixed_cost_usd: Option<String>,
+    /// redeem (hold to resolution, no exit fee) | sell_back (taker fee on the payout too)
+    #[serde(default)]
+    pub bundle_exit_mode: ExitMode,
+    /// top_of_book (best ask of every leg) | depth_walk (walk book_depth_levels ask levels)
+    #[serde(default)]
+    pub pricing_model: PricingKind,
+    /// Ask levels kept per book for depth_walk pricing
+    #[serde(default = "default_book_depth_levels")]
+    pub book_depth_levels: usize,
+
+    // Shadow mode: also run a candidate strategy (the primary with these overrides) on every
+    // snapshot and log the markets where the two disagree; the shadow never executes
+    #[serde(default)]
+    pub shadow_mode: bool,
+    pub shadow_min_edge_bps: Option<i64>,
+    pub shadow_pricing_model: Option<PricingKind>,
+
+    // Thin-snapshot guard: skip strategy/execution when the snapshot has fewer markets
+    // than min_snapshot_markets, or less than min_snapshot_ratio of the last good one (0 disables)
+    #[serde(default)]
+    pub min_snapshot_markets: usize,
+    #[serde(default)]
+    pub min_snapshot_ratio: f64,
+    /// Skip strategy when the venue returned books for less than this share of the
+    /// requested tokens (0 disables)
+    #[serde(default)]
+    pub min_snapshot_coverage: f64,
+
+    /// Sum light-weight prices first and fetch full books only for markets near arb
+    #[serde(default)]
+    pub prefilter_enabled: bool,
+    /// Price summed by the prefilter: ask (conservative) | mid (wider net)
+    #[serde(default)]
+    pub prefilter_basis: PriceBasis,
+    /// Promote a market when its summed price is below payoff * (1 + margin)
+    #[serde(default = "default_prefilter_margin_bps")]
+    pub prefilter_margin_bps: i64,
+
+    /// Tag markets with their Gamma event (event_id/event_title); off keeps Gamma out of the loop
+    #[serde(default)]
+    pub gamma_enabled: bool,
+    #[serde(default = "default_gamma_host")]
+    pub gamma_host: String,
+    #[serde(default = "default_gamma_cache_ttl_sec")]
+    pub gamma_cache_ttl_sec: u64,
+    #[serde(default = "default_gamma_max_pages")]
+    pub gamma_max_pages: usize,
+    /// Comma-separated Gamma event ids whose markets are never scanned (needs gamma_enabled)
+    pub exclude_event_ids: Option<String>,
+    /// Comma-separated market categories to scan (case-insensitive); empty scans all.
+    /// Markets without a category are kept
+    pub include_categories: Option<String>,
+    /// Comma-separated market categories never scanned; "uncategorized" drops markets without one
+    pub exclude_categories: Option<String>,
+
+    /// Allowed outcome label sets: labels joined by '|', sets by ';' (e.g. "Yes|No").
+    /// Markets whose labels match none are skipped at refresh; unset allows all
+    pub outcome_label_allowlist: Option<String>,
+    /// Skip markets with more than two outcomes at refresh
+    #[serde(default)]
+    pub binary_only: bool,
+
+    /// Also scan NO baskets of neg-risk events (a set of n NO tokens pays n - 1)
+    #[serde(default)]
+    pub no_side_arb: bool,
+    /// Comma-separated market ids whose legs, sums and filter decision are logged every cycle
+    pub trace_market_ids: Option<String>,
+    /// Log one line per leg (ask, size, share of sum_ask) after each opportunity
+    #[serde(default)]
+    pub log_leg_detail: bool,
+    /// Print each cycle's opportunities as an aligned table on stdout, besides the logs
+    #[serde(default)]
+    pub human_output: bool,
+
+    /// Quote markets on a thread pool when a snapshot has at least this many (0 = always serial)
+    #[serde(default = "default_strategy_parallel_min_markets")]
+    pub strategy_parallel_min_markets: usize,
+    /// Worker threads for parallel quoting (0 = one per core)
+    #[serde(default)]
+    pub strategy_parallelism: usize,
+
+    /// Time in force for bundle legs: FOK (default) | IOC | GTC
+    #[serde(default)]
+    pub leg_tif: TimeInForce,
+    /// Post-only bundle legs; only honored with LEG_TIF=GTC
+    #[serde(default)]
+    pub leg_post_only: bool,
+
+    /// Volatility-scaled execute threshold: min_edge + k * vol_bps (unset keeps it static)
+    pub vol_edge_k: Option<String>,
+    #[serde(default = "default_vol_lookback_ms")]
+    pub vol_lookback_ms: u64,
+    #[serde(default = "default_vol_min_samples")]
+    pub vol_min_samples: usize,
+
+    /// Per-market sum_ask EMA weight of the newest cycle (unset disables glitch detection)
+    pub sum_ask_ema_alpha: Option<String>,
+    #[serde(default = "default_sum_ask_ema_band_bps")]
+    pub sum_ask_ema_band_bps: i64,
+    /// Skip opportunities outside the band instead of only logging them
+    #[serde(default)]
+    pub sum_ask_ema_guard: bool,
+    /// Opportunities with sum_ask per $ of payout below this are logged as implausible and
+    /// skipped (unset disables)
+    pub min_plausible_sum_ask: Option<String>,
+    /// Execute implausible opportunities anyway
+    #[serde(default)]
+    pub trust_extreme_arbs: bool,
+
+    /// Observations of top of book kept per token for strategies (0 disables the history)
+    #[serde(default = "default_history_depth")]
+    pub history_depth: usize,
+    /// Tokens absent from this many consecutive snapshots are dropped from the history
+    #[serde(default = "default_history_evict_cycles")]
+    pub history_evict_cycles: u64,
+
+    /// Cap on bundles emitted per snapshot, highest expected profit first (0 = unlimited)
+    #[serde(default)]
+    pub max_bundles_per_snapshot: usize,
 
-    // Optional filters
+    // Executor selection: observer (log only) | mock (simulated venue)
+    #[serde(default)]
+    pub execution_mode: ExecutionMode,
+    // Mock executor: per-leg delay, per-leg timeout, and a comma-separated outcome script
+    // (fill | thin | reject | hang), cycled; empty fills everything
+    #[serde(default)]
+    pub mock_leg_delay_ms: u64,
+    #[serde(default = "default_exec_leg_timeout_ms")]
+    pub exec_leg_timeout_ms: u64,
+    pub mock_leg_script: Option<String>,
+    /// Mock fills this many bps above the intent price, to exercise realized vs theoretical edge
+    #[serde(default)]
+    pub mock_fill_slippage_bps: i64,
+    /// simultaneous | scarcest_first (one leg at a time, smallest available size first)
+    #[serde(default)]
+    pub leg_ordering: LegOrdering,
+    /// scarcest_first: how long each leg may take before the rest of the bundle is aborted
+    #[serde(default = "default_inter_leg_timeout_ms")]
+    pub inter_leg_timeout_ms: u64,
+    /// scarcest_first: share of a leg's size that must fill before the next leg is sent (default 1)
+    pub leg_min_fill_ratio: Option<String>,
+    /// Filled legs of a bundle that did not complete: hold | flatten | chase, or per profile
+    /// as "profile:policy" entries (e.g. "flatten,paper:chase")
+    pub stranded_leg_policy: Option<String>,
+    /// chase: how long the missing legs are retried before the filled ones are flattened
+    #[serde(default = "default_complete_or_flatten_ms")]
+    pub complete_or_flatten_ms: u64,
+
+    // Executor: drop bundles repeating a (market, token, side, price) leg within this window (0 disables)
+    #[serde(default)]
+    pub exec_dedup_window_ms: u64,
+
+    // Executor: min time between intents on the same token, across strategies (0 disables)
+    #[serde(default)]
+    pub min_intent_interval_per_token_ms: u64,
+
+    // Executor: upper bound on waiting for cancel confirmations at shutdown
+    #[serde(default = "default_shutdown_timeout_ms")]
+    pub shutdown_timeout_ms: u64,
+
+    // Executor: persisted ids of executed bundles, skipped for bundle_dedupe_ttl_sec across
+    // restarts (unset path disables)
+    pub bundle_ledger_path: Option<String>,
+    #[serde(default = "default_bundle_dedupe_ttl_sec")]
+    pub bundle_dedupe_ttl_sec: u64,
+
+    // Outcome-count bounds, applied at refresh and in the strategy (max_legs 0 = unlimited)
+    #[serde(default = "default_min_legs")]
+    pub min_legs: usize,
+    #[serde(default)]
+    pub max_legs: usize,
+
+    // Optional filters. The spread limits are independent: with both set a leg must pass
+    // both, with one set only that one applies
+    /// Max per-leg spread (best_ask - best_bid) in price units
     pub max_leg_spread: Option<String>,
+    /// Max per-leg spread as a percentage of the leg's mid ((ask + bid) / 2)
+    pub max_leg_spread_pct: Option<String>,
     pub min_leg_size: Option<String>,
+    /// Skip markets with a leg whose venue-side book timestamp is older than this (0 disables)
+    #[serde(default)]
+    pub max_book_update_age_sec: u64,
+    /// Count legs without a venue timestamp as stale for that check
+    #[serde(default)]
+    pub unknown_book_age_stale: bool,
+    /// Reference prices (JSON object token id -> price) polled every reference_poll_sec;
+    /// unset disables the reference check
+    pub reference_feed_url: Option<String>,
+    #[serde(default = "default_reference_poll_sec")]
+    pub reference_poll_sec: u64,
+    /// Reference prices older than this are ignored
+    #[serde(default = "default_reference_max_age_sec")]
+    pub reference_max_age_sec: u64,
+    /// Skip markets with a leg mid further than this from its reference price (0 disables)
+    #[serde(default = "default_max_reference_deviation_bps")]
+    pub max_reference_deviation_bps: u64,
+
+    // Collateral cap: bundle cost (incl. fee) never exceeds available - reserve
+    pub available_collateral_usd: Option<String>,
+    pub collateral_reserve_usd: Option<String>,
+
+    // Inspection API (needs --features api); 0 disables
+    #[serde(default)]
+    pub api_port: u16,
+
+    // Event stream (book_update / market_added / market_removed / opportunity JSON lines):
+    // host:port or unix:/path; unset disables
+    #[serde(default)]
+    pub events_bind_addr: Option<String>,
 
     // Stats
     pub stats_log_sec: u64,
     pub stats_jsonl_path: Option<String>,
+    /// Stats intervals plus a summary row as CSV, written at shutdown and on SIGUSR1
+    pub stats_csv_path: Option<String>,
+    /// Closed opportunity episodes (start, end, max edge, min sum_ask) as JSON lines
+    pub episodes_jsonl_path: Option<String>,
+    /// Execution results (status, fills, flatten loss) as JSON lines
+    pub executions_jsonl_path: Option<String>,
+    /// Emit the cycle summary line every cycle (otherwise only on near-arbs/opportunities)
+    #[serde(default)]
+    pub cycle_summary_log: bool,
+    /// Markets tracked for lifetime opportunity/near-arb counts (least recently hit evicted)
+    #[serde(default = "default_hot_markets_capacity")]
+    pub hot_markets_capacity: usize,
+    /// Markets in the "hot markets" line logged with stats and in GET /hotmarkets (0 disables the log)
+    #[serde(default = "default_hot_markets_top_n")]
+    pub hot_markets_top_n: usize,
+    /// Directory for per-bundle forensic dumps (raw books + computed opportunity); unset disables
+    pub forensics_path: Option<String>,
+
+    // Credentials (live mode)
+    pub polymarket_private_key: Option<Secret>,
+
+    // Safety profile: observe | paper | live; live also needs live_armed and credentials
+    #[serde(default)]
+    pub profile: Profile,
+    #[serde(default)]
+    pub live_armed: bool,
+    /// Fail at startup instead of downgrading a profile/executor request that is not allowed
+    #[serde(default)]
+    pub strict_profile: bool,
+}
+
+fn default_poll_ms_min() -> u64 { 250 }
+fn default_poll_ms_max() -> u64 { 5_000 }
+fn default_poll_quiet_cycles() -> u32 { 10 }
+fn default_prefilter_margin_bps() -> i64 { 200 }
+fn default_bundle_dedupe_ttl_sec() -> u64 { 3_600 }
+fn default_hot_markets_capacity() -> usize { 1_000 }
+fn default_hot_markets_top_n() -> usize { 10 }
+fn default_vol_lookback_ms() -> u64 { 60_000 }
+fn default_vol_min_samples() -> usize { 5 }
+fn default_sum_ask_ema_band_bps() -> i64 { 300 }
+fn default_requests_burst() -> u32 { 10 }
+fn default_true() -> bool { true }
+fn default_resolved_suspect_px() -> String { "0.99".to_string() }
+fn default_market_meta_ttl_sec() -> u64 { 300 }
+fn default_resolved_max_checks() -> usize { 20 }
+fn default_history_depth() -> usize { 120 }
+fn default_book_depth_levels() -> usize { 5 }
+fn default_history_evict_cycles() -> u64 { 30 }
+fn default_strategy_parallel_min_markets() -> usize { 512 }
+fn default_gamma_host() -> String { "https://gamma-api.polymarket.com".to_string() }
+fn default_gamma_cache_ttl_sec() -> u64 { 600 }
+fn default_gamma_max_pages() -> usize { 50 }
+fn default_shutdown_timeout_ms() -> u64 { 5_000 }
+fn default_exec_leg_timeout_ms() -> u64 { 2_000 }
+fn default_inter_leg_timeout_ms() -> u64 { 500 }
+fn default_complete_or_flatten_ms() -> u64 { 1_000 }
+fn default_size_decimals() -> u32 { 2 }
+fn default_score_weight() -> f64 { 1.0 }
+fn default_reference_poll_sec() -> u64 { 5 }
+fn default_reference_max_age_sec() -> u64 { 30 }
+fn default_max_reference_deviation_bps() -> u64 { 500 }
+fn default_min_legs() -> usize { 2 }
+fn default_refresh_page_limit() -> usize { 200 }
+fn default_book_fallback_max() -> usize { 50 }
+fn default_cached_top_max_age_ms() -> u64 { 5_000 }
+fn default_book_hedge_max_per_cycle() -> usize { 10 }
+fn default_http_timeout_ms() -> u64 { 10_000 }
+fn default_http_max_retries() -> u32 { 2 }
+fn default_http_backoff_ms() -> u64 { 250 }
+fn default_http_max_backoff_ms() -> u64 { 4_000 }
+fn default_http_backoff_jitter_pct() -> u32 { 20 }
+
+/// Where a setting's effective value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ValueSource {
+    Default,
+    /// Process environment (wins over .env)
+    Env,
+    /// The .env file
+    File,
+}
+
+/// The settings kept as strings in `Settings`, parsed and checked up front so a bad value
+/// fails at startup with its variable name instead of midway through setup.
+#[derive(Debug, Clone)]
+pub struct ParsedSettings {
+    pub max_bundle_shares: Decimal,
+    /// max_bundle_shares came from the deprecated MAX_BUNDLE_SIZE
+    pub bundle_shares_deprecated: bool,
+    pub max_bundle_notional: Option<Decimal>,
+    pub max_cycle_notional: Option<Decimal>,
+    pub bundle_fixed_cost: Decimal,
+    pub fee_tiers: Vec<FeeTier>,
+    pub fee_seed_volume: Decimal,
+    pub min_edge_bps_by_legs: HashMap<usize, i64>,
+    pub redemption_values: HashMap<String, Decimal>,
+    pub max_leg_spread: Option<Decimal>,
+    pub max_leg_spread_pct: Option<Decimal>,
+    pub min_leg_size: Option<Decimal>,
+    pub available_collateral: Option<Decimal>,
+    pub collateral_reserve: Decimal,
+    pub vol_edge_k: Option<Decimal>,
+    pub sum_ask_ema_alpha: Option<Decimal>,
+    pub min_plausible_sum_ask: Option<Decimal>,
+    pub resolved_suspect_px: Decimal,
+    pub leg_min_fill_ratio: Decimal,
+    pub stranded_leg_policies: StrandedLegPolicies,
+    pub jitter_seed: Option<u64>,
+}
+
+/// `value` of variable `name` as a decimal; unset or blank is None.
+fn opt_decimal(name: &str, value: &Option<String>) -> anyhow::Result<Option<Decimal>> {
+    match value.as_deref().map(str::trim).filter(|v| !v.is_empty()) {
+        Some(v) => v.parse::<Decimal>()
+            .map(Some)
+            .with_context(|| format!("{}={:?} is not a decimal", name, v)),
+        None => Ok(None),
+    }
 }
 
 impl Settings {
+    /// Whether the primary or the shadow strategy needs ask levels beyond the top.
+    pub fn uses_depth_walk(&self) -> bool {
+        self.pricing_model == PricingKind::DepthWalk || (self.shadow_mode && self.shadow_pricing_model == Some(PricingKind::DepthWalk))
+    }
+
+    /// Parses every string-typed numeric setting; the error names the offending variable
+    /// and value.
+    pub fn parsed(&self) -> anyhow::Result<ParsedSettings> {
+        let (max_bundle_shares, bundle_shares_deprecated) = match opt_decimal("MAX_BUNDLE_SHARES", &self.max_bundle_shares)? {
+            Some(v) => (v, false),
+            None => match opt_decimal("MAX_BUNDLE_SIZE", &self.max_bundle_size)? {
+                Some(v) => (v, true),
+                None => anyhow::bail!("MAX_BUNDLE_SHARES is required"),
+            },
+        };
+        Ok(ParsedSettings {
+            max_bundle_shares,
+            bundle_shares_deprecated,
+            max_bundle_notional: opt_decimal("MAX_BUNDLE_NOTIONAL_USD", &self.max_bundle_notional_usd)?,
+            max_cycle_notional: opt_decimal("MAX_CYCLE_NOTIONAL_USD", &self.max_cycle_notional_usd)?,
+            bundle_fixed_cost: opt_decimal("BUNDLE_FIXED_COST_USD", &self.bundle_fixed_cost_usd)?.unwrap_or(Decimal::ZERO),
+            fee_tiers: parse_tiers(self.fee_tiers.as_deref().unwrap_or("")).context("FEE_TIERS")?,
+            fee_seed_volume: opt_decimal("FEE_SEED_VOLUME_USD", &self.fee_seed_volume_usd)?.unwrap_or(Decimal::ZERO),
+            min_edge_bps_by_legs: parse_edge_by_legs(self.min_edge_bps_by_legs.as_deref().unwrap_or("")).context("MIN_EDGE_BPS_BY_LEGS")?,
+            redemption_values: parse_redemption_values(self.redemption_values.as_deref().unwrap_or("")).context("REDEMPTION_VALUES")?,
+            max_leg_spread: opt_decimal("MAX_LEG_SPREAD", &self.max_leg_spread)?,
+            max_leg_spread_pct: opt_decimal("MAX_LEG_SPREAD_PCT", &self.max_leg_spread_pct)?,
+            min_leg_size: opt_decimal("MIN_LEG_SIZE", &self.min_leg_size)?,
+            available_collateral: opt_decimal("AVAILABLE_COLLATERAL_USD", &self.available_collateral_usd)?,
+            collateral_reserve: opt_decimal("COLLATERAL_RESERVE_USD", &self.collateral_reserve_usd)?.unwrap_or(Decimal::ZERO),
+            vol_edge_k: opt_decimal("VOL_EDGE_K", &self.vol_edge_k)?,
+            sum_ask_ema_alpha: opt_decimal("SUM_ASK_EMA_ALPHA", &self.sum_ask_ema_alpha)?,
+            min_plausible_sum_ask: opt_decimal("MIN_PLAUSIBLE_SUM_ASK", &self.min_plausible_sum_ask)?,
+            resolved_suspect_px: opt_decimal("RESOLVED_SUSPECT_PX", &Some(self.resolved_suspect_px.clone()))?
+                .context("RESOLVED_SUSPECT_PX is required")?,
+            leg_min_fill_ratio: match opt_decimal("LEG_MIN_FILL_RATIO", &self.leg_min_fill_ratio)? {
+                Some(v) if v <= Decimal::ZERO || v > Decimal::ONE => {
+                    anyhow::bail!("LEG_MIN_FILL_RATIO={} must be in (0, 1]", v)
+                }
+                v => v.unwrap_or(Decimal::ONE),
+            },
+            stranded_leg_policies: parse_stranded_leg_policies(self.stranded_leg_policy.as_deref().unwrap_or(""))
+                .context("STRANDED_LEG_POLICY")?,
+            jitter_seed: match self.jitter_seed.as_deref().map(str::trim).filter(|x| !x.is_empty()) {
+                Some(seed) => Some(seed.parse().with_context(|| format!("JITTER_SEED={:?} must be an unsigned integer", seed))?),
+                None => None,
+            },
+        })
+    }
+
     pub fn from_env() -> anyhow::Result<Self> {
+        Ok(Self::load()?.0)
+    }
+
+    /// Like `from_env`, also reporting the source of every field. Variables already in the
+    /// process environment count as `Env`, ones only the .env file set as `File`.
+    pub fn load() -> anyhow::Result<(Self, BTreeMap<String, ValueSource>)> {
+        let preset: HashSet<String> = std::env::vars_os()
+            .filter_map(|(k, _)| k.into_string().ok())
+            .map(|k| k.to_lowercase())
+            .collect();
         dotenvy::dotenv().ok();
         let c = config::Config::builder()
             .add_source(config::Environment::default())
             .build()?;
-        Ok(c.try_deserialize()?)
+        let s: Self = c.try_deserialize()?;
+
+        let set: HashSet<String> = std::env::vars_os()
+            .filter_map(|(k, _)| k.into_string().ok())
+            .map(|k| k.to_lowercase())
+            .collect();
+        let fields = match serde_json::to_value(&s)? {
+            serde_json::Value::Object(m) => m.into_iter().map(|(k, _)| k).collect(),
+            _ => vec![],
+        };
+        let sources = fields.into_iter()
+            .map(|k| {
+                let src = if preset.contains(&k) {
+                    ValueSource::Env
+                } else if set.contains(&k) {
+                    ValueSource::File
+                } else {
+                    ValueSource::Default
+                };
+                (k, src)
+            })
+            .collect();
+        Ok((s, sources))
+    }
+
+    /// Every field as `{"value": .., "source": ..}`, secrets masked.
+    pub fn effective(&self, sources: &BTreeMap<String, ValueSource>) -> serde_json::Value {
+        let serde_json::Value::Object(fields) = serde_json::to_value(self).unwrap_or_default() else {
+            return serde_json::Value::Null;
+        };
+        fields.into_iter()
+            .map(|(k, value)| {
+                let source = sources.get(&k).copied().unwrap_or(ValueSource::Default);
+                let entry = serde_json::json!({ "value": value, "source": source });
+                (k, entry)
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+impl std::fmt::Display for Settings {
+    /// Effective configuration as a single JSON object, secrets masked.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let v = serde_json::to_string(self).map_err(|_| std::fmt::Error)?;
+        f.write_str(&v)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::settings::settings;
+
+    #[test]
+    fn max_bundle_size_is_a_deprecated_alias() {
+        let old = settings(serde_json::json!({ "max_bundle_shares": null, "max_bundle_size": "40" })).parsed().unwrap();
+        assert_eq!((old.max_bundle_shares, old.bundle_shares_deprecated), (Decimal::from(40), true));
+        let both = settings(serde_json::json!({ "max_bundle_shares": "25", "max_bundle_size": "40" })).parsed().unwrap();
+        assert_eq!((both.max_bundle_shares, both.bundle_shares_deprecated), (Decimal::from(25), false));
+        let neither = settings(serde_json::json!({ "max_bundle_shares": null })).parsed().unwrap_err();
+        assert!(neither.to_string().contains("MAX_BUNDLE_SHARES"));
+    }
+
+    #[test]
+    fn each_malformed_decimal_is_named() {
+        for (field, var) in [
+            ("max_bundle_shares", "MAX_BUNDLE_SHARES"),
+            ("max_bundle_notional_usd", "MAX_BUNDLE_NOTIONAL_USD"),
+            ("max_cycle_notional_usd", "MAX_CYCLE_NOTIONAL_USD"),
+            ("bundle_fixed_cost_usd", "BUNDLE_FIXED_COST_USD"),
+            ("fee_seed_volume_usd", "FEE_SEED_VOLUME_USD"),
+            ("max_leg_spread", "MAX_LEG_SPREAD"),
+            ("max_leg_spread_pct", "MAX_LEG_SPREAD_PCT"),
+            ("min_leg_size", "MIN_LEG_SIZE"),
+            ("available_collateral_usd", "AVAILABLE_COLLATERAL_USD"),
+            ("collateral_reserve_usd", "COLLATERAL_RESERVE_USD"),
+            ("vol_edge_k", "VOL_EDGE_K"),
+            ("sum_ask_ema_alpha", "SUM_ASK_EMA_ALPHA"),
+            ("min_plausible_sum_ask", "MIN_PLAUSIBLE_SUM_ASK"),
+            ("resolved_suspect_px", "RESOLVED_SUSPECT_PX"),
+            ("leg_min_fill_ratio", "LEG_MIN_FILL_RATIO"),
+        ] {
+            let err = settings(serde_json::json!({ field: "0.1x" })).parsed().unwrap_err().to_string();
+            assert_eq!(err, format!("{}=\"0.1x\" is not a decimal", var));
+        }
+        // the deprecated alias is only parsed when MAX_BUNDLE_SHARES is unset
+        let err = settings(serde_json::json!({ "max_bundle_shares": null, "max_bundle_size": "ten" })).parsed().unwrap_err();
+        assert_eq!(err.to_string(), "MAX_BUNDLE_SIZE=\"ten\" is not a decimal");
+    }
+
+    #[test]
+    fn blank_decimals_are_unset() {
+        let p = settings(serde_json::json!({ "max_leg_spread": "  ", "min_leg_size": "" })).parsed().unwrap();
+        assert_eq!((p.max_leg_spread, p.min_leg_size), (None, None));
+        let p = settings(serde_json::json!({ "max_leg_spread": " 0.02 " })).parsed().unwrap();
+        assert_eq!(p.max_leg_spread, Some(Decimal::new(2, 2)));
+    }
+
+    const KEY: &str = "0x4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318";
+
+    #[test]
+    fn formatting_settings_never_shows_the_private_key() {
+        let s = settings(serde_json::json!({ "polymarket_private_key": KEY }));
+        assert_eq!(s.polymarket_private_key.as_ref().map(|k| k.expose()), Some(KEY));
+
+        let effective = s.effective(&BTreeMap::new()).to_string();
+        for out in [format!("{:?}", s), format!("{:#?}", s), s.to_string(), serde_json::to_string(&s).unwrap(), effective] {
+            assert!(!out.contains(KEY), "raw key in {}", out);
+            assert!(!out.contains(&KEY[..20]), "key prefix in {}", out);
+            assert!(out.contains("***2318"), "no masked key in {}", out);
+        }
     }
 }
@@ -0,0 +1,137 @@
+use anyhow::Result;
+use rust_decimal::Decimal;
+
+use crate::pm::market_data::{MarketData, MarketDef};
+use crate::strategy::sum_arb::{BundleQuote, SumArbStrategy};
+use crate::types::{MarketBook, OutcomeTop};
+
+/// One market's line in the `--validate-markets` report.
+#[derive(Debug, Clone)]
+pub struct MarketReport {
+    pub market_id: String,
+    pub question: String,
+    pub outcomes: usize,
+    pub books_found: usize,
+    pub quote: Option<BundleQuote>,
+    /// `opportunity`, `near_arb`, `missing_books`, or the strategy skip reason
+    pub verdict: &'static str,
+}
+
+/// Fetches books for `markets` once and runs each through the strategy filters
+/// without emitting intents or touching stats.
+pub async fn validate_markets(
+    md: &MarketData,
+    strat: &SumArbStrategy,
+    markets: &[MarketDef],
+) -> Result<Vec<MarketReport>> {
+    let tops = md.fetch_tops(markets).await?;
+
+    let mut out = Vec::with_capacity(markets.len());
+    for m in markets {
+        let found: Vec<OutcomeTop> = m.token_ids.iter().filter_map(|t| tops.get(t).cloned()).collect();
+        let books_found = found.len();
+
+        let (quote, verdict) = if books_found < m.token_ids.len() {
+            (None, "missing_books")
+        } else {
+            let book = MarketBook {
+                market_id: m.market_id.clone(),
+                question: m.question.clone(),
+                polarity: m.polarity,
+                event_id: m.event_id.clone(),
+                category: m.category.clone(),
+                min_order_size: m.min_order_size,
+                redemption_value: m.redemption_value,
+                shared_by: m.shared_by,
+                outcomes: found,
+                partial: false,
+            };
+            match strat.quote(&book) {
+                Err(r) => (None, r.as_str()),
+                Ok(q) if strat.is_executable(&q) => (Some(q), "opportunity"),
+                Ok(q) if strat.is_near_arb(&q) => (Some(q), "near_arb"),
+                Ok(q) => (Some(q), "below_edge"),
+            }
+        };
+
+        out.push(MarketReport {
+            market_id: m.market_id.clone(),
+            question: m.question.clone(),
+            outcomes: m.token_ids.len(),
+            books_found,
+            quote,
+            verdict,
+        });
+    }
+    Ok(out)
+}
+
+pub fn print_report(reports: &[MarketReport]) {
+    let fmt = |d: Option<Decimal>| d.map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+    println!("market_id\toutcomes\tbooks\tsum_ask\tsum_bid\tverdict\tquestion");
+    for r in reports {
+        println!(
+            "{}\t{}\t{}/{}\t{}\t{}\t{}\t{}",
+            r.market_id,
+            r.outcomes,
+            r.books_found,
+            r.outcomes,
+            fmt(r.quote.as_ref().map(|q| q.sum_ask)),
+            fmt(r.quote.as_ref().map(|q| q.sum_bid)),
+            r.verdict,
+            r.question,
+        );
+    }
+
+    let mut counts: std::collections::BTreeMap<&str, usize> = std::collections::BTreeMap::new();
+    for r in reports {
+        *counts.entry(r.verdict).or_default() += 1;
+    }
+    let summary: Vec<String> = counts.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    println!("# {} markets: {}", reports.len(), summary.join(" "));
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::stats::Stats;
+    use crate::testutil::markets::{book_json, http_client, market_def};
+    use crate::testutil::strategy::sum_arb;
+
+    #[tokio::test]
+    async fn validate_markets_reports_a_verdict_per_market() {
+        let server = MockServer::start().await;
+        let books = serde_json::json!([
+            book_json("1", &[("0.44", "50")], &[("0.45", "50")]),
+            book_json("2", &[("0.46", "50")], &[("0.47", "50")]),
+            book_json("3", &[("0.49", "50")], &[("0.499", "50")]),
+            book_json("4", &[("0.49", "50")], &[("0.4995", "50")]),
+            book_json("5", &[("0.52", "50")], &[("0.53", "50")]),
+            book_json("6", &[("0.50", "50")], &[("0.51", "50")]),
+            book_json("7", &[("0.40", "50")], &[("0.41", "50")]),
+        ]);
+        Mock::given(method("POST"))
+            .and(path("/books"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(books))
+            .mount(&server)
+            .await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, stats.clone());
+        let markets = [
+            market_def("arb", &["1", "2"]),
+            market_def("near", &["3", "4"]),
+            market_def("fair", &["5", "6"]),
+            market_def("half", &["7", "8"]),
+        ];
+
+        let reports = validate_markets(&md, &sum_arb(), &markets).await.unwrap();
+        let verdicts: Vec<(&str, &str, usize)> = reports.iter().map(|r| (r.market_id.as_str(), r.verdict, r.books_found)).collect();
+        assert_eq!(verdicts, [("arb", "opportunity", 2), ("near", "near_arb", 2), ("fair", "below_edge", 2), ("half", "missing_books", 1)]);
+        assert_eq!(reports[0].quote.as_ref().map(|q| q.sum_ask), Some(Decimal::new(92, 2)));
+        // a diagnostic run leaves the funnel counters alone
+        assert_eq!(stats.snapshot(0).opportunities, 0);
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::strategy::MarketHit;
+
+/// One stretch of consecutive evaluated snapshots in which a market cleared the execute
+/// threshold. Times are snapshot fetch times, so durations are only as fine as the poll
+/// interval.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Episode {
+    pub market_id: String,
+    /// First snapshot the market was executable in
+    pub start_ts_ms: i64,
+    /// First snapshot it no longer was (below threshold, skipped or gone from the scan set)
+    pub end_ts_ms: i64,
+    /// From the first to the last snapshot it was executable in: a lower bound on how long
+    /// it lasted (0 when seen once), where end_ts_ms - start_ts_ms is the upper one
+    pub duration_ms: i64,
+    /// Snapshots the episode spanned
+    pub cycles: u64,
+    pub max_edge_bps: Decimal,
+    pub min_sum_ask: Decimal,
+}
+
+struct Open {
+    start_ts_ms: i64,
+    last_ts_ms: i64,
+    cycles: u64,
+    max_edge_bps: Decimal,
+    min_sum_ask: Decimal,
+}
+
+/// How long opportunities survive, per market.
+///
+/// Fed every evaluated snapshot: a market not executable in a snapshot closes its
+/// episode, so markets that leave the scan set close on the next cycle and nothing is
+/// kept for them.
+#[derive(Default)]
+pub struct OpportunityEpisodes {
+    open: HashMap<String, Open>,
+}
+
+impl OpportunityEpisodes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Markets currently inside an episode.
+    pub fn open(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Adds the hits of the snapshot taken at `ts_ms`; returns the episodes it closed.
+    pub fn observe(&mut self, ts_ms: i64, hits: &[MarketHit]) -> Vec<Episode> {
+        let executable: HashMap<&str, &MarketHit> = hits.iter()
+            .filter(|h| h.opportunity)
+            .map(|h| (h.market_id.as_str(), h))
+            .collect();
+        let mut closed = vec![];
+        self.open.retain(|market_id, o| {
+            if executable.contains_key(market_id.as_str()) {
+                return true;
+            }
+            closed.push(Episode {
+                market_id: market_id.clone(),
+                start_ts_ms: o.start_ts_ms,
+                end_ts_ms: ts_ms,
+                duration_ms: (o.last_ts_ms - o.start_ts_ms).max(0),
+                cycles: o.cycles,
+                max_edge_bps: o.max_edge_bps,
+                min_sum_ask: o.min_sum_ask,
+            });
+            false
+        });
+        for (market_id, h) in executable {
+            let o = self.open.entry(market_id.to_string()).or_insert_with(|| Open {
+                start_ts_ms: ts_ms,
+                last_ts_ms: ts_ms,
+                cycles: 0,
+                max_edge_bps: h.edge_bps,
+                min_sum_ask: h.sum_ask,
+            });
+            o.last_ts_ms = ts_ms;
+            o.cycles += 1;
+            o.max_edge_bps = o.max_edge_bps.max(h.edge_bps);
+            o.min_sum_ask = o.min_sum_ask.min(h.sum_ask);
+        }
+        closed.sort_by(|a, b| a.market_id.cmp(&b.market_id));
+        closed
+    }
+}
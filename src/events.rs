@@ -0,0 +1,221 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast;
+use uuid::Uuid;
+
+use crate::types::{GlobalSnapshot, LegGauge, OrderIntent, OutcomeTop, TokenId};
+
+/// Lines buffered per consumer; one that falls further behind is disconnected.
+const CONSUMER_BUFFER: usize = 4096;
+/// A single line write taking longer than this disconnects the consumer.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// One line of the event stream.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// Top of book changed for a token since the previous snapshot
+    BookUpdate {
+        ts_ms: i64,
+        market_id: String,
+        token_id: TokenId,
+        best_bid_px: Option<Decimal>,
+        best_bid_sz: Option<Decimal>,
+        best_ask_px: Option<Decimal>,
+        best_ask_sz: Option<Decimal>,
+    },
+    MarketAdded {
+        ts_ms: i64,
+        market_id: String,
+        question: String,
+    },
+    MarketRemoved {
+        ts_ms: i64,
+        market_id: String,
+    },
+    /// A bundle the strategy emitted this cycle
+    Opportunity {
+        ts_ms: i64,
+        bundle_id: Uuid,
+        market_id: String,
+        sum_ask: Decimal,
+        /// Net edge at detection
+        edge_bps: Decimal,
+        size: Decimal,
+        legs: usize,
+        /// Mid and spread per leg, in leg order
+        gauges: Vec<LegGauge>,
+    },
+}
+
+/// Remembers the previous snapshot and turns the next one into events.
+///
+/// Markets are compared by presence in the snapshot, so with the prefilter on a market
+/// that drops out of the promoted set is reported as removed.
+#[derive(Default)]
+pub struct SnapshotDiffer {
+    markets: HashSet<String>,
+    tops: HashMap<TokenId, OutcomeTop>,
+}
+
+impl SnapshotDiffer {
+    /// Market adds/removes and changed tops versus the previous call, then one
+    /// opportunity per bundle in `intents`.
+    pub fn diff(&mut self, snap: &GlobalSnapshot, intents: &[OrderIntent]) -> Vec<Event> {
+        let ts_ms = snap.ts_ms;
+        let mut out = vec![];
+        let mut markets = HashSet::with_capacity(snap.markets.len());
+        let mut tops = HashMap::with_capacity(self.tops.len());
+        for m in &snap.markets {
+            if !self.markets.contains(&m.market_id) {
+                out.push(Event::MarketAdded { ts_ms, market_id: m.market_id.clone(), question: m.question.clone() });
+            }
+            markets.insert(m.market_id.clone());
+            for o in &m.outcomes {
+                if self.tops.get(&o.token_id) != Some(o) {
+                    out.push(Event::BookUpdate {
+                        ts_ms,
+                        market_id: m.market_id.clone(),
+                        token_id: o.token_id.clone(),
+                        best_bid_px: o.best_bid_px,
+                        best_bid_sz: o.best_bid_sz,
+                        best_ask_px: o.best_ask_px,
+                        best_ask_sz: o.best_ask_sz,
+                    });
+                }
+                tops.insert(o.token_id.clone(), o.clone());
+            }
+        }
+        let mut removed: Vec<&String> = self.markets.difference(&markets).collect();
+        removed.sort();
+        out.extend(removed.into_iter().map(|id| Event::MarketRemoved { ts_ms, market_id: id.clone() }));
+
+        let mut order: Vec<Uuid> = vec![];
+        let mut by_bundle: HashMap<Uuid, Vec<&OrderIntent>> = HashMap::new();
+        for i in intents {
+            if !by_bundle.contains_key(&i.bundle_id) {
+                order.push(i.bundle_id);
+            }
+            by_bundle.entry(i.bundle_id).or_default().push(i);
+        }
+        for bid in order {
+            let legs = &by_bundle[&bid];
+            let gauges = snap.markets.iter()
+                .find(|m| m.market_id == legs[0].market_id)
+                .map(|m| m.outcomes.iter().map(|o| o.gauge()).collect())
+                .unwrap_or_default();
+            out.push(Event::Opportunity {
+                ts_ms,
+                bundle_id: bid,
+                market_id: legs[0].market_id.clone(),
+                sum_ask: legs[0].meta.sum_ask,
+                edge_bps: legs[0].meta.edge_bps,
+                size: legs[0].size,
+                legs: legs.len(),
+                gauges,
+            });
+        }
+
+        self.markets = markets;
+        self.tops = tops;
+        out
+    }
+}
+
+/// Fan-out of JSON lines to every connected consumer.
+///
+/// Consumers get events from connection time on; there is no replay. Publishing never
+/// waits: a consumer more than `CONSUMER_BUFFER` lines behind, or whose socket stalls a
+/// write for `WRITE_TIMEOUT`, is disconnected.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<Arc<str>>,
+}
+
+impl EventBus {
+    pub fn publish(&self, events: &[Event]) {
+        if self.tx.receiver_count() == 0 {
+            return;
+        }
+        for e in events {
+            match serde_json::to_string(e) {
+                // only fails when nobody is connected
+                Ok(line) => { let _ = self.tx.send(Arc::from(line)); }
+                Err(e) => tracing::warn!(err = %e, "event encode failed"),
+            }
+        }
+    }
+}
+
+/// Binds `addr` and accepts consumers in a background task. `addr` is `host:port` for
+/// TCP or `unix:/path` for a unix domain socket (an existing file there is replaced).
+pub async fn spawn(addr: &str) -> Result<EventBus> {
+    let (tx, _) = broadcast::channel(CONSUMER_BUFFER);
+    let bus = EventBus { tx: tx.clone() };
+
+    #[cfg(unix)]
+    if let Some(path) = addr.strip_prefix("unix:") {
+        let _ = std::fs::remove_file(path);
+        let listener = tokio::net::UnixListener::bind(path)
+            .with_context(|| format!("bind event stream on {}", addr))?;
+        tracing::info!(addr, "event stream listening");
+        tokio::spawn(async move {
+            loop {
+                match listener.accept().await {
+                    Ok((sock, _)) => { tokio::spawn(serve(sock, tx.subscribe(), "unix".to_string())); }
+                    Err(e) => tracing::warn!(err = %e, "event stream accept failed"),
+                }
+            }
+        });
+        return Ok(bus);
+    }
+
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .with_context(|| format!("bind event stream on {}", addr))?;
+    tracing::info!(addr, "event stream listening");
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((sock, peer)) => { tokio::spawn(serve(sock, tx.subscribe(), peer.to_string())); }
+                Err(e) => tracing::warn!(err = %e, "event stream accept failed"),
+            }
+        }
+    });
+    Ok(bus)
+}
+
+async fn serve<W: AsyncWrite + Unpin>(mut sock: W, mut rx: broadcast::Receiver<Arc<str>>, peer: String) {
+    tracing::info!(%peer, "event consumer connected");
+    loop {
+        let line = match rx.recv().await {
+            Ok(line) => line,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                tracing::warn!(%peer, missed, "event consumer too slow, disconnecting");
+                return;
+            }
+            Err(broadcast::error::RecvError::Closed) => return,
+        };
+        let write = async {
+            sock.write_all(line.as_bytes()).await?;
+            sock.write_all(b"\n").await
+        };
+        match tokio::time::timeout(WRITE_TIMEOUT, write).await {
+            Ok(Ok(())) => {}
+            Ok(Err(_)) => {
+                tracing::info!(%peer, "event consumer disconnected");
+                return;
+            }
+            Err(_) => {
+                tracing::warn!(%peer, "event consumer write stalled, disconnecting");
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::strategy::sum_arb::BundleQuote;
+use crate::types::{LegGauge, MarketBook, OrderIntent};
+
+/// Everything needed to re-check one emitted bundle after the fact: the raw venue
+/// responses for its tokens next to what we decoded and computed from them.
+#[derive(Debug, Serialize)]
+pub struct BundleForensics<'a> {
+    pub bundle_id: Uuid,
+    pub ts_ms: i64,
+    pub market: &'a MarketBook,
+    /// Mid and spread per leg of `market`, in leg order
+    pub gauges: Vec<LegGauge>,
+    pub quote: Option<&'a BundleQuote>,
+    pub intents: Vec<&'a OrderIntent>,
+    /// Book JSON exactly as returned by /books or /book, by token id
+    pub raw_books: HashMap<String, serde_json::Value>,
+}
+
+/// Writes `<dir>/<bundle_id>.json` and returns its path.
+pub async fn write_bundle(dir: &Path, record: &BundleForensics<'_>) -> Result<PathBuf> {
+    tokio::fs::create_dir_all(dir)
+        .await
+        .with_context(|| format!("create forensics dir {}", dir.display()))?;
+    let path = dir.join(format!("{}.json", record.bundle_id));
+    let body = serde_json::to_vec_pretty(record)?;
+    tokio::fs::write(&path, body)
+        .await
+        .with_context(|| format!("write {}", path.display()))?;
+    Ok(path)
+}
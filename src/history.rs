@@ -0,0 +1,283 @@
+use std::collections::{HashMap, VecDeque};
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::types::{GlobalSnapshot, TokenId};
+
+/// Top of book for one token at one snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Observation {
+    pub ts_ms: i64,
+    pub best_bid: Option<Decimal>,
+    pub best_ask: Option<Decimal>,
+}
+
+impl Observation {
+    pub fn mid(&self) -> Option<Decimal> {
+        Some((self.best_bid? + self.best_ask?) / dec!(2))
+    }
+}
+
+/// Last `depth` observations of one token, oldest first.
+#[derive(Debug, Clone)]
+pub struct TokenHistory {
+    ring: VecDeque<Observation>,
+    last_seen_cycle: u64,
+}
+
+impl TokenHistory {
+    pub fn len(&self) -> usize {
+        self.ring.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ring.is_empty()
+    }
+
+    pub fn latest(&self) -> Option<&Observation> {
+        self.ring.back()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Observation> {
+        self.ring.iter()
+    }
+
+    /// When the top of book last moved: the ts of the newest observation that differs from
+    /// the one before it, or of the oldest retained one if nothing moved since.
+    pub fn last_change_ms(&self) -> Option<i64> {
+        let changed = self.ring.iter()
+            .zip(self.ring.iter().skip(1))
+            .rev()
+            .find(|(prev, cur)| (prev.best_bid, prev.best_ask) != (cur.best_bid, cur.best_ask))
+            .map(|(_, cur)| cur.ts_ms);
+        changed.or_else(|| self.ring.front().map(|o| o.ts_ms))
+    }
+
+    /// Population standard deviation of mid-to-mid changes over the last `n` observations
+    /// (so up to n - 1 changes), in price units. None with fewer than two mids.
+    pub fn volatility(&self, n: usize) -> Option<f64> {
+        let mids: Vec<f64> = self.ring.iter()
+            .skip(self.ring.len().saturating_sub(n))
+            .filter_map(|o| o.mid()?.to_f64())
+            .collect();
+        if mids.len() < 2 {
+            return None;
+        }
+        let diffs: Vec<f64> = mids.windows(2).map(|w| w[1] - w[0]).collect();
+        let mean = diffs.iter().sum::<f64>() / diffs.len() as f64;
+        let var = diffs.iter().map(|d| (d - mean).powi(2)).sum::<f64>() / diffs.len() as f64;
+        Some(var.sqrt())
+    }
+
+    /// Largest top-of-book range over the last `window_ms` up to `now_ms`: max - min of the
+    /// ask or of the bid, whichever moved more, with the number of observations in the
+    /// window. None when no observation falls in the window.
+    pub fn max_move(&self, window_ms: i64, now_ms: i64) -> Option<(Decimal, usize)> {
+        let recent: Vec<&Observation> = self.ring.iter().filter(|o| o.ts_ms >= now_ms - window_ms).collect();
+        if recent.is_empty() {
+            return None;
+        }
+        let range = |side: fn(&Observation) -> Option<Decimal>| {
+            let px: Vec<Decimal> = recent.iter().filter_map(|o| side(o)).collect();
+            match (px.iter().max(), px.iter().min()) {
+                (Some(hi), Some(lo)) => *hi - *lo,
+                _ => Decimal::ZERO,
+            }
+        };
+        Some((range(|o| o.best_ask).max(range(|o| o.best_bid)), recent.len()))
+    }
+
+    /// Mid weighted by how long each observation stood, over the last `window_ms` up to
+    /// `now_ms`. Observations without a mid are skipped; None if none had one.
+    pub fn twap_mid(&self, window_ms: i64, now_ms: i64) -> Option<Decimal> {
+        let start = now_ms - window_ms;
+        let mut weighted = Decimal::ZERO;
+        let mut total_ms = 0i64;
+        for (i, o) in self.ring.iter().enumerate() {
+            let until = self.ring.get(i + 1).map(|n| n.ts_ms).unwrap_or(now_ms).min(now_ms);
+            let from = o.ts_ms.max(start);
+            if until <= from {
+                continue;
+            }
+            let Some(mid) = o.mid() else { continue };
+            weighted += mid * Decimal::from(until - from);
+            total_ms += until - from;
+        }
+        (total_ms > 0).then(|| weighted / Decimal::from(total_ms))
+    }
+}
+
+/// Bounded per-token history of top of book, fed once per snapshot.
+///
+/// Each token keeps at most `depth` observations (oldest dropped first), and a token
+/// absent from `evict_after_cycles` consecutive snapshots is forgotten, so memory is
+/// bounded by depth times the tokens seen recently.
+#[derive(Debug, Clone)]
+pub struct PriceHistory {
+    depth: usize,
+    evict_after_cycles: u64,
+    cycle: u64,
+    tokens: HashMap<TokenId, TokenHistory>,
+}
+
+impl PriceHistory {
+    /// `depth == 0` disables recording.
+    pub fn new(depth: usize, evict_after_cycles: u64) -> Self {
+        Self { depth, evict_after_cycles: evict_after_cycles.max(1), cycle: 0, tokens: HashMap::new() }
+    }
+
+    pub fn get(&self, token_id: &TokenId) -> Option<&TokenHistory> {
+        self.tokens.get(token_id)
+    }
+
+    /// Tokens currently tracked.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Appends every token's top from `snap`, then evicts tokens not seen recently.
+    pub fn record(&mut self, snap: &GlobalSnapshot) {
+        if self.depth == 0 {
+            return;
+        }
+        self.cycle += 1;
+        for o in snap.markets.iter().flat_map(|m| m.outcomes.iter()) {
+            let h = self.tokens.entry(o.token_id.clone()).or_insert_with(|| TokenHistory {
+                ring: VecDeque::with_capacity(self.depth),
+                last_seen_cycle: 0,
+            });
+            // a token shared by two markets (NO baskets) is seen twice per snapshot
+            if h.last_seen_cycle == self.cycle {
+                continue;
+            }
+            if h.ring.len() == self.depth {
+                h.ring.pop_front();
+            }
+            h.ring.push_back(Observation { ts_ms: snap.ts_ms, best_bid: o.best_bid_px, best_ask: o.best_ask_px });
+            h.last_seen_cycle = self.cycle;
+        }
+        let (cycle, keep) = (self.cycle, self.evict_after_cycles);
+        self.tokens.retain(|_, h| cycle - h.last_seen_cycle < keep);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::snapshot_builder::{outcome, SnapshotBuilder};
+
+    /// One market at `ts_ms` whose legs quote each `(token, bid, ask)`.
+    fn snap(ts_ms: i64, legs: &[(&str, Decimal, Decimal)]) -> GlobalSnapshot {
+        let mut s = SnapshotBuilder::new().markets(1).ts_ms(ts_ms).build();
+        s.markets[0].outcomes = legs.iter().map(|(t, bid, ask)| outcome(t, *bid, *ask, dec!(100))).collect();
+        s
+    }
+
+    fn tok(id: &str) -> TokenId {
+        TokenId::from(id)
+    }
+
+    /// History of token "a" with one observation per (ts, bid, ask).
+    fn history(obs: &[(i64, Decimal, Decimal)]) -> PriceHistory {
+        let mut h = PriceHistory::new(100, 10);
+        for (ts, bid, ask) in obs {
+            h.record(&snap(*ts, &[("a", *bid, *ask)]));
+        }
+        h
+    }
+
+    #[test]
+    fn ring_keeps_the_newest_depth_observations() {
+        let mut h = PriceHistory::new(3, 10);
+        for ts in 1..=5 {
+            h.record(&snap(ts, &[("a", dec!(0.4), dec!(0.5))]));
+        }
+        let a = h.get(&tok("a")).unwrap();
+        assert_eq!(a.iter().map(|o| o.ts_ms).collect::<Vec<_>>(), [3, 4, 5]);
+        assert_eq!(a.latest().unwrap().ts_ms, 5);
+    }
+
+    #[test]
+    fn tokens_unseen_for_m_cycles_are_evicted() {
+        let mut h = PriceHistory::new(10, 2);
+        h.record(&snap(1, &[("a", dec!(0.4), dec!(0.5)), ("b", dec!(0.4), dec!(0.5))]));
+        h.record(&snap(2, &[("a", dec!(0.4), dec!(0.5))]));
+        assert!(h.get(&tok("b")).is_some());
+        h.record(&snap(3, &[("a", dec!(0.4), dec!(0.5))]));
+        assert!(h.get(&tok("b")).is_none());
+        assert_eq!(h.len(), 1);
+    }
+
+    #[test]
+    fn a_shared_token_is_recorded_once_per_snapshot() {
+        let mut h = PriceHistory::new(10, 2);
+        let mut s = snap(1, &[("a", dec!(0.4), dec!(0.5))]);
+        s.markets.push(s.markets[0].clone());
+        h.record(&s);
+        assert_eq!(h.get(&tok("a")).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn zero_depth_records_nothing() {
+        let mut h = PriceHistory::new(0, 2);
+        h.record(&snap(1, &[("a", dec!(0.4), dec!(0.5))]));
+        assert!(h.is_empty());
+    }
+
+    #[test]
+    fn volatility_is_the_deviation_of_mid_changes() {
+        // mids 0.50, 0.52, 0.50, 0.52: changes +2c, -2c, +2c around a mean of +2/3c
+        let h = history(&[
+            (1, dec!(0.49), dec!(0.51)),
+            (2, dec!(0.51), dec!(0.53)),
+            (3, dec!(0.49), dec!(0.51)),
+            (4, dec!(0.51), dec!(0.53)),
+        ]);
+        let a = h.get(&tok("a")).unwrap();
+        let expected = (2.0f64 * (0.04f64 / 3.0).powi(2) + (0.02f64 + 0.02 / 3.0).powi(2)) / 3.0;
+        assert!((a.volatility(4).unwrap() - expected.sqrt()).abs() < 1e-12);
+        // the last two changes only: +2c and -2c around zero
+        assert!((a.volatility(3).unwrap() - 0.02).abs() < 1e-12);
+        assert_eq!(a.volatility(1), None);
+    }
+
+    #[test]
+    fn steady_drift_has_no_volatility() {
+        let h = history(&[(1, dec!(0.49), dec!(0.51)), (2, dec!(0.50), dec!(0.52)), (3, dec!(0.51), dec!(0.53))]);
+        assert!(h.get(&tok("a")).unwrap().volatility(10).unwrap().abs() < 1e-12);
+    }
+
+    #[test]
+    fn last_change_and_max_move() {
+        let h = history(&[
+            (1_000, dec!(0.40), dec!(0.50)),
+            (2_000, dec!(0.40), dec!(0.53)),
+            (3_000, dec!(0.40), dec!(0.53)),
+            (4_000, dec!(0.38), dec!(0.53)),
+            (5_000, dec!(0.38), dec!(0.53)),
+        ]);
+        let a = h.get(&tok("a")).unwrap();
+        assert_eq!(a.last_change_ms(), Some(4_000));
+        // asks moved 3c over the whole ring, bids 2c
+        assert_eq!(a.max_move(10_000, 5_000), Some((dec!(0.03), 5)));
+        // from 3s on only the bid moved
+        assert_eq!(a.max_move(2_000, 5_000), Some((dec!(0.02), 3)));
+        assert_eq!(a.max_move(500, 9_000), None);
+    }
+
+    #[test]
+    fn twap_weights_mids_by_how_long_they_stood() {
+        // mid 0.45 for 3s, then 0.55 for 1s
+        let h = history(&[(0, dec!(0.40), dec!(0.50)), (3_000, dec!(0.50), dec!(0.60))]);
+        let a = h.get(&tok("a")).unwrap();
+        assert_eq!(a.twap_mid(4_000, 4_000), Some(dec!(0.475)));
+        // only the last 2s: 1s of each
+        assert_eq!(a.twap_mid(2_000, 4_000), Some(dec!(0.50)));
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::strategy::MarketHit;
+
+/// Lifetime counts for one market.
+#[derive(Debug, Clone, Serialize)]
+pub struct HotMarket {
+    pub market_id: String,
+    pub question: String,
+    pub opportunities: u64,
+    pub near_arbs: u64,
+}
+
+struct Entry {
+    market: HotMarket,
+    /// Cycle the market last produced a hit, for LRU eviction
+    last_hit_cycle: u64,
+}
+
+/// Which markets produced opportunities and near-arbs over the run.
+///
+/// Bounded at `capacity` markets: when full, the market whose last hit is oldest is
+/// evicted, so a burst of one-off markets cannot push out steadily productive ones for
+/// long, but does reset their counts if they are evicted.
+pub struct HotMarkets {
+    capacity: usize,
+    cycle: u64,
+    entries: HashMap<String, Entry>,
+}
+
+impl HotMarkets {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity: capacity.max(1), cycle: 0, entries: HashMap::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds one cycle's hits.
+    pub fn observe(&mut self, hits: &[MarketHit]) {
+        self.cycle += 1;
+        for h in hits {
+            if !self.entries.contains_key(&h.market_id) && self.entries.len() >= self.capacity {
+                self.evict_oldest();
+            }
+            let e = self.entries.entry(h.market_id.clone()).or_insert_with(|| Entry {
+                market: HotMarket {
+                    market_id: h.market_id.clone(),
+                    question: h.question.clone(),
+                    opportunities: 0,
+                    near_arbs: 0,
+                },
+                last_hit_cycle: 0,
+            });
+            e.market.opportunities += h.opportunity as u64;
+            e.market.near_arbs += h.near_arb as u64;
+            e.last_hit_cycle = self.cycle;
+        }
+    }
+
+    /// Opportunities plus near-arbs `market_id` produced while tracked.
+    pub fn hits(&self, market_id: &str) -> u64 {
+        self.entries.get(market_id).map(|e| e.market.opportunities + e.market.near_arbs).unwrap_or(0)
+    }
+
+    /// Up to `n` markets, most opportunities first, then most near-arbs, then market id.
+    pub fn top(&self, n: usize) -> Vec<HotMarket> {
+        let mut all: Vec<&HotMarket> = self.entries.values().map(|e| &e.market).collect();
+        all.sort_by(|a, b| {
+            b.opportunities.cmp(&a.opportunities)
+                .then(b.near_arbs.cmp(&a.near_arbs))
+                .then(a.market_id.cmp(&b.market_id))
+        });
+        all.into_iter().take(n).cloned().collect()
+    }
+
+    fn evict_oldest(&mut self) {
+        let oldest = self.entries.iter()
+            .min_by_key(|(_, e)| e.last_hit_cycle)
+            .map(|(k, _)| k.clone());
+        if let Some(k) = oldest {
+            self.entries.remove(&k);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::testutil::snapshot_builder::SnapshotBuilder;
+    use crate::testutil::strategy::{run, sum_arb};
+    use crate::types::GlobalSnapshot;
+
+    /// Markets mkt-0.. with two even legs summing to each of `sums`.
+    fn snapshot(sums: &[Decimal]) -> GlobalSnapshot {
+        let mut snap = SnapshotBuilder::new().markets(sums.len()).build();
+        for (m, sum) in snap.markets.iter_mut().zip(sums) {
+            for o in &mut m.outcomes {
+                o.best_ask_px = Some(sum / dec!(2));
+                o.best_bid_px = Some(sum / dec!(2) - dec!(0.01));
+            }
+        }
+        snap
+    }
+
+    fn hit(market_id: &str, opportunity: bool) -> MarketHit {
+        MarketHit {
+            market_id: market_id.into(),
+            question: format!("{}?", market_id),
+            near_arb: true,
+            opportunity,
+            edge_bps: Decimal::ZERO,
+            sum_ask: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn top_market_after_several_snapshots() {
+        let strategy = sum_arb();
+        let mut hot = HotMarkets::new(100);
+        for sums in [
+            [dec!(0.96), dec!(0.96), dec!(1.04)],
+            [dec!(1.04), dec!(0.96), dec!(0.998)],
+            [dec!(1.04), dec!(0.96), dec!(0.998)],
+        ] {
+            let (_, summary) = run(&strategy, &snapshot(&sums));
+            hot.observe(&summary.hits);
+        }
+        let top = hot.top(10);
+        let rows: Vec<(&str, u64, u64)> = top.iter().map(|m| (m.market_id.as_str(), m.opportunities, m.near_arbs)).collect();
+        assert_eq!(rows, [("mkt-1", 3, 3), ("mkt-0", 1, 1), ("mkt-2", 0, 2)]);
+        assert_eq!(hot.top(1)[0].market_id, "mkt-1");
+        assert_eq!(hot.hits("mkt-2"), 2);
+    }
+
+    #[test]
+    fn ties_break_on_near_arbs_then_market_id() {
+        let mut hot = HotMarkets::new(10);
+        hot.observe(&[hit("b", true), hit("c", true), hit("a", true), hit("c", false)]);
+        let ids: Vec<String> = hot.top(3).into_iter().map(|m| m.market_id).collect();
+        assert_eq!(ids, ["c", "a", "b"]);
+    }
+
+    #[test]
+    fn full_map_evicts_the_least_recently_hit_market() {
+        let mut hot = HotMarkets::new(2);
+        hot.observe(&[hit("a", true)]);
+        hot.observe(&[hit("b", true)]);
+        hot.observe(&[hit("a", true)]);
+        hot.observe(&[hit("c", true)]);
+        assert_eq!(hot.len(), 2);
+        assert_eq!((hot.hits("a"), hot.hits("b"), hot.hits("c")), (4, 0, 2));
+    }
+}
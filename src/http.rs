@@ -0,0 +1,476 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::sync::Semaphore;
+
+use crate::config::Settings;
+use crate::jitter::Jitter;
+
+const SNIPPET_LEN: usize = 200;
+
+/// Classified failure of a venue request, shared by every data source.
+#[derive(Debug)]
+pub enum SourceError {
+    Timeout { url: String },
+    Transport { url: String, err: String },
+    Status { url: String, status: u16, snippet: String },
+    Decode { url: String, err: String, snippet: String },
+}
+
+impl SourceError {
+    /// Timeouts, transport failures, 429 and 5xx are worth retrying; everything else is not.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            SourceError::Timeout { .. } | SourceError::Transport { .. } => true,
+            SourceError::Status { status, .. } => *status == 429 || *status >= 500,
+            SourceError::Decode { .. } => false,
+        }
+    }
+}
+
+impl std::fmt::Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SourceError::Timeout { url } => write!(f, "timeout: {}", url),
+            SourceError::Transport { url, err } => write!(f, "transport error: {}: {}", url, err),
+            SourceError::Status { url, status, snippet } => {
+                write!(f, "http {}: {}: {}", status, url, snippet)
+            }
+            SourceError::Decode { url, err, snippet } => {
+                write!(f, "decode error: {}: {} (body: {})", url, err, snippet)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff_ms: u64,
+    pub max_backoff_ms: u64,
+    /// Each wait is spread by up to this percentage either way so clients retrying the
+    /// same failure do not hit the venue in lockstep
+    pub jitter_pct: u32,
+    pub jitter: Arc<Jitter>,
+}
+
+impl RetryPolicy {
+    /// Exponential backoff before retry `attempt` (1-based) with jitter applied, capped
+    /// at `max_backoff_ms`.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.backoff_ms.saturating_mul(1u64 << attempt.saturating_sub(1).min(16));
+        let wait = self.jitter.spread(Duration::from_millis(exp.min(self.max_backoff_ms)), self.jitter_pct);
+        wait.min(Duration::from_millis(self.max_backoff_ms))
+    }
+}
+
+/// Token bucket: refills at `per_sec` up to `burst` tokens, one token per request.
+///
+/// Callers reserve a token up front, letting the bucket go negative, and sleep off the
+/// deficit; waiters are thus admitted in arrival order and the long-run rate is exact
+/// regardless of how many requests run concurrently.
+pub struct RateLimiter {
+    per_sec: f64,
+    burst: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    /// `burst` is raised to at least 1. The bucket starts full.
+    pub fn new(per_sec: f64, burst: u32) -> Self {
+        let burst = burst.max(1) as f64;
+        Self { per_sec, burst, state: Mutex::new((burst, Instant::now())) }
+    }
+
+    /// Waits until a request may be sent.
+    pub async fn acquire(&self) {
+        let wait = {
+            let mut st = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            let now = Instant::now();
+            let (tokens, last) = *st;
+            let tokens = (tokens + now.duration_since(last).as_secs_f64() * self.per_sec).min(self.burst) - 1.0;
+            *st = (tokens, now);
+            if tokens >= 0.0 { Duration::ZERO } else { Duration::from_secs_f64(-tokens / self.per_sec) }
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+/// Which request answered a `hedged` call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hedge {
+    /// The first request answered in time, or no hedge was allowed
+    NotFired,
+    PrimaryWon,
+    HedgeWon,
+}
+
+/// Sends `make()` and, when it has not answered after `after` and `may_hedge()` agrees,
+/// races an identical second request against it. The first success wins and the other
+/// future is dropped, cancelling it; an error is only returned once both have failed.
+pub async fn hedged<T, E, Fut>(after: Duration, may_hedge: impl FnOnce() -> bool, make: impl Fn() -> Fut) -> (Result<T, E>, Hedge)
+where
+    Fut: std::future::Future<Output = Result<T, E>>,
+{
+    let primary = make();
+    tokio::pin!(primary);
+    tokio::select! {
+        r = &mut primary => return (r, Hedge::NotFired),
+        _ = tokio::time::sleep(after) => {}
+    }
+    if !may_hedge() {
+        return (primary.await, Hedge::NotFired);
+    }
+    let hedge = make();
+    tokio::pin!(hedge);
+    tokio::select! {
+        r = &mut primary => match r {
+            Ok(v) => (Ok(v), Hedge::PrimaryWon),
+            Err(_) => (hedge.await, Hedge::HedgeWon),
+        },
+        r = &mut hedge => match r {
+            Ok(v) => (Ok(v), Hedge::HedgeWon),
+            Err(_) => (primary.await, Hedge::PrimaryWon),
+        },
+    }
+}
+
+/// reqwest client plus timeout/retry policy; cheap to clone.
+///
+/// Clones share the optional global in-flight limit, so every subsystem built from one
+/// client counts against the same ceiling. A rate limit is shared the same way; give a
+/// clone its own with `with_rate_limit` to budget one venue separately.
+#[derive(Clone)]
+pub struct HttpClient {
+    http: reqwest::Client,
+    policy: RetryPolicy,
+    in_flight: Option<Arc<Semaphore>>,
+    rate: Option<Arc<RateLimiter>>,
+}
+
+impl HttpClient {
+    /// The shared client `s` describes: timeout, retry policy and global concurrency limit.
+    /// Per-venue rate limits are added by the caller.
+    pub fn from_settings(s: &Settings, jitter: Arc<Jitter>) -> anyhow::Result<Self> {
+        Ok(Self::new(s.http_timeout_ms, RetryPolicy {
+            max_retries: s.http_max_retries,
+            backoff_ms: s.http_backoff_ms,
+            max_backoff_ms: s.http_max_backoff_ms,
+            jitter_pct: s.http_backoff_jitter_pct,
+            jitter,
+        })?
+        .with_global_limit(s.http_global_concurrency))
+    }
+
+    pub fn new(timeout_ms: u64, policy: RetryPolicy) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(timeout_ms.max(1)))
+            .build()?;
+        Ok(Self { http, policy, in_flight: None, rate: None })
+    }
+
+    /// At most `max` requests in flight at once across all clones; `0` is unlimited.
+    /// A permit is held per attempt, not across retry backoff.
+    pub fn with_global_limit(mut self, max: usize) -> Self {
+        self.in_flight = (max > 0).then(|| Arc::new(Semaphore::new(max)));
+        self
+    }
+
+    /// At most `per_sec` requests per second on average with bursts of up to `burst`,
+    /// across all clones made after this call; `per_sec <= 0` is unlimited. Every attempt,
+    /// retries included, takes a token.
+    pub fn with_rate_limit(mut self, per_sec: f64, burst: u32) -> Self {
+        self.rate = (per_sec > 0.0).then(|| Arc::new(RateLimiter::new(per_sec, burst)));
+        self
+    }
+
+    pub async fn get_json<T: DeserializeOwned>(
+        &self,
+        url: &str,
+        headers: &[(&str, &str)],
+    ) -> Result<T, SourceError> {
+        self.request_json::<(), T>(reqwest::Method::GET, url, None, headers).await
+    }
+
+    pub async fn post_json<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        url: &str,
+        body: &B,
+        headers: &[(&str, &str)],
+    ) -> Result<T, SourceError> {
+        self.request_json(reqwest::Method::POST, url, Some(body), headers).await
+    }
+
+    /// Sends the request, retrying retryable failures per the policy, and decodes the JSON body.
+    pub async fn request_json<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<&B>,
+        headers: &[(&str, &str)],
+    ) -> Result<T, SourceError> {
+        let mut attempt: u32 = 0;
+        loop {
+            match self.send_once(method.clone(), url, body, headers).await {
+                Ok(v) => return Ok(v),
+                Err(e) if e.is_retryable() && attempt < self.policy.max_retries => {
+                    attempt += 1;
+                    let wait = self.policy.backoff(attempt);
+                    tracing::debug!(url = %url, attempt, wait_ms = wait.as_millis() as u64, err = %e, "retrying request");
+                    tokio::time::sleep(wait).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    async fn send_once<B: Serialize + ?Sized, T: DeserializeOwned>(
+        &self,
+        method: reqwest::Method,
+        url: &str,
+        body: Option<&B>,
+        headers: &[(&str, &str)],
+    ) -> Result<T, SourceError> {
+        // take the rate token first so a throttled request holds no connection slot
+        if let Some(rate) = &self.rate {
+            rate.acquire().await;
+        }
+        let _permit = match &self.in_flight {
+            // the semaphore is never closed
+            Some(sem) => Some(sem.acquire().await.expect("http semaphore closed")),
+            None => None,
+        };
+        let mut req = self.http.request(method, url);
+        for (k, v) in headers {
+            req = req.header(*k, *v);
+        }
+        if let Some(b) = body {
+            req = req.json(b);
+        }
+
+        let resp = req.send().await.map_err(|e| classify(url, e))?;
+        let status = resp.status();
+        let text = resp.text().await.map_err(|e| classify(url, e))?;
+        if !status.is_success() {
+            return Err(SourceError::Status {
+                url: url.to_string(),
+                status: status.as_u16(),
+                snippet: snippet(&text),
+            });
+        }
+
+        serde_json::from_str(&text).map_err(|e| SourceError::Decode {
+            url: url.to_string(),
+            err: e.to_string(),
+            snippet: snippet(&text),
+        })
+    }
+}
+
+fn classify(url: &str, e: reqwest::Error) -> SourceError {
+    if e.is_timeout() {
+        SourceError::Timeout { url: url.to_string() }
+    } else {
+        SourceError::Transport { url: url.to_string(), err: e.to_string() }
+    }
+}
+
+fn snippet(body: &str) -> String {
+    body.chars().take(SNIPPET_LEN).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::testutil::markets::http_client;
+
+    #[derive(Debug, serde::Deserialize)]
+    struct Pong {
+        ok: bool,
+    }
+
+    async fn server(status: u16, body: &str, times: Option<u64>) -> MockServer {
+        let server = MockServer::start().await;
+        let mock = Mock::given(method("GET")).and(path("/ping")).respond_with(ResponseTemplate::new(status).set_body_string(body));
+        match times {
+            Some(n) => mock.up_to_n_times(n).mount(&server).await,
+            None => mock.mount(&server).await,
+        }
+        server
+    }
+
+    /// A local server answering every request with `[]` after `hold`, tracking the most
+    /// requests it had open at once.
+    async fn peak_server(hold: Duration) -> (String, Arc<std::sync::atomic::AtomicUsize>) {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let open = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let peak_out = peak.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut sock, _) = listener.accept().await.unwrap();
+                let (open, peak) = (open.clone(), peak.clone());
+                tokio::spawn(async move {
+                    let mut req = vec![];
+                    let mut buf = [0u8; 1024];
+                    while !req.windows(4).any(|w| w == b"\r\n\r\n") {
+                        match sock.read(&mut buf).await {
+                            Ok(0) | Err(_) => return,
+                            Ok(n) => req.extend_from_slice(&buf[..n]),
+                        }
+                    }
+                    peak.fetch_max(open.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+                    tokio::time::sleep(hold).await;
+                    open.fetch_sub(1, Ordering::SeqCst);
+                    let resp = "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: 2\r\nconnection: close\r\n\r\n[]";
+                    let _ = sock.write_all(resp.as_bytes()).await;
+                });
+            }
+        });
+        (url, peak_out)
+    }
+
+    /// `n` concurrent GETs through `client`.
+    async fn burst(client: &HttpClient, url: &str, n: usize) {
+        let calls = (0..n).map(|_| client.get_json::<Vec<u8>>(url, &[]));
+        for r in futures::future::join_all(calls).await {
+            r.unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn global_limit_holds_across_clones() {
+        let (url, peak) = peak_server(Duration::from_millis(40)).await;
+        let shared = http_client(0).with_global_limit(3);
+        // two subsystems built from the same client, each wanting 6 at once
+        let (books, prices) = (shared.clone(), shared.clone());
+        tokio::join!(burst(&books, &url, 6), burst(&prices, &url, 6));
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn no_global_limit_lets_everything_through() {
+        let (url, peak) = peak_server(Duration::from_millis(100)).await;
+        let (books, prices) = (http_client(0), http_client(0));
+        tokio::join!(burst(&books, &url, 4), burst(&prices, &url, 4));
+        assert_eq!(peak.load(std::sync::atomic::Ordering::SeqCst), 8);
+    }
+
+    /// How many `acquire`s return within `window`.
+    async fn admitted(limiter: &RateLimiter, window: Duration) -> usize {
+        let deadline = Instant::now() + window;
+        let mut n = 0;
+        loop {
+            limiter.acquire().await;
+            if Instant::now() > deadline {
+                return n;
+            }
+            n += 1;
+        }
+    }
+
+    #[tokio::test]
+    async fn rate_limiter_admits_the_configured_rate() {
+        // a full bucket of 5 then 50/s: 5 + 0.5 * 50 = 30 over half a second
+        let n = admitted(&RateLimiter::new(50.0, 5), Duration::from_millis(500)).await;
+        assert!((27..=32).contains(&n), "admitted {}", n);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_client_spaces_out_requests() {
+        let server = server(200, "{\"ok\":true}", None).await;
+        let client = http_client(0).with_rate_limit(20.0, 1);
+        let url = format!("{}/ping", server.uri());
+        let started = Instant::now();
+        for _ in 0..5 {
+            assert!(client.get_json::<Pong>(&url, &[]).await.unwrap().ok);
+        }
+        // the first is free, the next four wait 50 ms each
+        assert!(started.elapsed() >= Duration::from_millis(190), "took {:?}", started.elapsed());
+    }
+
+    fn policy(seed: u64, jitter_pct: u32) -> RetryPolicy {
+        RetryPolicy { max_retries: 5, backoff_ms: 100, max_backoff_ms: 1_000, jitter_pct, jitter: Arc::new(Jitter::seeded(seed)) }
+    }
+
+    fn waits(p: &RetryPolicy) -> Vec<Duration> {
+        (1..=6).map(|attempt| p.backoff(attempt)).collect()
+    }
+
+    #[test]
+    fn seeded_backoff_is_reproducible() {
+        let first = waits(&policy(42, 25));
+        assert_eq!(first, waits(&policy(42, 25)));
+        assert_ne!(first, waits(&policy(7, 25)));
+        for (attempt, wait) in first.iter().enumerate() {
+            let base = (100u64 << attempt).min(1_000);
+            assert!(wait.as_millis() as u64 >= base * 3 / 4 && wait.as_millis() as u64 <= 1_000, "attempt {}: {:?}", attempt + 1, wait);
+        }
+    }
+
+    #[test]
+    fn backoff_without_jitter_doubles_up_to_the_cap() {
+        let ms: Vec<u128> = waits(&policy(0, 0)).iter().map(Duration::as_millis).collect();
+        assert_eq!(ms, [100, 200, 400, 800, 1_000, 1_000]);
+    }
+
+    #[tokio::test]
+    async fn get_json_decodes_a_success() {
+        let server = server(200, r#"{"ok":true}"#, None).await;
+        let pong: Pong = http_client(0).get_json(&format!("{}/ping", server.uri()), &[]).await.unwrap();
+        assert!(pong.ok);
+    }
+
+    #[tokio::test]
+    async fn server_errors_are_retried_until_success() {
+        let server = server(503, "busy", Some(2)).await;
+        Mock::given(method("GET"))
+            .and(path("/ping"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"ok":true}"#))
+            .mount(&server)
+            .await;
+        let pong: Pong = http_client(2).get_json(&format!("{}/ping", server.uri()), &[]).await.unwrap();
+        assert!(pong.ok);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn server_errors_past_max_retries_surface_the_status() {
+        let server = server(502, "bad gateway", None).await;
+        let err = http_client(1).get_json::<Pong>(&format!("{}/ping", server.uri()), &[]).await.unwrap_err();
+        assert!(matches!(&err, SourceError::Status { status: 502, snippet, .. } if snippet == "bad gateway"), "{}", err);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn client_errors_are_not_retried() {
+        let server = server(404, "no such market", None).await;
+        let err = http_client(3).get_json::<Pong>(&format!("{}/ping", server.uri()), &[]).await.unwrap_err();
+        assert!(!err.is_retryable());
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn decode_errors_keep_a_body_snippet_and_are_not_retried() {
+        let server = server(200, "<html>maintenance</html>", None).await;
+        let err = http_client(3).get_json::<Pong>(&format!("{}/ping", server.uri()), &[]).await.unwrap_err();
+        match &err {
+            SourceError::Decode { snippet, .. } => assert_eq!(snippet, "<html>maintenance</html>"),
+            other => panic!("expected a decode error, got {}", other),
+        }
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+    }
+}
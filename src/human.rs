@@ -0,0 +1,112 @@
+use crate::scan::OpportunityRecord;
+
+/// Questions longer than this are cut, so a row fits a terminal.
+const QUESTION_WIDTH: usize = 48;
+
+/// `rows` as an aligned text table with a header line; empty when there are no rows.
+pub fn render_table(rows: &[OpportunityRecord]) -> String {
+    if rows.is_empty() {
+        return String::new();
+    }
+    let header = ["question", "legs", "sum_ask", "edge_bps", "size", "profit"];
+    let cells: Vec<[String; 6]> = rows.iter()
+        .map(|r| [
+            truncate(&r.question, QUESTION_WIDTH),
+            r.legs.to_string(),
+            r.sum_ask.round_dp(4).normalize().to_string(),
+            r.edge_bps.round_dp(1).normalize().to_string(),
+            r.size.normalize().to_string(),
+            r.net_profit.round_dp(2).to_string(),
+        ])
+        .collect();
+    let mut widths = header.map(|h| h.chars().count());
+    for row in &cells {
+        for (w, c) in widths.iter_mut().zip(row) {
+            *w = (*w).max(c.chars().count());
+        }
+    }
+    let line = |row: &[String]| {
+        let mut s = String::new();
+        for (i, (c, w)) in row.iter().zip(widths).enumerate() {
+            // the question reads left to right, numbers line up on the right
+            if i == 0 {
+                s.push_str(&format!("{:<w$}", c, w = w));
+            } else {
+                s.push_str(&format!("  {:>w$}", c, w = w));
+            }
+        }
+        s.trim_end().to_string()
+    };
+    let mut out = line(&header.map(String::from));
+    out.push('\n');
+    for row in &cells {
+        out.push_str(&line(row));
+        out.push('\n');
+    }
+    out
+}
+
+/// `s` cut to `max` chars, the last three replaced by "..." when anything was cut.
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        return s.to_string();
+    }
+    let mut t: String = s.chars().take(max.saturating_sub(3)).collect();
+    t.push_str("...");
+    t
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    fn record(question: &str, legs: usize, sum_ask: Decimal, edge_bps: Decimal, size: Decimal, net_profit: Decimal) -> OpportunityRecord {
+        OpportunityRecord {
+            bundle_id: uuid::Uuid::nil(),
+            market_id: "m".into(),
+            question: question.into(),
+            legs,
+            sum_ask,
+            edge_bps,
+            size,
+            net_profit,
+        }
+    }
+
+    #[test]
+    fn table_aligns_and_truncates() {
+        let rows = [
+            record("Will it rain in Paris tomorrow?", 2, dec!(0.95), dec!(500), dec!(100), dec!(5.00)),
+            record(
+                "Will the 2026 election be decided by fewer than ten thousand votes in total?",
+                3,
+                dec!(0.98767),
+                dec!(123.456),
+                dec!(12.50),
+                dec!(0.153),
+            ),
+        ];
+        assert_eq!(
+            render_table(&rows),
+            concat!(
+                "question                                          legs  sum_ask  edge_bps  size  profit\n",
+                "Will it rain in Paris tomorrow?                      2     0.95       500   100    5.00\n",
+                "Will the 2026 election be decided by fewer th...     3   0.9877     123.5  12.5    0.15\n",
+            )
+        );
+    }
+
+    #[test]
+    fn no_rows_is_no_table() {
+        assert_eq!(render_table(&[]), "");
+    }
+
+    #[test]
+    fn truncation_counts_chars_not_bytes() {
+        assert_eq!(truncate("é".repeat(10).as_str(), 6), "ééé...");
+        assert_eq!(truncate("short", 6), "short");
+    }
+}
@@ -0,0 +1,79 @@
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Randomness for backoff and poll jitter, behind one small type so it can be seeded.
+///
+/// A splitmix64 stream: `seeded` is fully reproducible, `from_entropy` seeds from the
+/// OS-random keys std uses for `HashMap`. Shared by reference (`&self`), so one instance
+/// can serve every clone of the HTTP client and the main loop.
+#[derive(Debug)]
+pub struct Jitter {
+    state: AtomicU64,
+}
+
+impl Jitter {
+    pub fn seeded(seed: u64) -> Self {
+        Self { state: AtomicU64::new(seed) }
+    }
+
+    pub fn from_entropy() -> Self {
+        let mut h = std::collections::hash_map::RandomState::new().build_hasher();
+        h.write_u128(std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or_default());
+        Self::seeded(h.finish())
+    }
+
+    /// Uniform in [0, 1).
+    pub fn next_unit(&self) -> f64 {
+        let mut z = self.state.fetch_add(0x9E37_79B9_7F4A_7C15, Ordering::Relaxed).wrapping_add(0x9E37_79B9_7F4A_7C15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// `d` scaled by a uniform factor in [1 - pct/100, 1 + pct/100]; `pct` is capped at 100.
+    pub fn spread(&self, d: Duration, pct: u32) -> Duration {
+        if pct == 0 {
+            return d;
+        }
+        let frac = pct.min(100) as f64 / 100.0;
+        d.mul_f64(1.0 + frac * (2.0 * self.next_unit() - 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_stream() {
+        let (a, b) = (Jitter::seeded(42), Jitter::seeded(42));
+        let xs: Vec<f64> = (0..20).map(|_| a.next_unit()).collect();
+        let ys: Vec<f64> = (0..20).map(|_| b.next_unit()).collect();
+        assert_eq!(xs, ys);
+        let c = Jitter::seeded(43);
+        assert_ne!(xs[0], c.next_unit());
+    }
+
+    #[test]
+    fn units_stay_in_range() {
+        let j = Jitter::seeded(1);
+        assert!((0..10_000).map(|_| j.next_unit()).all(|u| (0.0..1.0).contains(&u)));
+    }
+
+    #[test]
+    fn spread_stays_within_pct_and_caps_at_100() {
+        let j = Jitter::seeded(9);
+        let d = Duration::from_millis(1_000);
+        assert_eq!(j.spread(d, 0), d);
+        for _ in 0..1_000 {
+            let x = j.spread(d, 20);
+            assert!(x >= Duration::from_millis(800) && x <= Duration::from_millis(1_200), "{:?}", x);
+            assert!(j.spread(d, 500) <= Duration::from_millis(2_000));
+        }
+    }
+}
@@ -0,0 +1,29 @@
+#[cfg(feature = "api")]
+pub mod api;
+pub mod bus;
+pub mod config;
+pub mod diagnostic;
+pub mod episodes;
+pub mod events;
+pub mod forensics;
+pub mod history;
+pub mod hot_markets;
+pub mod http;
+pub mod human;
+pub mod jitter;
+pub mod pipeline;
+pub mod poll;
+pub mod profile;
+pub mod refresh;
+pub mod scan;
+pub mod secret;
+pub mod selftest;
+pub mod source;
+pub mod stats;
+pub mod types;
+
+pub mod pm;
+pub mod strategy;
+
+#[cfg(any(test, feature = "testutil"))]
+pub mod testutil;
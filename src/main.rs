@@ -1,32 +1,196 @@
-mod config;
-mod types;
-mod stats;
+use std::sync::Arc;
 
-mod pm;
-mod strategy;
-
-use anyhow::Result;
-use rust_decimal::Decimal;
+use anyhow::{Context, Result};
 use tracing_subscriber::EnvFilter;
 
-use crate::config::Settings;
-use crate::pm::market_data::{MarketData, MarketDef};
-use crate::pm::execution_observer::ExecutionObserver;
-use crate::stats::Stats;
-use crate::strategy::sum_arb::SumArbStrategy;
-use crate::strategy::Strategy;
-
-fn parse_opt_decimal(s: &Option<String>) -> anyhow::Result<Option<Decimal>> {
-    Ok(match s {
-        Some(v) if !v.trim().is_empty() => Some(v.trim().parse::<Decimal>()?),
-        _ => None,
-    })
-}
+use polymarket_arb_bot::config::{ParsedSettings, Settings};
+use polymarket_arb_bot::{bus, diagnostic, events, forensics, human, profile, scan, selftest, source};
+use polymarket_arb_bot::episodes::OpportunityEpisodes;
+use polymarket_arb_bot::events::SnapshotDiffer;
+use polymarket_arb_bot::history::PriceHistory;
+use polymarket_arb_bot::hot_markets::HotMarkets;
+use polymarket_arb_bot::http::HttpClient;
+use polymarket_arb_bot::jitter::Jitter;
+use polymarket_arb_bot::pipeline::{latest, LatestReceiver};
+use polymarket_arb_bot::poll::PollController;
+use polymarket_arb_bot::refresh::{RefreshOutcome, RefreshTrigger};
+use polymarket_arb_bot::pm::bundle_ledger::{BundleLedger, LedgerExecutor};
+use polymarket_arb_bot::pm::gamma::GammaClient;
+use polymarket_arb_bot::pm::market_data::{load_universe, save_universe, MarketData, MarketDef, MarketsEndpoint, TokenCollisionPolicy};
+use polymarket_arb_bot::pm::market_meta::MarketMetaCache;
+use polymarket_arb_bot::pm::market_score::{keep_top, ScoreWeights};
+use polymarket_arb_bot::pm::reference::{HttpReferenceFeed, ReferenceFeed};
+use polymarket_arb_bot::pm::execution_observer::ExecutionObserver;
+use polymarket_arb_bot::pm::execution_mock::{LegOutcome, MockExecutor};
+use polymarket_arb_bot::pm::executor::{BundleStatus, ExecutionMode, Executor};
+use polymarket_arb_bot::pm::intent_gate::{GatedExecutor, IntentGate};
+use polymarket_arb_bot::pm::prefilter::Prefilter;
+use polymarket_arb_bot::source::replay::ReplayExhausted;
+use polymarket_arb_bot::source::{DataSource, MarketDataSource};
+use polymarket_arb_bot::stats::Stats;
+use polymarket_arb_bot::strategy::fees::FeeModel;
+use polymarket_arb_bot::strategy::shadow::ShadowStrategy;
+use polymarket_arb_bot::strategy::sum_arb::SumArbStrategy;
+use polymarket_arb_bot::strategy::{CycleSummary, Strategy, StrategyContext};
+use polymarket_arb_bot::types::{category_bucket, GlobalSnapshot, OrderIntent, TimeInForce, TokenId};
 
 fn now_ms() -> u64 {
     chrono::Utc::now().timestamp_millis() as u64
 }
 
+/// A snapshot is thin when it is below the absolute floor or has collapsed relative
+/// to the last good snapshot.
+fn snapshot_is_thin(n: usize, prev_good: usize, min_markets: usize, min_ratio: f64) -> bool {
+    if n < min_markets {
+        return true;
+    }
+    min_ratio > 0.0 && prev_good > 0 && (n as f64) < min_ratio * prev_good as f64
+}
+
+/// Why a cycle's snapshot is not handed to the strategy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CycleSkip {
+    /// Too few of the requested books came back
+    LowCoverage,
+    /// Too few markets, see `snapshot_is_thin`
+    Thin,
+}
+
+/// Coverage is checked first: a partial outage also thins the snapshot, and the missing
+/// books are what explains it.
+fn cycle_skip(s: &Settings, snap: &GlobalSnapshot, markets: usize, prev_good: usize) -> Option<CycleSkip> {
+    if snap.coverage < s.min_snapshot_coverage {
+        Some(CycleSkip::LowCoverage)
+    } else if snapshot_is_thin(markets, prev_good, s.min_snapshot_markets, s.min_snapshot_ratio) {
+        Some(CycleSkip::Thin)
+    } else {
+        None
+    }
+}
+
+fn build_executor(s: &Settings, parsed: &ParsedSettings, resolved: profile::Resolved, stats: &Arc<Stats>) -> Result<Box<dyn Executor>> {
+    let ex: Box<dyn Executor> = match resolved.mode {
+        ExecutionMode::Observer => Box::new(ExecutionObserver::new(stats.clone(), s.exec_dedup_window_ms)),
+        ExecutionMode::Mock => {
+            let script = s.mock_leg_script.as_deref().unwrap_or("")
+                .split(',')
+                .filter(|x| !x.trim().is_empty())
+                .map(|x| x.parse::<LegOutcome>())
+                .collect::<Result<Vec<_>>>()?;
+            Box::new(MockExecutor::new(
+                std::time::Duration::from_millis(s.mock_leg_delay_ms),
+                std::time::Duration::from_millis(s.exec_leg_timeout_ms),
+                script,
+            )
+            .with_fill_slippage_bps(s.mock_fill_slippage_bps)
+            .with_leg_ordering(
+                s.leg_ordering,
+                std::time::Duration::from_millis(s.inter_leg_timeout_ms),
+                parsed.leg_min_fill_ratio,
+            )
+            .with_stranded_legs(
+                parsed.stranded_leg_policies.for_profile(resolved.profile),
+                std::time::Duration::from_millis(s.complete_or_flatten_ms),
+            ))
+        }
+    };
+    let ex: Box<dyn Executor> = match s.bundle_ledger_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        Some(path) => {
+            let ledger = BundleLedger::load(path, s.bundle_dedupe_ttl_sec, now_ms())?;
+            tracing::info!(path, entries = ledger.len(), ttl_sec = s.bundle_dedupe_ttl_sec, "bundle ledger loaded");
+            Box::new(LedgerExecutor::new(ex, ledger))
+        }
+        None => ex,
+    };
+    // outermost, so suppressed bundles never reach the ledger
+    if s.min_intent_interval_per_token_ms > 0 {
+        let gate = IntentGate::new(s.min_intent_interval_per_token_ms);
+        return Ok(Box::new(GatedExecutor::new(ex, gate, stats.clone())));
+    }
+    Ok(ex)
+}
+
+fn log_cycle_summary(summary: &CycleSummary, poll_ms: u64) {
+    let best = summary.best.as_ref();
+    tracing::info!(
+        snapshot_age_ms = summary.snapshot_age_ms,
+        markets_evaluated = summary.markets_evaluated,
+        skipped_too_few_legs = summary.skipped_too_few_legs,
+        skipped_too_many_legs = summary.skipped_too_many_legs,
+        skipped_missing_quote = summary.skipped_missing_quote,
+        skipped_leg_spread = summary.skipped_leg_spread,
+        skipped_leg_spread_pct = summary.skipped_leg_spread_pct,
+        skipped_leg_size = summary.skipped_leg_size,
+        skipped_anomaly = summary.skipped_anomaly,
+        skipped_partial_book = summary.skipped_partial_book,
+        skipped_resolved = summary.skipped_resolved,
+        skipped_suspected_glitch = summary.skipped_suspected_glitch,
+        skipped_implausible_sum_ask = summary.skipped_implausible_sum_ask,
+        skipped_stale_book = summary.skipped_stale_book,
+        skipped_reference_deviation = summary.skipped_reference_deviation,
+        skipped_no_size = summary.skipped_no_size,
+        skipped_rounded_to_zero = summary.skipped_rounded_to_zero,
+        skipped_below_min_order = summary.skipped_below_min_order,
+        below_edge = summary.below_edge,
+        near_arb_hits = summary.near_arb_hits,
+        opportunities = summary.opportunities,
+        capped = summary.capped,
+        budget_trimmed = summary.budget_trimmed,
+        budget_dropped = summary.budget_dropped,
+        collateral_trimmed = summary.collateral_trimmed,
+        collateral_dropped = summary.collateral_dropped,
+        intents = summary.intents,
+        best_edge_bps = %best.map(|b| b.edge_bps.to_string()).unwrap_or_default(),
+        best_sum_ask = %best.map(|b| b.sum_ask.to_string()).unwrap_or_default(),
+        best_market_id = %best.map(|b| b.market_id.as_str()).unwrap_or_default(),
+        best_question = %best.map(|b| b.question.as_str()).unwrap_or_default(),
+        poll_ms,
+        "cycle summary"
+    );
+    for (rank, (event_id, b)) in summary.event_leaderboard(5).into_iter().enumerate() {
+        tracing::info!(
+            rank = rank + 1,
+            event_id,
+            edge_bps = %b.edge_bps,
+            sum_ask = %b.sum_ask,
+            market_id = %b.market_id,
+            question = %b.question,
+            "event leaderboard"
+        );
+    }
+}
+
+/// One forensic file per emitted bundle; failures are logged and never stop the loop.
+async fn write_forensics(
+    dir: &std::path::Path,
+    md: &MarketData,
+    strat: &SumArbStrategy,
+    snap: &GlobalSnapshot,
+    intents: &[OrderIntent],
+) {
+    let mut bundle_ids: Vec<uuid::Uuid> = intents.iter().map(|i| i.bundle_id).collect();
+    bundle_ids.dedup();
+    for bundle_id in bundle_ids {
+        let legs: Vec<&OrderIntent> = intents.iter().filter(|i| i.bundle_id == bundle_id).collect();
+        let Some(market) = snap.markets.iter().find(|m| m.market_id == legs[0].market_id) else { continue };
+        let token_ids: Vec<TokenId> = market.outcomes.iter().map(|o| o.token_id.clone()).collect();
+        let quote = strat.quote(market).ok();
+        let record = forensics::BundleForensics {
+            bundle_id,
+            ts_ms: snap.ts_ms,
+            market,
+            gauges: market.outcomes.iter().map(|o| o.gauge()).collect(),
+            quote: quote.as_ref(),
+            intents: legs,
+            raw_books: md.raw_books(&token_ids),
+        };
+        match forensics::write_bundle(dir, &record).await {
+            Ok(path) => tracing::info!(bundle_id = %bundle_id, path = %path.display(), "forensics written"),
+            Err(e) => tracing::warn!(bundle_id = %bundle_id, err = %format!("{:#}", e), "forensics write failed"),
+        }
+    }
+}
+
 async fn maybe_write_jsonl(path: &Option<String>, line: &str) {
     if let Some(p) = path.as_ref().map(|x| x.trim().to_string()).filter(|x| !x.is_empty()) {
         if let Ok(mut f) = tokio::fs::OpenOptions::new().create(true).append(true).open(&p).await {
@@ -43,71 +207,1038 @@ async fn main() -> Result<()> {
         .with_env_filter(EnvFilter::from_default_env())
         .init();
 
-    let s = Settings::from_env()?;
-    let md = MarketData::new(s.clob_host.clone(), s.books_chunk_size, s.books_concurrency);
+    let (s, sources) = Settings::load()?;
+    let parsed = s.parsed()?;
+    if parsed.bundle_shares_deprecated {
+        tracing::warn!("MAX_BUNDLE_SIZE is deprecated, use MAX_BUNDLE_SHARES");
+    }
+    if std::env::args().nth(1).as_deref() == Some("scan-once") {
+        let result = scan::scan_once(&s).await?;
+        println!("{}", serde_json::to_string_pretty(&result)?);
+        return Ok(());
+    }
+    if s.strategy_parallelism > 0 {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(s.strategy_parallelism)
+            .thread_name(|i| format!("quote-{}", i))
+            .build_global()
+            .context("building the strategy thread pool")?;
+    }
+    let has_credentials = s.polymarket_private_key.as_ref().map(|k| !k.expose().trim().is_empty()).unwrap_or(false);
+    let resolved = profile::resolve(s.profile, s.execution_mode, s.live_armed, has_credentials, s.strict_profile)?;
+    tracing::info!(
+        version = env!("CARGO_PKG_VERSION"),
+        git_hash = env!("GIT_HASH"),
+        profile = %resolved.profile,
+        requested = %s.profile,
+        live_armed = s.live_armed,
+        execution_mode = ?resolved.mode,
+        source = %s.clob_host,
+        "==== polymarket-arb-bot starting: PROFILE={} ====", resolved.profile
+    );
+    let effective = s.effective(&sources);
+    tracing::info!(config = %effective, "effective configuration (value and source per setting)");
+    // recorded runs start with the configuration that produced them
+    let header = serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "git_hash": env!("GIT_HASH"),
+        "profile": resolved.profile.to_string(),
+        "execution_mode": resolved.mode,
+        "effective_config": effective,
+    });
+    maybe_write_jsonl(&s.stats_jsonl_path, &header.to_string()).await;
 
+    let jitter = Arc::new(match parsed.jitter_seed {
+        Some(seed) => Jitter::seeded(seed),
+        None => Jitter::from_entropy(),
+    });
+    let http = HttpClient::from_settings(&s, jitter.clone())?;
+    let clob_http = http.clone().with_rate_limit(s.clob_requests_per_sec, s.clob_burst);
     let stats = Stats::new(now_ms());
+    stats.set_profile(resolved.profile);
+    let replay = s.data_source == DataSource::Replay;
+    if replay {
+        // these read the venue, which a replay must not touch
+        for (on, setting) in [
+            (s.gamma_enabled, "GAMMA_ENABLED"),
+            (s.resolved_check_enabled, "RESOLVED_CHECK_ENABLED"),
+            (s.prefilter_enabled, "PREFILTER_ENABLED"),
+            (s.forensics_path.as_deref().is_some_and(|p| !p.trim().is_empty()), "FORENSICS_PATH"),
+            (s.markets_cache_path.as_deref().is_some_and(|p| !p.trim().is_empty()), "MARKETS_CACHE_PATH"),
+        ] {
+            if on {
+                tracing::warn!(setting, "ignored with DATA_SOURCE=replay");
+            }
+        }
+    }
+    let forensics_dir = s.forensics_path.as_deref().map(str::trim).filter(|p| !p.is_empty() && !replay).map(std::path::PathBuf::from);
+    let md = MarketData::from_settings(&s, clob_http.clone(), forensics_dir.is_some(), stats.clone());
 
-    let mut markets: Vec<MarketDef> = vec![];
-    let mut last_refresh = std::time::Instant::now()
-        .checked_sub(std::time::Duration::from_secs(3600))
-        .unwrap_or_else(std::time::Instant::now);
-
-    let strat = SumArbStrategy {
-        min_edge_bps: s.min_edge_bps,
-        warn_edge_bps: s.warn_edge_bps,
-        fee_bps: s.fee_bps,
-        max_bundle_size: s.max_bundle_size.parse::<Decimal>()?,
-        max_leg_spread: parse_opt_decimal(&s.max_leg_spread)?,
-        min_leg_size: parse_opt_decimal(&s.min_leg_size)?,
-        stats: stats.clone(),
-    };
+    if !(s.require_order_book && s.require_accepting_orders && s.skip_closed_markets) {
+        tracing::warn!(
+            require_order_book = s.require_order_book,
+            require_accepting_orders = s.require_accepting_orders,
+            skip_closed_markets = s.skip_closed_markets,
+            "market eligibility relaxed: markets without a CLOB book show as missing books, and closed or paused ones cannot fill"
+        );
+    }
+    if s.no_side_arb && s.pm_markets_endpoint != MarketsEndpoint::Full {
+        tracing::warn!(endpoint = ?s.pm_markets_endpoint, "NO_SIDE_ARB=true but the simplified listings carry no neg-risk flag, so no NO baskets are built; set PM_MARKETS_ENDPOINT=full");
+    }
+    if s.no_side_arb && s.token_collision_policy == TokenCollisionPolicy::DropLater {
+        tracing::warn!("NO_SIDE_ARB=true with TOKEN_COLLISION_POLICY=drop_later: NO baskets reuse their binary markets' NO tokens and are dropped; set TOKEN_COLLISION_POLICY=share");
+    }
+    let data = source::from_settings(&s, &md)?;
 
-    let ex = ExecutionObserver::new();
+    let fees = Arc::new(FeeModel::tiered(
+        s.fee_bps,
+        parsed.fee_tiers.clone(),
+        parsed.fee_seed_volume,
+        now_ms(),
+    ));
+    tracing::info!(fee_bps = fees.fee_bps(), "fee tier at start");
 
-    loop {
-        let refresh_due = markets.is_empty()
-            || (s.markets_refresh_sec > 0
-                && last_refresh.elapsed() >= std::time::Duration::from_secs(s.markets_refresh_sec));
+    if s.leg_post_only && s.leg_tif != TimeInForce::Gtc {
+        tracing::warn!(leg_tif = ?s.leg_tif, "LEG_POST_ONLY needs LEG_TIF=GTC (a post-only order cannot fill immediately); ignoring");
+    }
+    let strat = SumArbStrategy::from_settings(&s, &parsed, fees.clone(), stats.clone());
+    let shadow = s.shadow_mode.then(|| {
+        let candidate = SumArbStrategy {
+            min_edge_bps: s.shadow_min_edge_bps.unwrap_or(s.min_edge_bps),
+            pricing: s.shadow_pricing_model.unwrap_or(s.pricing_model).model(),
+            sum_ask_ema: Default::default(),
+            stats: Stats::new(now_ms()),
+            ..strat.clone()
+        };
+        tracing::info!(
+            min_edge_bps = candidate.min_edge_bps,
+            pricing_model = candidate.pricing.name(),
+            "shadow strategy enabled"
+        );
+        ShadowStrategy::new(Box::new(candidate), stats.clone())
+    });
 
-        if refresh_due {
-            tracing::info!(max_markets=s.max_markets, "refreshing open markets");
-            markets = md.fetch_open_markets(s.max_markets).await?;
-            last_refresh = std::time::Instant::now();
-            tracing::info!(count=markets.len(), "open markets loaded");
-            stats.set_markets_loaded(markets.len() as u64);
+    if std::env::args().nth(1).as_deref() == Some("selftest") {
+        let results = selftest::run(&s, &clob_http, &md).await;
+        if !selftest::print_report(&results) {
+            std::process::exit(1);
         }
+        return Ok(());
+    }
 
-        let snap = md.snapshot_for_markets(&markets).await?;
-        stats.inc_heartbeat();
-        stats.set_markets_in_snapshot(snap.markets.len() as u64);
+    if std::env::args().any(|a| a == "--validate-markets")
+        || std::env::var("DIAGNOSTIC").map(|v| v == "1").unwrap_or(false)
+    {
+        let markets = md.fetch_open_markets(s.max_markets).await?;
+        let reports = diagnostic::validate_markets(&md, &strat, &markets).await?;
+        diagnostic::print_report(&reports);
+        return Ok(());
+    }
 
-        tracing::info!(markets=snap.markets.len(), ts=snap.ts_ms, "heartbeat: snapshot fetched");
+    let ex = build_executor(&s, &parsed, resolved, &stats)?;
+    tracing::info!(
+        profile = %resolved.profile,
+        execution_mode = ?resolved.mode,
+        stranded_leg_policy = ?parsed.stranded_leg_policies.for_profile(resolved.profile),
+        "executor selected"
+    );
+    let refresh_trigger = Arc::new(RefreshTrigger::default());
+    #[cfg(feature = "api")]
+    let api_state = {
+        let st = polymarket_arb_bot::api::SharedState::default();
+        if s.api_port > 0 {
+            polymarket_arb_bot::api::spawn(s.api_port, st.clone(), refresh_trigger.clone()).await?;
+        }
+        st
+    };
+    #[cfg(not(feature = "api"))]
+    if s.api_port > 0 {
+        tracing::warn!(api_port = s.api_port, "API_PORT set but built without the api feature; ignoring");
+    }
 
-        let intents = strat.on_snapshot(&snap);
-        ex.execute(intents).await?;
+    let market_meta = if s.resolved_check_enabled && !replay {
+        Some(Arc::new(MarketMetaCache::new(
+            s.clob_host.clone(),
+            clob_http.clone(),
+            s.market_meta_ttl_sec,
+            parsed.resolved_suspect_px,
+            s.resolved_max_checks,
+        )))
+    } else {
+        None
+    };
+    let reference = s.reference_feed_url.as_deref().map(str::trim).filter(|u| !u.is_empty()).map(|url| {
+        tracing::info!(url, max_deviation_bps = s.max_reference_deviation_bps, "reference price check enabled");
+        Arc::new(HttpReferenceFeed::new(url.to_string(), http.clone(), s.reference_max_age_sec))
+    });
+    let reference_task = reference.clone().map(|f| f.spawn(s.reference_poll_sec));
+    let gamma = (s.gamma_enabled && !replay).then(|| {
+        GammaClient::new(
+            s.gamma_host.clone(),
+            http.clone().with_rate_limit(s.gamma_requests_per_sec, s.gamma_burst),
+            s.gamma_cache_ttl_sec,
+            s.gamma_max_pages,
+        )
+    });
+    let excluded_events: std::collections::HashSet<String> = s.exclude_event_ids.as_deref().unwrap_or("")
+        .split(',')
+        .map(|x| x.trim().to_string())
+        .filter(|x| !x.is_empty())
+        .collect();
+    if !excluded_events.is_empty() && gamma.is_none() {
+        tracing::warn!("EXCLUDE_EVENT_IDS set but GAMMA_ENABLED=false; markets carry no event ids, nothing is excluded");
+    }
+    let categories = |v: &Option<String>| -> std::collections::HashSet<String> {
+        v.as_deref().unwrap_or("").split(',').filter(|x| !x.trim().is_empty()).map(|x| category_bucket(Some(x))).collect()
+    };
+    let event_bus = bus::EventBus::new(stats.clone());
+    let mut consumers = vec![bus::spawn_execution_log(&event_bus)];
+    if let Some(path) = s.stats_jsonl_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        consumers.push(bus::spawn_stats_journal(&event_bus, path.to_string()));
+    }
+    if let Some(path) = s.episodes_jsonl_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        consumers.push(bus::spawn_episode_journal(&event_bus, path.to_string()));
+    }
+    if let Some(path) = s.executions_jsonl_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        consumers.push(bus::spawn_execution_journal(&event_bus, path.to_string()));
+    }
+    if let Some(path) = s.stats_csv_path.as_deref().map(str::trim).filter(|p| !p.is_empty()) {
+        consumers.push(bus::spawn_stats_csv(&event_bus, path.to_string()));
+    }
+    let mut events = match s.events_bind_addr.as_deref().map(str::trim).filter(|a| !a.is_empty()) {
+        Some(addr) => Some((events::spawn(addr).await?, SnapshotDiffer::default())),
+        None => None,
+    };
+    let hot = Arc::new(std::sync::Mutex::new(HotMarkets::new(s.hot_markets_capacity)));
+    let mut episodes = OpportunityEpisodes::new();
+    let mut refresher = MarketRefresher {
+        source: data.clone(),
+        gamma,
+        excluded_events,
+        include_categories: categories(&s.include_categories),
+        exclude_categories: categories(&s.exclude_categories),
+        max_markets: s.max_markets,
+        scoring: s.market_scoring.then_some(ScoreWeights {
+            liquidity: s.market_score_w_liquidity,
+            volume: s.market_score_w_volume,
+            legs: s.market_score_w_legs,
+            hits: s.market_score_w_hits,
+        }),
+        score_candidates: s.market_score_candidates,
+        hot: hot.clone(),
+        redemption_values: parsed.redemption_values.clone(),
+        cache_path: s.markets_cache_path.as_deref().map(str::trim).filter(|p| !p.is_empty() && !replay).map(std::path::PathBuf::from),
+        known: Default::default(),
+        stats: stats.clone(),
+    };
+    // without a saved list the first one is fetched up front so a bad host fails startup;
+    // later refreshes run in the background and keep the previous list on failure
+    let (initial, reconcile_now) = match refresher.load_cached() {
+        Some(cached) => (cached, true),
+        None => (refresher.refresh().await?, false),
+    };
+    let (lists, markets) = tokio::sync::watch::channel(Arc::new(initial));
+    let refresh_task = refresher.spawn(s.markets_refresh_sec, reconcile_now, lists, refresh_trigger);
+    let fetcher = Fetcher::new(data, md.clone(), markets, market_meta.clone(), (s.prefilter_enabled && !replay).then_some(Prefilter {
+        basis: s.prefilter_basis,
+        margin_bps: s.prefilter_margin_bps,
+    }), stats.clone());
+    let mut shutdown = std::pin::pin!(shutdown_signal());
+    let mut prev_good_markets: usize = 0;
+    let mut universe: u64 = 0;
+    let mut history = PriceHistory::new(s.history_depth, s.history_evict_cycles);
+    let mut poll = if s.poll_adaptive {
+        PollController::adaptive(s.poll_ms, s.poll_ms_min, s.poll_ms_max, s.poll_quiet_cycles)
+    } else {
+        PollController::fixed(s.poll_ms)
+    }
+    .with_jitter(s.poll_jitter_pct, jitter);
+    let mut source = if s.pipeline_enabled {
+        tracing::info!("pipelined: fetching the next snapshot while the current one is evaluated");
+        SnapshotSource::pipelined(fetcher, poll.sleep_duration(), stats.clone())
+    } else {
+        SnapshotSource::Inline(Box::new(fetcher))
+    };
+    // an executor error ends the run, after the normal shutdown so the bus consumers see it
+    let mut failed: Option<anyhow::Error> = None;
+
+    loop {
+        let Fetched { snap, coverage, universe: snap_universe } = match source.next().await {
+            Ok(f) => f,
+            Err(e) if e.downcast_ref::<ReplayExhausted>().is_some() => {
+                tracing::info!("{}", e);
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        if snap_universe != universe {
+            // the universe changed, so the last good snapshot size no longer applies
+            universe = snap_universe;
+            prev_good_markets = 0;
+        }
+
+        let skip = cycle_skip(&s, &snap, coverage, prev_good_markets);
+        if skip == Some(CycleSkip::LowCoverage) {
+            stats.inc_cycle_low_coverage();
+            tracing::warn!(
+                coverage = format!("{:.3}", snap.coverage),
+                min_snapshot_coverage = s.min_snapshot_coverage,
+                markets = coverage,
+                "low book coverage: too few requested books returned, skipping strategy this cycle"
+            );
+            poll.observe(false);
+        } else if skip == Some(CycleSkip::Thin) {
+            stats.inc_degraded_snapshot();
+            tracing::warn!(
+                markets = coverage,
+                prev_good_markets,
+                min_snapshot_markets = s.min_snapshot_markets,
+                min_snapshot_ratio = s.min_snapshot_ratio,
+                "degraded snapshot: too few markets, skipping strategy this cycle"
+            );
+            poll.observe(false);
+        } else {
+            prev_good_markets = coverage;
+            history.record(&snap);
+            let ctx = StrategyContext {
+                history: &history,
+                meta: market_meta.as_deref(),
+                reference: reference.as_deref().map(|f| f as &dyn ReferenceFeed),
+            };
+            let (intents, summary) = strat.on_snapshot(&snap, &ctx);
+            if let Some(shadow) = &shadow {
+                shadow.compare(&snap, &ctx, &intents);
+            }
+            if s.human_output && !intents.is_empty() {
+                print!("{}", human::render_table(&scan::opportunity_records(&snap, &intents)));
+            }
+            hot.lock().unwrap_or_else(|e| e.into_inner()).observe(&summary.hits);
+            if s.pm_markets_endpoint != MarketsEndpoint::Full && !replay {
+                let unnamed: Vec<String> = summary.hits.iter()
+                    .filter(|h| (h.near_arb || h.opportunity) && h.question.is_empty())
+                    .map(|h| h.market_id.clone())
+                    .collect();
+                if !unnamed.is_empty() {
+                    let md = md.clone();
+                    tokio::spawn(async move { md.backfill_questions(&unnamed).await });
+                }
+            }
+            for ep in episodes.observe(snap.ts_ms, &summary.hits) {
+                stats.add_opportunity_episode(ep.duration_ms as u64);
+                event_bus.publish(bus::Event::OpportunityEpisode(ep));
+            }
+            poll.observe(summary.near_arb_hits > 0 || summary.opportunities > 0);
+            if s.cycle_summary_log || summary.is_interesting() {
+                log_cycle_summary(&summary, poll.current_ms());
+            }
+            if let Some((bus, differ)) = &mut events {
+                bus.publish(&differ.diff(&snap, &intents));
+            }
+            #[cfg(feature = "api")]
+            {
+                let mut cycle = polymarket_arb_bot::api::LastCycleState::from_cycle(&snap, &intents);
+                cycle.hot_markets = hot.lock().unwrap_or_else(|e| e.into_inner()).top(s.hot_markets_top_n);
+                *api_state.write().unwrap_or_else(|e| e.into_inner()) = cycle;
+            }
+            if let Some(dir) = &forensics_dir {
+                write_forensics(dir, &md, &strat, &snap, &intents).await;
+            }
+            let results = match ex.execute(intents).await {
+                Ok(r) => r,
+                Err(e) => {
+                    event_bus.publish(bus::Event::ExecutionError { ts_ms: now_ms() as i64, error: format!("{:#}", e) });
+                    failed = Some(e);
+                    break;
+                }
+            };
+            let fee_before = fees.fee_bps();
+            for r in &results {
+                if let Some(slip) = r.slippage_bps() {
+                    stats.record_slippage(slip);
+                }
+                if let Some(notional) = r.fill_cost {
+                    fees.record_fill(notional, now_ms());
+                }
+                if r.status == BundleStatus::Flattened {
+                    stats.inc_bundles_flattened();
+                }
+            }
+            let volume_30d = fees.refresh(now_ms());
+            if fees.fee_bps() != fee_before {
+                tracing::info!(from_bps = fee_before, to_bps = fees.fee_bps(), volume_30d = %volume_30d, "fee tier changed");
+            }
+            for r in results {
+                event_bus.publish(bus::Event::BundleExecuted(r));
+            }
+        }
+
+        stats.set_poll_ms(poll.current_ms());
 
         // stats summary
         let t = now_ms();
         if stats.should_log(t, s.stats_log_sec) {
             let ss = stats.snapshot(t);
-            stats.mark_logged(t);
+            stats.mark_logged(&ss);
 
-            let line = serde_json::to_string(&ss).unwrap_or_default();
             tracing::info!(
+                profile = %resolved.profile,
                 up_sec = ss.up_sec,
+                interval_sec = ss.interval_sec,
                 heartbeats = ss.heartbeats,
+                heartbeats_interval = ss.heartbeats_interval,
                 markets_loaded = ss.markets_loaded,
                 markets_in_snapshot = ss.markets_in_snapshot,
+                degraded_snapshots = ss.degraded_snapshots,
+                degraded_snapshots_interval = ss.degraded_snapshots_interval,
+                cycles_low_coverage = ss.cycles_low_coverage,
                 near_arb_hits = ss.near_arb_hits,
+                near_arb_hits_interval = ss.near_arb_hits_interval,
                 opportunities = ss.opportunities,
+                opportunities_interval = ss.opportunities_interval,
                 intents_emitted = ss.intents_emitted,
+                intents_emitted_interval = ss.intents_emitted_interval,
+                bundles_deduplicated = ss.bundles_deduplicated,
+                bundles_deduplicated_interval = ss.bundles_deduplicated_interval,
+                bundles_suppressed_churn = ss.bundles_suppressed_churn,
+                bundles_suppressed_churn_interval = ss.bundles_suppressed_churn_interval,
+                opportunity_episodes = ss.opportunity_episodes,
+                opportunity_episodes_interval = ss.opportunity_episodes_interval,
+                episode_median_ms_interval = ?ss.episode_median_ms_interval,
+                books_missing_tokens = ss.books_missing_tokens,
+                book_id_mismatches = ss.book_id_mismatches,
+                legs_filled_from_cache = ss.legs_filled_from_cache,
+                books_fetched = ss.books_fetched,
+                books_fetched_interval = ss.books_fetched_interval,
+                books_missed = ss.books_missed,
+                books_missed_interval = ss.books_missed_interval,
+                per_min_interval = %serde_json::to_string(&ss.per_min_interval).unwrap_or_default(),
+                token_collisions = ss.token_collisions,
+                book_fallback_fetches = ss.book_fallback_fetches,
+                book_fallback_misses = ss.book_fallback_misses,
+                hedges_fired = ss.hedges_fired,
+                hedges_won = ss.hedges_won,
+                skipped_label_mismatch = ss.skipped_label_mismatch,
+                skipped_multi_outcome = ss.skipped_multi_outcome,
+                markets_resolved_detected = ss.markets_resolved_detected,
+                markets_refresh_age_sec = ss.markets_refresh_age_sec,
+                markets_refresh_failures = ss.markets_refresh_failures,
+                markets_added = ss.markets_added,
+                markets_removed = ss.markets_removed,
+                shadow_primary_only = ss.shadow_primary_only,
+                shadow_only = ss.shadow_only,
+                snapshots_dropped_stale = ss.snapshots_dropped_stale,
+                max_snapshot_age_ms = ss.max_snapshot_age_ms,
+                snapshot_age_hist = ?ss.snapshot_age_hist,
+                poll_ms = ss.poll_ms,
+                slippage_bundles = ss.slippage_bundles,
+                avg_slippage_bps = ss.avg_slippage_bps,
+                bundles_flattened = ss.bundles_flattened,
+                by_category = %serde_json::to_string(&ss.by_category).unwrap_or_default(),
+                skipped_by_reason = %serde_json::to_string(&ss.skipped_by_reason).unwrap_or_default(),
+                bus_events_dropped = ss.bus_events_dropped,
                 "stats"
             );
+            event_bus.publish(bus::Event::Stats(Box::new(ss)));
+
+            let hot = hot.lock().unwrap_or_else(|e| e.into_inner());
+            if s.hot_markets_top_n > 0 && !hot.is_empty() {
+                let top = hot.top(s.hot_markets_top_n);
+                tracing::info!(
+                    tracked = hot.len(),
+                    top = %top.iter()
+                        .map(|m| format!("{}:{}/{}", m.market_id, m.opportunities, m.near_arbs))
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    "hot markets (market:opportunities/near_arbs)"
+                );
+            }
+        }
+
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = source.pace(poll.sleep_duration()) => {}
+        }
+    }
+    source.stop();
+    for t in [Some(refresh_task), reference_task].into_iter().flatten() {
+        t.abort();
+    }
+
+    tracing::info!(timeout_ms = s.shutdown_timeout_ms, "shutting down, stopping executor");
+    match tokio::time::timeout(std::time::Duration::from_millis(s.shutdown_timeout_ms), ex.shutdown()).await {
+        Ok(Ok(r)) if r.not_cancelled.is_empty() => {
+            tracing::info!(cancelled = r.cancelled.len(), "executor stopped cleanly");
+        }
+        Ok(Ok(r)) => {
+            tracing::error!(cancelled = r.cancelled.len(), not_cancelled = ?r.not_cancelled, "orders left open, cancel manually");
+        }
+        Ok(Err(e)) => tracing::error!(err = %format!("{:#}", e), "executor shutdown failed; check for open orders"),
+        Err(_) => tracing::error!("executor shutdown timed out; check for open orders"),
+    }
+    // a last interval, so the journals cover the tail of the run
+    let ss = stats.snapshot(now_ms());
+    stats.mark_logged(&ss);
+    event_bus.publish(bus::Event::Stats(Box::new(ss)));
+    // consumers drain what is buffered once the last bus handle is gone
+    drop(event_bus);
+    for c in consumers {
+        let _ = c.await;
+    }
+    match failed {
+        Some(e) => Err(e),
+        None => Ok(()),
+    }
+}
+
+/// One fetched snapshot, ready for evaluation.
+struct Fetched {
+    snap: GlobalSnapshot,
+    /// Markets the thin-snapshot guard counts
+    coverage: usize,
+    /// Bumped on every market refresh
+    universe: u64,
+}
+
+/// Builds the market list: open CLOB markets, Gamma-tagged, event- and category-filtered.
+struct MarketRefresher {
+    source: Arc<dyn MarketDataSource>,
+    gamma: Option<GammaClient>,
+    excluded_events: std::collections::HashSet<String>,
+    /// Category buckets to keep (empty keeps all); uncategorized markets always pass
+    include_categories: std::collections::HashSet<String>,
+    exclude_categories: std::collections::HashSet<String>,
+    max_markets: usize,
+    /// Rank candidates and keep the top max_markets; None keeps the first ones fetched
+    scoring: Option<ScoreWeights>,
+    /// Candidates fetched when scoring (0: no limit)
+    score_candidates: usize,
+    /// Past near-arbs and opportunities per market, a scoring input
+    hot: Arc<std::sync::Mutex<HotMarkets>>,
+    /// REDEMPTION_VALUES overrides, by market id
+    redemption_values: std::collections::HashMap<String, rust_decimal::Decimal>,
+    /// Where the list is saved after each refresh, for a fast start
+    cache_path: Option<std::path::PathBuf>,
+    /// Market ids of the last list, to count markets added and removed
+    known: std::collections::HashSet<String>,
+    stats: Arc<Stats>,
+}
+
+impl MarketRefresher {
+    /// The saved list, if there is a usable one.
+    fn load_cached(&mut self) -> Option<Vec<MarketDef>> {
+        let path = self.cache_path.as_ref()?;
+        match load_universe(path) {
+            Ok(Some(markets)) if !markets.is_empty() => {
+                tracing::info!(path = %path.display(), count = markets.len(), "market list loaded from cache, reconciling in the background");
+                self.stats.set_markets_loaded(markets.len() as u64);
+                self.known = markets.iter().map(|m| m.market_id.clone()).collect();
+                Some(markets)
+            }
+            Ok(_) => None,
+            Err(e) => {
+                tracing::warn!(err = %format!("{:#}", e), "market list cache unusable, doing a full refresh");
+                None
+            }
+        }
+    }
+
+    async fn refresh(&mut self) -> Result<Vec<MarketDef>> {
+        tracing::info!(max_markets = self.max_markets, scoring = self.scoring.is_some(), "refreshing open markets");
+        let fetch = match (self.scoring, self.score_candidates) {
+            (None, _) => self.max_markets,
+            (Some(_), 0) => usize::MAX,
+            (Some(_), n) => n.max(self.max_markets),
+        };
+        let mut markets = self.source.fetch_open_markets(fetch).await?;
+        for m in markets.iter_mut() {
+            if let Some(v) = self.redemption_values.get(&m.market_id) {
+                m.redemption_value = Some(*v);
+            }
+        }
+        if let Some(g) = &self.gamma {
+            let tagged = g.tag_markets(&mut markets).await;
+            let before = markets.len();
+            markets.retain(|m| m.event_id.as_ref().map(|e| !self.excluded_events.contains(e)).unwrap_or(true));
+            tracing::info!(tagged, excluded = before - markets.len(), "gamma event tags applied");
+        }
+        if !self.include_categories.is_empty() || !self.exclude_categories.is_empty() {
+            let before = markets.len();
+            markets.retain(|m| {
+                let bucket = category_bucket(m.category.as_deref());
+                !self.exclude_categories.contains(&bucket)
+                    && (self.include_categories.is_empty() || bucket == "uncategorized" || self.include_categories.contains(&bucket))
+            });
+            tracing::info!(excluded = before - markets.len(), "category filter applied");
+        }
+        if let Some(w) = self.scoring {
+            let hot = self.hot.lock().unwrap_or_else(|e| e.into_inner());
+            let (kept, sc) = keep_top(markets, self.max_markets, |m| w.score(m, hot.hits(&m.market_id)));
+            markets = kept;
+            tracing::info!(
+                candidates = sc.candidates,
+                kept = sc.kept,
+                min = format!("{:.3}", sc.min),
+                median = format!("{:.3}", sc.median),
+                max = format!("{:.3}", sc.max),
+                cutoff = sc.cutoff.map(|c| format!("{:.3}", c)),
+                "market scores"
+            );
+        }
+        tracing::info!(count = markets.len(), "open markets loaded");
+        self.stats.set_markets_loaded(markets.len() as u64);
+        self.stats.mark_markets_refreshed(now_ms());
+
+        let ids: std::collections::HashSet<String> = markets.iter().map(|m| m.market_id.clone()).collect();
+        if !self.known.is_empty() {
+            let added = ids.difference(&self.known).count();
+            let removed = self.known.difference(&ids).count();
+            self.stats.add_markets_churn(added as u64, removed as u64);
+            if added + removed > 0 {
+                tracing::info!(added, removed, "market list changed");
+            }
+        }
+        self.known = ids;
+        if let Some(path) = &self.cache_path {
+            if let Err(e) = save_universe(path, &markets) {
+                tracing::warn!(err = %format!("{:#}", e), "market list cache write failed");
+            }
+        }
+        Ok(markets)
+    }
+
+    /// Publishes a fresh list every `every_sec` (0: never) and whenever `trigger` asks
+    /// until aborted, and right away when `now` is set (a cached start). A failed refresh
+    /// keeps the previous list; its age shows in the stats.
+    fn spawn(
+        mut self,
+        every_sec: u64,
+        now: bool,
+        lists: tokio::sync::watch::Sender<Arc<Vec<MarketDef>>>,
+        trigger: Arc<RefreshTrigger>,
+    ) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut first = now;
+            loop {
+                if !first {
+                    let tick = async {
+                        match every_sec {
+                            0 => std::future::pending().await,
+                            n => tokio::time::sleep(std::time::Duration::from_secs(n)).await,
+                        }
+                    };
+                    tokio::select! {
+                        _ = tick => {}
+                        _ = trigger.requested() => {}
+                    }
+                }
+                first = false;
+                match self.refresh().await {
+                    Ok(markets) => {
+                        trigger.finished(RefreshOutcome::Done(markets.len()));
+                        lists.send_replace(Arc::new(markets));
+                    }
+                    Err(e) => {
+                        self.stats.inc_markets_refresh_failure();
+                        trigger.finished(RefreshOutcome::Failed(format!("{:#}", e)));
+                        tracing::warn!(err = %format!("{:#}", e), "market refresh failed, keeping the previous list");
+                    }
+                }
+            }
+        })
+    }
+}
+
+/// Turns the current market list into snapshots and drops markets the venue reports
+/// resolved. Picks up a new list from the refresher before the next fetch.
+struct Fetcher {
+    source: Arc<dyn MarketDataSource>,
+    /// Venue client for prefilter prices
+    md: MarketData,
+    lists: tokio::sync::watch::Receiver<Arc<Vec<MarketDef>>>,
+    market_meta: Option<Arc<MarketMetaCache>>,
+    prefilter: Option<Prefilter>,
+    markets: Vec<MarketDef>,
+    universe: u64,
+    stats: Arc<Stats>,
+}
 
-            maybe_write_jsonl(&s.stats_jsonl_path, &line).await;
+impl Fetcher {
+    fn new(
+        source: Arc<dyn MarketDataSource>,
+        md: MarketData,
+        mut lists: tokio::sync::watch::Receiver<Arc<Vec<MarketDef>>>,
+        market_meta: Option<Arc<MarketMetaCache>>,
+        prefilter: Option<Prefilter>,
+        stats: Arc<Stats>,
+    ) -> Self {
+        let markets = lists.borrow_and_update().as_ref().clone();
+        Self { source, md, lists, market_meta, prefilter, markets, universe: 1, stats }
+    }
+
+    async fn next(&mut self) -> Result<Fetched> {
+        // a closed channel (no periodic refresh) just keeps the current list
+        if self.lists.has_changed().unwrap_or(false) {
+            self.markets = self.lists.borrow_and_update().as_ref().clone();
+            if let Some(mm) = &self.market_meta {
+                mm.clear();
+            }
+            self.universe += 1;
         }
 
-        tokio::time::sleep(std::time::Duration::from_millis(s.poll_ms)).await;
+        // with the prefilter on, coverage is judged on priced markets since the snapshot
+        // only holds the promoted ones
+        let (snap, coverage) = match &self.prefilter {
+            Some(pf) => {
+                let prices = self.md.fetch_prices(&self.markets, pf.basis).await?;
+                let promoted = pf.promote(&self.markets, &prices);
+                tracing::debug!(priced = promoted.priced, promoted = promoted.markets.len(), basis = ?pf.basis, "prefilter");
+                (self.source.snapshot_for_markets(&promoted.markets).await?, promoted.priced)
+            }
+            None => {
+                let snap = self.source.snapshot_for_markets(&self.markets).await?;
+                // partial markets are not coverage: nothing can be executed on them
+                let n = snap.markets.iter().filter(|m| !m.partial).count();
+                (snap, n)
+            }
+        };
+        self.stats.inc_heartbeat();
+        self.stats.set_markets_in_snapshot(snap.markets.len() as u64);
+
+        tracing::info!(markets = snap.markets.len(), ts = snap.ts_ms, "heartbeat: snapshot fetched");
+
+        if let Some(mm) = &self.market_meta {
+            let resolved = mm.detect_resolved(&snap, &self.stats).await;
+            if !resolved.is_empty() {
+                self.markets.retain(|m| !resolved.contains(&m.market_id));
+            }
+        }
+
+        Ok(Fetched { snap, coverage, universe: self.universe })
+    }
+}
+
+/// Where the main loop gets snapshots: fetched inline between evaluations, or by a task
+/// that fetches the next one while the current one is evaluated. Pipelined snapshots the
+/// loop was too busy to take are replaced by newer ones and counted as stale.
+enum SnapshotSource {
+    Inline(Box<Fetcher>),
+    Pipelined {
+        rx: LatestReceiver<Fetched>,
+        /// Sleep between fetches, following the loop's poll controller
+        pace: tokio::sync::watch::Sender<std::time::Duration>,
+        task: tokio::task::JoinHandle<Result<()>>,
+    },
+}
+
+impl SnapshotSource {
+    fn pipelined(mut fetcher: Fetcher, initial_sleep: std::time::Duration, stats: Arc<Stats>) -> Self {
+        let (tx, rx) = latest();
+        let (pace, pace_rx) = tokio::sync::watch::channel(initial_sleep);
+        let task = tokio::spawn(async move {
+            loop {
+                let f = fetcher.next().await?;
+                if tx.publish(f) {
+                    stats.add_snapshots_dropped_stale(1);
+                    tracing::debug!("evaluation behind fetching, stale snapshot dropped");
+                }
+                let sleep = *pace_rx.borrow();
+                tokio::time::sleep(sleep).await;
+            }
+        });
+        SnapshotSource::Pipelined { rx, pace, task }
+    }
+
+    async fn next(&mut self) -> Result<Fetched> {
+        match self {
+            SnapshotSource::Inline(f) => f.next().await,
+            SnapshotSource::Pipelined { rx, task, .. } => match rx.recv().await {
+                Some(f) => Ok(f),
+                // the fetcher only stops on error
+                None => match task.await {
+                    Ok(Err(e)) => Err(e),
+                    Ok(Ok(())) => anyhow::bail!("snapshot fetcher stopped"),
+                    Err(e) => Err(anyhow::anyhow!("snapshot fetcher panicked: {}", e)),
+                },
+            },
+        }
+    }
+
+    /// Waits before the next cycle: the poll sleep inline; pipelined, until the fetcher
+    /// has a snapshot ready (it sleeps `sleep` between fetches itself).
+    async fn pace(&mut self, sleep: std::time::Duration) {
+        match self {
+            SnapshotSource::Inline(_) => tokio::time::sleep(sleep).await,
+            SnapshotSource::Pipelined { rx, pace, .. } => {
+                pace.send_replace(sleep);
+                rx.ready().await;
+            }
+        }
+    }
+
+    fn stop(&self) {
+        if let SnapshotSource::Pipelined { task, .. } = self {
+            task.abort();
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or SIGTERM on unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        let mut term = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(t) => t,
+            Err(e) => {
+                tracing::warn!(err = %e, "SIGTERM handler unavailable, only Ctrl-C stops the bot gracefully");
+                let _ = tokio::signal::ctrl_c().await;
+                return;
+            }
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => {}
+            _ = term.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polymarket_arb_bot::pm::executor::BundleResult;
+    use polymarket_arb_bot::profile::Profile;
+    use polymarket_arb_bot::types::{IntentMeta, Side};
+    use rust_decimal::Decimal;
+
+    fn settings(overrides: serde_json::Value) -> Settings {
+        let mut v = serde_json::json!({
+            "clob_host": "http://127.0.0.1:1",
+            "poll_ms": 1000,
+            "max_markets": 200,
+            "markets_refresh_sec": 0,
+            "books_chunk_size": 100,
+            "books_concurrency": 1,
+            "fee_bps": 0,
+            "min_edge_bps": 30,
+            "warn_edge_bps": 150,
+            "stats_log_sec": 60,
+            "max_bundle_shares": "100",
+            "exec_dedup_window_ms": 0,
+        });
+        if let (Some(base), serde_json::Value::Object(extra)) = (v.as_object_mut(), overrides) {
+            base.extend(extra);
+        }
+        serde_json::from_value(v).unwrap()
+    }
+
+    fn two_legs(bundle: u128) -> Vec<OrderIntent> {
+        let bundle_id = uuid::Uuid::from_u128(bundle);
+        ["1", "2"].iter().enumerate().map(|(k, token)| {
+            let meta = IntentMeta { strategy: "test".into(), leg_index: k, leg_count: 2, ..Default::default() };
+            OrderIntent {
+                market_id: "m".into(),
+                token_id: TokenId::from(*token),
+                side: Side::Buy,
+                price: Decimal::new(45, 2),
+                size: Decimal::TEN,
+                reason: meta.summary(Decimal::TEN),
+                bundle_id,
+                tif: TimeInForce::Fok,
+                post_only: false,
+                avail_size: None,
+                meta,
+            }
+        }).collect()
+    }
+
+    /// Builds the executor `main` would for `requested` and `mode`, and runs one bundle
+    /// through it.
+    async fn run_one(s: &Settings, requested: Profile, mode: ExecutionMode) -> (Box<dyn Executor>, Vec<BundleResult>) {
+        let resolved = profile::resolve(requested, mode, false, false, false).unwrap();
+        let ex = build_executor(s, &s.parsed().unwrap(), resolved, &Stats::new(0)).unwrap();
+        let results = ex.execute(two_legs(1)).await.unwrap();
+        (ex, results)
+    }
+
+    #[tokio::test]
+    async fn observer_mode_builds_the_observer() {
+        let s = settings(serde_json::json!({}));
+        let (_, results) = run_one(&s, Profile::Paper, ExecutionMode::Observer).await;
+        assert_eq!(results.iter().map(|r| r.status).collect::<Vec<_>>(), [BundleStatus::Logged]);
+    }
+
+    #[tokio::test]
+    async fn mock_mode_builds_the_mock_executor() {
+        let s = settings(serde_json::json!({ "mock_leg_script": "fill" }));
+        let (_, results) = run_one(&s, Profile::Paper, ExecutionMode::Mock).await;
+        assert_eq!(results[0].status, BundleStatus::Filled);
+        assert_eq!(results[0].legs_filled, 2);
+    }
+
+    #[tokio::test]
+    async fn observe_profile_downgrades_mock_to_the_observer() {
+        let s = settings(serde_json::json!({ "mock_leg_script": "fill" }));
+        let (_, results) = run_one(&s, Profile::Observe, ExecutionMode::Mock).await;
+        assert_eq!(results[0].status, BundleStatus::Logged);
+    }
+
+    #[tokio::test]
+    async fn churn_interval_wraps_the_executor_in_the_gate() {
+        let s = settings(serde_json::json!({ "min_intent_interval_per_token_ms": 60_000 }));
+        let (ex, first) = run_one(&s, Profile::Paper, ExecutionMode::Observer).await;
+        assert_eq!(first[0].status, BundleStatus::Logged);
+        let second = ex.execute(two_legs(2)).await.unwrap();
+        assert_eq!(second[0].status, BundleStatus::SuppressedChurn);
+    }
+
+    #[test]
+    fn bad_mock_script_is_an_error() {
+        let s = settings(serde_json::json!({ "mock_leg_script": "fill,explode" }));
+        let resolved = profile::resolve(Profile::Paper, ExecutionMode::Mock, false, false, false).unwrap();
+        assert!(build_executor(&s, &s.parsed().unwrap(), resolved, &Stats::new(0)).is_err());
+    }
+
+    #[test]
+    fn every_stats_row_carries_the_resolved_profile() {
+        let resolved = profile::resolve(Profile::Observe, ExecutionMode::Mock, false, false, false).unwrap();
+        let stats = Stats::new(0);
+        stats.set_profile(resolved.profile);
+        let rows = [stats.snapshot(1_000), stats.snapshot(2_000)];
+        for ss in &rows {
+            assert_eq!(serde_json::to_value(ss).unwrap()["profile"], "observe");
+        }
+
+        let mut csv = polymarket_arb_bot::stats::StatsCsv::new(10);
+        rows.iter().for_each(|ss| csv.push(ss));
+        let text = csv.render();
+        assert!(text.lines().next().unwrap().split(',').any(|c| c == "profile"));
+        // nested cells are quoted JSON, so look for the cell rather than split on commas
+        let intervals: Vec<&str> = text.lines().filter(|l| l.starts_with("interval,")).collect();
+        assert_eq!(intervals.len(), 2);
+        assert!(intervals.iter().all(|l| l.contains(",observe,")), "{}", text);
+    }
+
+    #[test]
+    fn snapshot_below_the_absolute_floor_is_thin() {
+        assert!(snapshot_is_thin(4, 0, 5, 0.0));
+        assert!(!snapshot_is_thin(5, 0, 5, 0.0));
+    }
+
+    #[test]
+    fn snapshot_collapsed_against_the_last_good_one_is_thin() {
+        // 40 of the last 100 is under half
+        assert!(snapshot_is_thin(40, 100, 1, 0.5));
+        assert!(!snapshot_is_thin(50, 100, 1, 0.5));
+        // no ratio check before the first good snapshot, or with the ratio off
+        assert!(!snapshot_is_thin(40, 0, 1, 0.5));
+        assert!(!snapshot_is_thin(40, 100, 1, 0.0));
+    }
+
+    fn covered(coverage: f64) -> GlobalSnapshot {
+        GlobalSnapshot { ts_ms: 0, markets: vec![], coverage }
+    }
+
+    #[test]
+    fn half_coverage_skips_the_cycle() {
+        let s = settings(serde_json::json!({ "min_snapshot_coverage": 0.8 }));
+        // half the requested books came back
+        assert_eq!(cycle_skip(&s, &covered(0.5), 10, 10), Some(CycleSkip::LowCoverage));
+        assert_eq!(cycle_skip(&s, &covered(0.8), 10, 10), None);
+        assert_eq!(cycle_skip(&s, &covered(1.0), 10, 10), None);
+    }
+
+    #[test]
+    fn coverage_is_checked_before_thinness() {
+        let s = settings(serde_json::json!({ "min_snapshot_coverage": 0.8, "min_snapshot_markets": 5 }));
+        assert_eq!(cycle_skip(&s, &covered(0.5), 2, 0), Some(CycleSkip::LowCoverage));
+        assert_eq!(cycle_skip(&s, &covered(0.9), 2, 0), Some(CycleSkip::Thin));
+        // off by default
+        let off = settings(serde_json::json!({}));
+        assert_eq!(cycle_skip(&off, &covered(0.1), 10, 10), None);
+    }
+
+    /// A GET /markets server listing `ids` as open binary markets.
+    async fn markets_server(ids: &[&str]) -> wiremock::MockServer {
+        let server = wiremock::MockServer::start().await;
+        let data: Vec<_> = ids.iter().enumerate().map(|(k, id)| serde_json::json!({
+            "enable_order_book": true,
+            "accepting_orders": true,
+            "closed": false,
+            "condition_id": id,
+            "question": format!("{}?", id),
+            "tokens": [{ "token_id": format!("{}1", k + 1), "outcome": "Yes" }, { "token_id": format!("{}2", k + 1), "outcome": "No" }],
+        })).collect();
+        wiremock::Mock::given(wiremock::matchers::method("GET"))
+            .and(wiremock::matchers::path("/markets"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({ "data": data, "next_cursor": "LTE=" })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn refresher(host: &str, cache_path: &std::path::Path, stats: Arc<Stats>) -> MarketRefresher {
+        let s = settings(serde_json::json!({ "clob_host": host, "http_max_retries": 0 }));
+        let http = HttpClient::from_settings(&s, Arc::new(Jitter::seeded(7))).unwrap();
+        MarketRefresher {
+            source: Arc::new(MarketData::from_settings(&s, http, false, stats.clone())),
+            gamma: None,
+            excluded_events: Default::default(),
+            include_categories: Default::default(),
+            exclude_categories: Default::default(),
+            max_markets: 100,
+            scoring: None,
+            score_candidates: 0,
+            hot: Arc::new(std::sync::Mutex::new(HotMarkets::new(10))),
+            redemption_values: Default::default(),
+            cache_path: Some(cache_path.to_path_buf()),
+            known: Default::default(),
+            stats,
+        }
+    }
+
+    fn market_ids(markets: &[MarketDef]) -> Vec<&str> {
+        markets.iter().map(|m| m.market_id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn a_refresh_request_runs_the_refresher_between_ticks() {
+        let path = std::env::temp_dir().join(format!("arb-universe-trigger-{}.json", std::process::id()));
+        let server = markets_server(&["a", "b"]).await;
+        let (tx, mut rx) = tokio::sync::watch::channel(Arc::new(vec![]));
+        let trigger = Arc::new(RefreshTrigger::default());
+        // no interval and no start-up refresh: only the request can run one
+        let task = refresher(&server.uri(), &path, Stats::new(0)).spawn(0, false, tx, trigger.clone());
+
+        let outcome = trigger.request_and_wait(std::time::Duration::from_secs(5)).await;
+        assert_eq!(outcome, Some(RefreshOutcome::Done(2)));
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(market_ids(&rx.borrow_and_update()), ["a", "b"]);
+        task.abort();
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn persisted_universe_is_loaded_then_reconciled() {
+        let path = std::env::temp_dir().join(format!("arb-universe-{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        // a first run saves [a, b]
+        let before = markets_server(&["a", "b"]).await;
+        let mut first = refresher(&before.uri(), &path, Stats::new(0));
+        assert!(first.load_cached().is_none());
+        assert_eq!(market_ids(&first.refresh().await.unwrap()), ["a", "b"]);
+
+        // the next starts from the saved list while the venue now lists [b, c]
+        let after = markets_server(&["b", "c"]).await;
+        let stats = Stats::new(0);
+        let mut next = refresher(&after.uri(), &path, stats.clone());
+        assert_eq!(market_ids(&next.load_cached().unwrap()), ["a", "b"]);
+        assert_eq!(stats.snapshot(0).markets_loaded, 2);
+        assert_eq!(market_ids(&next.refresh().await.unwrap()), ["b", "c"]);
+        let ss = stats.snapshot(0);
+        assert_eq!((ss.markets_added, ss.markets_removed), (1, 1));
+        assert_eq!(market_ids(&load_universe(&path).unwrap().unwrap()), ["b", "c"]);
+        std::fs::remove_file(&path).unwrap();
     }
 }
@@ -0,0 +1,130 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::Notify;
+
+struct Slot<T> {
+    value: Option<T>,
+    closed: bool,
+}
+
+struct Shared<T> {
+    slot: Mutex<Slot<T>>,
+    notify: Notify,
+}
+
+/// Producer half of `latest`.
+pub struct LatestSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// Consumer half of `latest`.
+pub struct LatestReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// One-slot channel for a consumer that only wants the newest value: publishing while a
+/// value is still waiting replaces it, so the producer never blocks and the consumer
+/// never works on anything older than the last publish.
+pub fn latest<T>() -> (LatestSender<T>, LatestReceiver<T>) {
+    let shared = Arc::new(Shared { slot: Mutex::new(Slot { value: None, closed: false }), notify: Notify::new() });
+    (LatestSender { shared: shared.clone() }, LatestReceiver { shared })
+}
+
+impl<T> LatestSender<T> {
+    /// Stores `value`; true when it replaced one the consumer had not taken yet.
+    pub fn publish(&self, value: T) -> bool {
+        let replaced = self.shared.slot.lock().unwrap_or_else(|e| e.into_inner()).value.replace(value).is_some();
+        self.shared.notify.notify_one();
+        replaced
+    }
+}
+
+impl<T> Drop for LatestSender<T> {
+    fn drop(&mut self) {
+        self.shared.slot.lock().unwrap_or_else(|e| e.into_inner()).closed = true;
+        self.shared.notify.notify_one();
+    }
+}
+
+impl<T> LatestReceiver<T> {
+    /// Waits until a value is waiting or the sender is gone, without taking it.
+    pub async fn ready(&self) {
+        loop {
+            {
+                let slot = self.shared.slot.lock().unwrap_or_else(|e| e.into_inner());
+                if slot.value.is_some() || slot.closed {
+                    return;
+                }
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+
+    /// Takes the newest value, waiting for one; None once the sender is gone and the
+    /// slot is empty.
+    pub async fn recv(&self) -> Option<T> {
+        loop {
+            {
+                let mut slot = self.shared.slot.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(v) = slot.value.take() {
+                    return Some(v);
+                }
+                if slot.closed {
+                    return None;
+                }
+            }
+            self.shared.notify.notified().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn consumer_sees_only_the_newest_value() {
+        let (tx, rx) = latest();
+        assert!(!tx.publish(1));
+        assert!(tx.publish(2));
+        assert!(tx.publish(3));
+        assert_eq!(rx.recv().await, Some(3));
+        drop(tx);
+        assert_eq!(rx.recv().await, None);
+    }
+
+    #[tokio::test]
+    async fn slow_consumer_skips_to_the_freshest_snapshot() {
+        let (tx, rx) = latest();
+        let producer = tokio::spawn(async move {
+            let mut dropped = 0;
+            for n in 1..=20u32 {
+                dropped += tx.publish(n) as u32;
+                tokio::time::sleep(Duration::from_millis(2)).await;
+            }
+            dropped
+        });
+        let mut seen = vec![];
+        while let Some(n) = rx.recv().await {
+            seen.push(n);
+            // evaluation is slower than fetching
+            tokio::time::sleep(Duration::from_millis(15)).await;
+        }
+        let dropped = producer.await.unwrap();
+
+        assert!(seen.windows(2).all(|w| w[0] < w[1]), "out of order: {:?}", seen);
+        assert_eq!(seen.last(), Some(&20));
+        assert!(dropped > 0);
+        assert_eq!(seen.len() as u32 + dropped, 20);
+    }
+
+    #[tokio::test]
+    async fn ready_does_not_take_the_value() {
+        let (tx, rx) = latest();
+        tx.publish("a");
+        rx.ready().await;
+        assert_eq!(rx.recv().await, Some("a"));
+    }
+}
@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use uuid::Uuid;
+
+use crate::pm::executor::{BundleResult, BundleStatus, Executor, ShutdownReport};
+use crate::types::OrderIntent;
+
+/// Bundle ids executed recently, persisted to disk so a restart does not re-submit a
+/// bundle whose (deterministic) id was already sent.
+///
+/// Stored as a JSON object of bundle_id -> executed-at ms; entries older than `ttl_ms`
+/// are pruned on load and on every write.
+pub struct BundleLedger {
+    path: PathBuf,
+    ttl_ms: u64,
+    executed: Mutex<HashMap<Uuid, u64>>,
+}
+
+impl BundleLedger {
+    /// Loads `path` if it exists; a missing file starts an empty ledger.
+    pub fn load(path: impl Into<PathBuf>, ttl_sec: u64, now_ms: u64) -> Result<Self> {
+        let path = path.into();
+        let mut executed: HashMap<Uuid, u64> = match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("decode bundle ledger {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("read bundle ledger {}", path.display())),
+        };
+        let ttl_ms = ttl_sec.saturating_mul(1000);
+        executed.retain(|_, ts| now_ms.saturating_sub(*ts) < ttl_ms);
+        Ok(Self { path, ttl_ms, executed: Mutex::new(executed) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.executed.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// True if `id` was executed within the ttl.
+    pub fn contains(&self, id: &Uuid, now_ms: u64) -> bool {
+        let executed = self.executed.lock().unwrap_or_else(|e| e.into_inner());
+        executed.get(id).map(|ts| now_ms.saturating_sub(*ts) < self.ttl_ms).unwrap_or(false)
+    }
+
+    /// Adds `ids`, prunes expired entries and rewrites the file (write to temp, then rename).
+    pub fn record(&self, ids: &[Uuid], now_ms: u64) -> Result<()> {
+        let body = {
+            let mut executed = self.executed.lock().unwrap_or_else(|e| e.into_inner());
+            for id in ids {
+                executed.insert(*id, now_ms);
+            }
+            let ttl = self.ttl_ms;
+            executed.retain(|_, ts| now_ms.saturating_sub(*ts) < ttl);
+            serde_json::to_vec(&*executed)?
+        };
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, body).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, &self.path).with_context(|| format!("replace {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+/// Executor wrapper that drops bundles already in the ledger and records the ones the
+/// inner executor acted on.
+pub struct LedgerExecutor {
+    inner: Box<dyn Executor>,
+    ledger: BundleLedger,
+}
+
+impl LedgerExecutor {
+    pub fn new(inner: Box<dyn Executor>, ledger: BundleLedger) -> Self {
+        Self { inner, ledger }
+    }
+
+    async fn run(&self, intents: Vec<OrderIntent>) -> Result<Vec<BundleResult>> {
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        let mut results: Vec<BundleResult> = vec![];
+        let mut suppressed: HashMap<Uuid, Vec<OrderIntent>> = HashMap::new();
+        let mut fresh: Vec<OrderIntent> = vec![];
+        for i in intents {
+            if self.ledger.contains(&i.bundle_id, now) {
+                suppressed.entry(i.bundle_id).or_default().push(i);
+            } else {
+                fresh.push(i);
+            }
+        }
+        for (bid, legs) in &suppressed {
+            tracing::info!(bundle_id=%bid, status="deduplicated", "bundle dropped: already executed (persisted ledger)");
+            results.push(BundleResult::new(*bid, BundleStatus::Deduplicated, legs));
+        }
+
+        let done = self.inner.execute(fresh).await?;
+        let executed: Vec<Uuid> = done.iter()
+            .filter(|r| !matches!(r.status, BundleStatus::Deduplicated | BundleStatus::SuppressedChurn | BundleStatus::Failed))
+            .map(|r| r.bundle_id)
+            .collect();
+        if !executed.is_empty() {
+            if let Err(e) = self.ledger.record(&executed, now) {
+                tracing::warn!(err = %format!("{:#}", e), "bundle ledger write failed");
+            }
+        }
+        results.extend(done);
+        Ok(results)
+    }
+}
+
+impl Executor for LedgerExecutor {
+    fn execute(&self, intents: Vec<OrderIntent>) -> BoxFuture<'_, Result<Vec<BundleResult>>> {
+        Box::pin(self.run(intents))
+    }
+
+    fn shutdown(&self) -> BoxFuture<'_, Result<ShutdownReport>> {
+        self.inner.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::pm::execution_observer::ExecutionObserver;
+    use crate::stats::Stats;
+    use crate::testutil::intents::{bundle, bundle_legs};
+    use crate::testutil::temp_path;
+
+    const HOUR_MS: u64 = 3_600_000;
+
+    #[test]
+    fn recorded_ids_survive_a_reload() {
+        let path = temp_path("ledger.json");
+        let ledger = BundleLedger::load(&path, 3600, 0).unwrap();
+        assert!(ledger.is_empty());
+        ledger.record(&[bundle(1), bundle(2)], 1_000).unwrap();
+
+        let reloaded = BundleLedger::load(&path, 3600, 2_000).unwrap();
+        assert_eq!(reloaded.len(), 2);
+        assert!(reloaded.contains(&bundle(1), 2_000));
+        assert!(!reloaded.contains(&bundle(3), 2_000));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn expired_entries_are_pruned_on_load_and_on_write() {
+        let path = temp_path("ledger.json");
+        let ledger = BundleLedger::load(&path, 3600, 0).unwrap();
+        ledger.record(&[bundle(1)], 0).unwrap();
+        ledger.record(&[bundle(2)], HOUR_MS / 2).unwrap();
+        // an hour on, bundle 1 has expired but is still in memory until the next write
+        assert!(!ledger.contains(&bundle(1), HOUR_MS));
+        assert!(ledger.contains(&bundle(2), HOUR_MS));
+
+        let reloaded = BundleLedger::load(&path, 3600, HOUR_MS).unwrap();
+        assert_eq!(reloaded.len(), 1);
+        ledger.record(&[bundle(3)], 2 * HOUR_MS).unwrap();
+        assert_eq!(ledger.len(), 1);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn a_corrupt_file_is_an_error() {
+        let path = temp_path("ledger.json");
+        std::fs::write(&path, "not json").unwrap();
+        assert!(BundleLedger::load(&path, 3600, 0).is_err());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn a_bundle_in_the_ledger_is_suppressed() {
+        let path = temp_path("ledger.json");
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        BundleLedger::load(&path, 3600, now).unwrap().record(&[bundle(1)], now).unwrap();
+
+        // as after a restart
+        let ledger = BundleLedger::load(&path, 3600, now).unwrap();
+        let ex = LedgerExecutor::new(Box::new(ExecutionObserver::new(Stats::new(0), 0)), ledger);
+        let mut intents = bundle_legs("m", &[("1", dec!(0.45)), ("2", dec!(0.47))], dec!(10), bundle(1));
+        intents.extend(bundle_legs("m", &[("1", dec!(0.45)), ("2", dec!(0.47))], dec!(10), bundle(2)));
+        let results = ex.execute(intents).await.unwrap();
+        let status = |b| results.iter().find(|r| r.bundle_id == b).unwrap().status;
+        assert_eq!(status(bundle(1)), BundleStatus::Deduplicated);
+        assert_eq!(status(bundle(2)), BundleStatus::Logged);
+
+        // the bundle that went through is now in the ledger on disk
+        assert!(BundleLedger::load(&path, 3600, now).unwrap().contains(&bundle(2), now));
+        std::fs::remove_file(path).unwrap();
+    }
+}
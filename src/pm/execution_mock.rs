@@ -0,0 +1,444 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+use futures::future::{join_all, BoxFuture};
+use uuid::Uuid;
+
+use crate::pm::executor::{edge_bps, flatten_intents, BundleResult, BundleStatus, Executor, LegOrdering, StrandedLegPolicy};
+use crate::types::OrderIntent;
+
+/// What the mock venue does with the next leg it receives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegOutcome {
+    Fill,
+    /// Fills `THIN_FILL_RATIO` of the leg's size
+    Thin,
+    Reject,
+    /// Never answers; the leg ends by timing out
+    Hang,
+}
+
+impl std::str::FromStr for LegOutcome {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "fill" => Ok(LegOutcome::Fill),
+            "thin" => Ok(LegOutcome::Thin),
+            "reject" => Ok(LegOutcome::Reject),
+            "hang" => Ok(LegOutcome::Hang),
+            other => anyhow::bail!("unknown mock leg outcome: {}", other),
+        }
+    }
+}
+
+/// Share of a leg's size a `Thin` outcome fills.
+const THIN_FILL_RATIO: Decimal = Decimal::from_parts(5, 0, 0, false, 1);
+
+/// Pause between chase rounds, so a rejecting script does not spin.
+const CHASE_RETRY_PAUSE: Duration = Duration::from_millis(50);
+
+/// Final state of one leg as seen by the executor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LegStatus {
+    Filled,
+    /// Filled `THIN_FILL_RATIO` of its size
+    Short,
+    Rejected,
+    TimedOut,
+    /// Never sent: an earlier leg of a scarcest-first bundle failed
+    Aborted,
+}
+
+impl LegStatus {
+    /// Share of the leg's size that filled.
+    fn fill_ratio(self) -> Decimal {
+        match self {
+            LegStatus::Filled => Decimal::ONE,
+            LegStatus::Short => THIN_FILL_RATIO,
+            LegStatus::Rejected | LegStatus::TimedOut | LegStatus::Aborted => Decimal::ZERO,
+        }
+    }
+}
+
+/// Simulated executor for exercising atomicity and timeout handling.
+///
+/// Every leg waits `leg_delay`, then takes the next outcome from `script` (cycled;
+/// an empty script fills everything). With `Simultaneous` ordering the legs of a bundle
+/// are sent concurrently, each bounded by `leg_timeout`; with `ScarcestFirst` they go one
+/// at a time by ascending `avail_size`, each bounded by `inter_leg_timeout`, and the rest
+/// are aborted once a leg fills below `min_fill_ratio`. Filled legs fill at the intent
+/// price moved against us by `fill_slippage_bps`.
+///
+/// A bundle with some but not all legs filled is handled per `stranded`: held (reported
+/// partial), flattened, or chased (missing legs retried against the script for up to
+/// `complete_or_flatten`, then flattened). Flattening sells at each leg's bid, estimated as
+/// its intent price scaled by the bundle's sum_bid / sum_ask, and always fills.
+pub struct MockExecutor {
+    leg_delay: Duration,
+    leg_timeout: Duration,
+    script: Vec<LegOutcome>,
+    cursor: AtomicUsize,
+    fill_slippage_bps: i64,
+    ordering: LegOrdering,
+    inter_leg_timeout: Duration,
+    min_fill_ratio: Decimal,
+    stranded: StrandedLegPolicy,
+    complete_or_flatten: Duration,
+}
+
+impl MockExecutor {
+    pub fn new(leg_delay: Duration, leg_timeout: Duration, script: Vec<LegOutcome>) -> Self {
+        Self {
+            leg_delay,
+            leg_timeout,
+            script,
+            cursor: AtomicUsize::new(0),
+            fill_slippage_bps: 0,
+            ordering: LegOrdering::Simultaneous,
+            inter_leg_timeout: leg_timeout,
+            min_fill_ratio: Decimal::ONE,
+            stranded: StrandedLegPolicy::Flatten,
+            complete_or_flatten: Duration::ZERO,
+        }
+    }
+
+    /// What happens to the filled legs of a bundle that did not complete;
+    /// `complete_or_flatten` bounds `Chase`.
+    pub fn with_stranded_legs(mut self, policy: StrandedLegPolicy, complete_or_flatten: Duration) -> Self {
+        self.stranded = policy;
+        self.complete_or_flatten = complete_or_flatten;
+        self
+    }
+
+    /// Leg submission policy; `inter_leg_timeout` and `min_fill_ratio` apply to
+    /// `ScarcestFirst` only.
+    pub fn with_leg_ordering(mut self, ordering: LegOrdering, inter_leg_timeout: Duration, min_fill_ratio: Decimal) -> Self {
+        self.ordering = ordering;
+        self.inter_leg_timeout = inter_leg_timeout;
+        self.min_fill_ratio = min_fill_ratio;
+        self
+    }
+
+    /// Fill buys this many bps above the intent price (negative fills better).
+    pub fn with_fill_slippage_bps(mut self, bps: i64) -> Self {
+        self.fill_slippage_bps = bps;
+        self
+    }
+
+    fn fill_price(&self, leg: &OrderIntent) -> Decimal {
+        leg.price * (Decimal::ONE + Decimal::from(self.fill_slippage_bps) / Decimal::from(10_000))
+    }
+
+    /// Best bid assumed for a leg when flattening.
+    fn flatten_bid(leg: &OrderIntent) -> Decimal {
+        if leg.meta.sum_ask > Decimal::ZERO {
+            (leg.price * leg.meta.sum_bid / leg.meta.sum_ask).round_dp(4)
+        } else {
+            leg.price
+        }
+    }
+
+    fn next_outcome(&self) -> LegOutcome {
+        if self.script.is_empty() {
+            return LegOutcome::Fill;
+        }
+        let i = self.cursor.fetch_add(1, Ordering::Relaxed);
+        self.script[i % self.script.len()]
+    }
+
+    async fn send_leg(&self, outcome: LegOutcome, timeout: Duration) -> LegStatus {
+        let leg = async {
+            tokio::time::sleep(self.leg_delay).await;
+            match outcome {
+                LegOutcome::Fill => LegStatus::Filled,
+                LegOutcome::Thin => LegStatus::Short,
+                LegOutcome::Reject => LegStatus::Rejected,
+                LegOutcome::Hang => std::future::pending().await,
+            }
+        };
+        tokio::time::timeout(timeout, leg).await.unwrap_or(LegStatus::TimedOut)
+    }
+
+    /// Sends the legs of one bundle per the ordering policy and returns each leg's status
+    /// in input order.
+    pub async fn execute_bundle(&self, legs: &[OrderIntent]) -> Vec<LegStatus> {
+        match self.ordering {
+            LegOrdering::Simultaneous => {
+                // draw outcomes up front so the script order follows leg order, not completion order
+                let outcomes: Vec<LegOutcome> = legs.iter().map(|_| self.next_outcome()).collect();
+                join_all(outcomes.into_iter().map(|o| self.send_leg(o, self.leg_timeout))).await
+            }
+            LegOrdering::ScarcestFirst => self.execute_scarcest_first(legs).await,
+        }
+    }
+
+    async fn execute_scarcest_first(&self, legs: &[OrderIntent]) -> Vec<LegStatus> {
+        let mut order: Vec<usize> = (0..legs.len()).collect();
+        // legs without a known size go last
+        order.sort_by_key(|&i| (legs[i].avail_size.is_none(), legs[i].avail_size));
+        let mut statuses = vec![LegStatus::Aborted; legs.len()];
+        for (n, &i) in order.iter().enumerate() {
+            let st = self.send_leg(self.next_outcome(), self.inter_leg_timeout).await;
+            statuses[i] = st;
+            if st.fill_ratio() < self.min_fill_ratio {
+                tracing::warn!(
+                    bundle_id = %legs[i].bundle_id,
+                    token_id = %legs[i].token_id,
+                    status = ?st,
+                    aborted = order.len() - n - 1,
+                    "mock leg below completion threshold, aborting remaining legs"
+                );
+                break;
+            }
+        }
+        statuses
+    }
+
+    /// Retries the legs `fills` leaves short until every leg is complete, `complete_or_flatten`
+    /// runs out, or completing at the current fill prices would no longer be profitable.
+    async fn chase(&self, legs: &[OrderIntent], mut fills: Vec<Option<(Decimal, Decimal)>>) -> Vec<Option<(Decimal, Decimal)>> {
+        let deadline = Instant::now() + self.complete_or_flatten;
+        let mut rounds = 0usize;
+        loop {
+            let short: Vec<usize> = (0..legs.len())
+                .filter(|&i| fills[i].is_none_or(|(_, qty)| qty < legs[i].size))
+                .collect();
+            if short.is_empty() {
+                tracing::info!(bundle_id = %legs[0].bundle_id, rounds, "mock chase completed the bundle");
+                break;
+            }
+            let cost: Decimal = legs.iter().zip(&fills)
+                .map(|(l, f)| {
+                    let (px, qty) = f.unwrap_or((Decimal::ZERO, Decimal::ZERO));
+                    px * qty + (l.size - qty) * self.fill_price(l)
+                })
+                .sum();
+            if edge_bps(legs, cost).is_none_or(|e| e <= Decimal::ZERO) {
+                tracing::warn!(bundle_id = %legs[0].bundle_id, cost = %cost, "mock chase stopped: completing the bundle would lose money");
+                break;
+            }
+            let left = deadline.saturating_duration_since(Instant::now());
+            if left.is_zero() {
+                tracing::warn!(bundle_id = %legs[0].bundle_id, rounds, short = short.len(), "mock chase ran out of time");
+                break;
+            }
+            rounds += 1;
+            for i in short {
+                let st = self.send_leg(self.next_outcome(), self.leg_timeout.min(left)).await;
+                if st.fill_ratio() > Decimal::ZERO {
+                    let (px, qty) = fills[i].unwrap_or((Decimal::ZERO, Decimal::ZERO));
+                    let add = (legs[i].size - qty) * st.fill_ratio();
+                    let avg = (px * qty + self.fill_price(&legs[i]) * add) / (qty + add);
+                    fills[i] = Some((avg, qty + add));
+                }
+            }
+            tokio::time::sleep(CHASE_RETRY_PAUSE.min(deadline.saturating_duration_since(Instant::now()))).await;
+        }
+        fills
+    }
+
+    async fn run(&self, intents: Vec<OrderIntent>) -> Result<Vec<BundleResult>> {
+        let mut results: Vec<BundleResult> = vec![];
+        let mut order: Vec<Uuid> = vec![];
+        let mut by_bundle: HashMap<Uuid, Vec<OrderIntent>> = HashMap::new();
+        for i in intents {
+            if !by_bundle.contains_key(&i.bundle_id) {
+                order.push(i.bundle_id);
+            }
+            by_bundle.entry(i.bundle_id).or_default().push(i);
+        }
+
+        for bid in order {
+            let legs = by_bundle.remove(&bid).unwrap_or_default();
+            let statuses = self.execute_bundle(&legs).await;
+            let mut fills: Vec<Option<(Decimal, Decimal)>> = legs.iter().zip(&statuses)
+                .map(|(leg, st)| (st.fill_ratio() > Decimal::ZERO).then(|| (self.fill_price(leg), leg.size * st.fill_ratio())))
+                .collect();
+            let complete = |fills: &[Option<(Decimal, Decimal)>]| legs.iter().zip(fills).all(|(l, f)| f.is_some_and(|(_, qty)| qty >= l.size));
+            let stranded = !complete(&fills) && fills.iter().any(Option::is_some);
+            if stranded && self.stranded == StrandedLegPolicy::Chase {
+                fills = self.chase(&legs, fills).await;
+            }
+            let filled = fills.iter().flatten().count();
+            let status = if complete(&fills) {
+                BundleStatus::Filled
+            } else if filled == 0 {
+                BundleStatus::Failed
+            } else {
+                BundleStatus::Partial
+            };
+            let mut result = BundleResult::new(bid, status, &legs).with_fills(&legs, &fills);
+            for (leg, st) in legs.iter().zip(&statuses) {
+                tracing::debug!(bundle_id=%bid, token_id=%leg.token_id, price=%leg.price, size=%leg.size, status=?st, "mock leg");
+            }
+            if status == BundleStatus::Partial {
+                if self.stranded == StrandedLegPolicy::Hold {
+                    for (leg, (_, qty)) in legs.iter().zip(&fills).filter_map(|(l, f)| Some((l, (*f)?))) {
+                        tracing::warn!(bundle_id=%bid, token_id=%leg.token_id, size=%qty, "mock hold: keeping filled leg of partial bundle");
+                    }
+                } else {
+                    let bids: Vec<Decimal> = legs.iter().map(Self::flatten_bid).collect();
+                    let sells = flatten_intents(&legs, &fills, &bids);
+                    for s in &sells {
+                        tracing::warn!(bundle_id=%bid, token_id=%s.token_id, size=%s.size, bid=%s.price, "mock flatten: selling filled leg of partial bundle");
+                    }
+                    result = result.with_flatten(&sells);
+                }
+            }
+            tracing::info!(
+                bundle_id=%bid,
+                legs=legs.len(),
+                filled,
+                status=?result.status,
+                theoretical_edge_bps=%result.theoretical_edge_bps,
+                realized_edge_bps=%result.realized_edge_bps.map(|v| v.to_string()).unwrap_or_default(),
+                flatten_loss=%result.flatten_loss.map(|v| v.to_string()).unwrap_or_default(),
+                "mock bundle executed"
+            );
+            results.push(result);
+        }
+        Ok(results)
+    }
+}
+
+impl Executor for MockExecutor {
+    fn execute(&self, intents: Vec<OrderIntent>) -> BoxFuture<'_, Result<Vec<BundleResult>>> {
+        Box::pin(self.run(intents))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::testutil::intents::{bundle, bundle_legs};
+
+    fn legs(n: u128) -> Vec<OrderIntent> {
+        bundle_legs("m", &[("a", dec!(0.45)), ("b", dec!(0.47))], dec!(10), bundle(n))
+    }
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[tokio::test]
+    async fn latency_past_the_leg_timeout_times_every_leg_out() {
+        let ex = MockExecutor::new(ms(200), ms(20), vec![]);
+        let started = Instant::now();
+        assert_eq!(ex.execute_bundle(&legs(1)).await, [LegStatus::TimedOut, LegStatus::TimedOut]);
+        // legs run concurrently, so the bundle takes one timeout, not two
+        assert!(started.elapsed() < ms(150), "took {:?}", started.elapsed());
+
+        let r = ex.execute(legs(2)).await.unwrap();
+        assert_eq!(r[0].status, BundleStatus::Failed);
+        assert_eq!((r[0].legs_filled, r[0].fill_cost), (0, None));
+    }
+
+    #[tokio::test]
+    async fn latency_under_the_leg_timeout_fills() {
+        let ex = MockExecutor::new(ms(5), ms(200), vec![]);
+        let r = ex.execute(legs(1)).await.unwrap();
+        assert_eq!(r[0].status, BundleStatus::Filled);
+        assert_eq!(r[0].fill_cost, Some(dec!(9.20)));
+    }
+
+    #[tokio::test]
+    async fn fills_worse_than_intent_prices_show_as_slippage() {
+        let ex = MockExecutor::new(ms(0), ms(200), vec![]).with_fill_slippage_bps(100);
+        let r = ex.execute(legs(1)).await.unwrap();
+        // 0.45 and 0.47 fill at 0.4545 and 0.4747: 9.292 for a 10 share payout
+        assert_eq!(r[0].theoretical_cost, dec!(9.20));
+        assert_eq!(r[0].fill_cost, Some(dec!(9.292)));
+        assert_eq!(r[0].theoretical_edge_bps, dec!(800));
+        assert_eq!(r[0].realized_edge_bps, Some(dec!(708)));
+        assert_eq!(r[0].slippage_bps(), Some(dec!(92)));
+    }
+
+    #[tokio::test]
+    async fn a_hanging_leg_times_out_and_strands_the_other() {
+        let ex = MockExecutor::new(ms(1), ms(20), vec![LegOutcome::Fill, LegOutcome::Hang])
+            .with_stranded_legs(StrandedLegPolicy::Hold, Duration::ZERO);
+        assert_eq!(ex.execute_bundle(&legs(1)).await, [LegStatus::Filled, LegStatus::TimedOut]);
+
+        let r = ex.execute(legs(2)).await.unwrap();
+        assert_eq!(r[0].status, BundleStatus::Partial);
+        assert_eq!((r[0].legs_filled, r[0].fill_cost), (1, Some(dec!(4.50))));
+    }
+
+    #[tokio::test]
+    async fn each_stranded_leg_policy_settles_a_partial_bundle() {
+        // a fills, b is rejected; a chase draws the script on from there
+        let ex = |policy, window, script| MockExecutor::new(Duration::ZERO, ms(20), script).with_stranded_legs(policy, window);
+        let one_missing = || vec![LegOutcome::Fill, LegOutcome::Reject];
+
+        let r = ex(StrandedLegPolicy::Hold, Duration::ZERO, one_missing()).execute(legs(1)).await.unwrap();
+        assert_eq!((r[0].status, r[0].legs_filled, r[0].flatten_proceeds), (BundleStatus::Partial, 1, None));
+
+        let r = ex(StrandedLegPolicy::Flatten, Duration::ZERO, one_missing()).execute(legs(1)).await.unwrap();
+        assert_eq!((r[0].status, r[0].flatten_proceeds), (BundleStatus::Flattened, Some(dec!(0))));
+
+        // the retry of b fills, completing the bundle
+        let r = ex(StrandedLegPolicy::Chase, ms(500), one_missing()).execute(legs(1)).await.unwrap();
+        assert_eq!((r[0].status, r[0].legs_filled, r[0].fill_cost), (BundleStatus::Filled, 2, Some(dec!(9.20))));
+
+        // the retry is rejected too and the window closes, so a is flattened
+        let script = vec![LegOutcome::Fill, LegOutcome::Reject, LegOutcome::Reject];
+        let r = ex(StrandedLegPolicy::Chase, ms(30), script).execute(legs(1)).await.unwrap();
+        assert_eq!((r[0].status, r[0].flatten_proceeds), (BundleStatus::Flattened, Some(dec!(0))));
+    }
+
+    #[tokio::test]
+    async fn script_outcomes_cycle_in_leg_order() {
+        let ex = MockExecutor::new(Duration::ZERO, ms(20), vec![LegOutcome::Reject, LegOutcome::Thin, LegOutcome::Fill]);
+        assert_eq!(ex.execute_bundle(&legs(1)).await, [LegStatus::Rejected, LegStatus::Short]);
+        assert_eq!(ex.execute_bundle(&legs(2)).await, [LegStatus::Filled, LegStatus::Rejected]);
+    }
+
+    /// Three legs showing 50, 5 and an unknown size: sent as b, a, c.
+    fn uneven(n: u128) -> Vec<OrderIntent> {
+        let mut legs = bundle_legs("m", &[("a", dec!(0.30)), ("b", dec!(0.30)), ("c", dec!(0.30))], dec!(10), bundle(n));
+        legs[0].avail_size = Some(dec!(50));
+        legs[1].avail_size = Some(dec!(5));
+        legs
+    }
+
+    fn scarcest_first(script: Vec<LegOutcome>, min_fill_ratio: Decimal) -> MockExecutor {
+        MockExecutor::new(Duration::ZERO, ms(200), script).with_leg_ordering(LegOrdering::ScarcestFirst, ms(20), min_fill_ratio)
+    }
+
+    #[tokio::test]
+    async fn scarcest_leg_goes_first_and_its_failure_aborts_the_rest() {
+        let ex = || scarcest_first(vec![LegOutcome::Reject, LegOutcome::Fill], Decimal::ONE);
+        assert_eq!(ex().execute_bundle(&uneven(1)).await, [LegStatus::Aborted, LegStatus::Rejected, LegStatus::Aborted]);
+
+        // nothing filled, so nothing to flatten
+        let r = ex().execute(uneven(2)).await.unwrap();
+        assert_eq!((r[0].status, r[0].legs_filled, r[0].flatten_proceeds), (BundleStatus::Failed, 0, None));
+    }
+
+    #[tokio::test]
+    async fn a_later_leg_timing_out_flattens_the_filled_one() {
+        let ex = || scarcest_first(vec![LegOutcome::Fill, LegOutcome::Hang], Decimal::ONE);
+        assert_eq!(ex().execute_bundle(&uneven(1)).await, [LegStatus::TimedOut, LegStatus::Filled, LegStatus::Aborted]);
+
+        let r = ex().execute(uneven(2)).await.unwrap();
+        assert_eq!((r[0].status, r[0].legs_filled), (BundleStatus::Flattened, 1));
+        // b, the only leg bought, is sold back at the 0 bid the intents imply
+        assert_eq!(r[0].flatten_proceeds, Some(dec!(0)));
+    }
+
+    #[tokio::test]
+    async fn a_thin_fill_aborts_below_the_completion_threshold_only() {
+        let strict = scarcest_first(vec![LegOutcome::Thin, LegOutcome::Fill], Decimal::ONE);
+        assert_eq!(strict.execute_bundle(&uneven(1)).await, [LegStatus::Aborted, LegStatus::Short, LegStatus::Aborted]);
+
+        // the script cycles: b thin, a filled, c thin
+        let lenient = scarcest_first(vec![LegOutcome::Thin, LegOutcome::Fill], dec!(0.5));
+        assert_eq!(lenient.execute_bundle(&uneven(1)).await, [LegStatus::Filled, LegStatus::Short, LegStatus::Short]);
+    }
+}
@@ -1,37 +1,80 @@
+use std::sync::Arc;
+
 use anyhow::Result;
+use futures::future::BoxFuture;
+
+use crate::pm::executor::{BundleResult, BundleStatus, Executor};
+use crate::pm::intent_dedup::IntentDedup;
+use crate::stats::Stats;
 use crate::types::OrderIntent;
 
+const DEDUP_MAX_ENTRIES: usize = 10_000;
+
 #[derive(Clone)]
-pub struct ExecutionObserver;
+pub struct ExecutionObserver {
+    dedup: Option<Arc<IntentDedup>>,
+    stats: Arc<Stats>,
+}
 
 impl ExecutionObserver {
-    pub fn new() -> Self { Self }
+    /// `dedup_window_ms == 0` disables duplicate suppression.
+    pub fn new(stats: Arc<Stats>, dedup_window_ms: u64) -> Self {
+        let dedup = (dedup_window_ms > 0)
+            .then(|| Arc::new(IntentDedup::new(dedup_window_ms, DEDUP_MAX_ENTRIES)));
+        Self { dedup, stats }
+    }
 
-    pub async fn execute(&self, intents: Vec<OrderIntent>) -> Result<()> {
+    async fn log_intents(&self, intents: Vec<OrderIntent>) -> Result<Vec<BundleResult>> {
+        let mut results: Vec<BundleResult> = vec![];
         if intents.is_empty() {
-            return Ok(());
+            return Ok(results);
         }
+        let intents = match &self.dedup {
+            Some(d) => {
+                let now = chrono::Utc::now().timestamp_millis() as u64;
+                let (kept, dropped) = d.filter(intents, now);
+                for (bid, legs) in &dropped {
+                    tracing::info!(bundle_id=%bid, status="deduplicated", "bundle dropped: duplicate within dedup window");
+                    results.push(BundleResult::new(*bid, BundleStatus::Deduplicated, legs));
+                }
+                self.stats.add_bundles_deduplicated(dropped.len() as u64);
+                kept
+            }
+            None => intents,
+        };
+
         let mut by_bundle: std::collections::HashMap<uuid::Uuid, Vec<OrderIntent>> = std::collections::HashMap::new();
         for i in intents {
             by_bundle.entry(i.bundle_id).or_default().push(i);
         }
 
         for (bid, legs) in by_bundle {
-            let market_id = legs.get(0).map(|x| x.market_id.clone()).unwrap_or_default();
-            tracing::info!(bundle_id=%bid, market_id=%market_id, legs=legs.len(), "bundle intents");
+            let result = BundleResult::new(bid, BundleStatus::Logged, &legs);
+            tracing::info!(bundle_id=%bid, market_id=%result.market_id, legs=legs.len(), "bundle intents");
+            results.push(result);
             for i in legs {
                 tracing::info!(
                     bundle_id=%i.bundle_id,
                     market_id=%i.market_id,
                     token_id=%i.token_id,
+                    side=?i.side,
                     price=%i.price,
                     size=%i.size,
+                    tif=?i.tif,
+                    post_only=i.post_only,
                     reason=%i.reason,
+                    meta=%serde_json::to_string(&i.meta).unwrap_or_default(),
                     "intent"
                 );
             }
         }
 
-        Ok(())
+        Ok(results)
+    }
+}
+
+impl Executor for ExecutionObserver {
+    fn execute(&self, intents: Vec<OrderIntent>) -> BoxFuture<'_, Result<Vec<BundleResult>>> {
+        Box::pin(self.log_intents(intents))
     }
 }
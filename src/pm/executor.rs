@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::profile::Profile;
+use crate::types::{OrderIntent, Side, TimeInForce};
+
+/// Which executor `main` builds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionMode {
+    /// Log intents only
+    #[default]
+    Observer,
+    /// Simulated venue with scripted leg outcomes and latency
+    Mock,
+}
+
+/// How an executor submits the legs of one bundle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LegOrdering {
+    /// Every leg at once
+    #[default]
+    Simultaneous,
+    /// One at a time, smallest available size first; stop at the first leg that fails
+    /// or fills short, so a bundle is not left holding only its liquid legs
+    ScarcestFirst,
+}
+
+/// What an executor does with the filled legs of a bundle that did not complete.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StrandedLegPolicy {
+    /// Keep the filled legs as an open position
+    Hold,
+    /// Sell the filled legs back at their best bids right away, taking the loss
+    #[default]
+    Flatten,
+    /// Keep trying the missing legs for `complete_or_flatten_ms` while the completed bundle
+    /// would still be profitable, then flatten
+    Chase,
+}
+
+impl std::str::FromStr for StrandedLegPolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "hold" => Ok(StrandedLegPolicy::Hold),
+            "flatten" => Ok(StrandedLegPolicy::Flatten),
+            "chase" => Ok(StrandedLegPolicy::Chase),
+            other => anyhow::bail!("unknown stranded leg policy: {}", other),
+        }
+    }
+}
+
+/// Stranded leg policy per profile, from STRANDED_LEG_POLICY.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StrandedLegPolicies {
+    /// For profiles without their own entry
+    pub default: StrandedLegPolicy,
+    pub by_profile: HashMap<Profile, StrandedLegPolicy>,
+}
+
+impl StrandedLegPolicies {
+    pub fn for_profile(&self, profile: Profile) -> StrandedLegPolicy {
+        self.by_profile.get(&profile).copied().unwrap_or(self.default)
+    }
+}
+
+/// Parses "policy" for every profile, or comma-separated "profile:policy" entries with at
+/// most one bare policy as the default, e.g. "hold,paper:chase".
+pub fn parse_stranded_leg_policies(s: &str) -> Result<StrandedLegPolicies> {
+    let mut out = StrandedLegPolicies::default();
+    let mut default_set = false;
+    for x in s.split(',').map(str::trim).filter(|x| !x.is_empty()) {
+        match x.split_once(':') {
+            Some((profile, policy)) => {
+                let profile: Profile = serde_json::from_value(serde_json::Value::String(profile.trim().to_ascii_lowercase()))
+                    .with_context(|| format!("stranded leg policy {:?}: unknown profile", x))?;
+                out.by_profile.insert(profile, policy.parse()?);
+            }
+            None => {
+                anyhow::ensure!(!default_set, "stranded leg policy {:?}: more than one default", x);
+                out.default = x.parse()?;
+                default_set = true;
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Sell intents closing the filled part of each leg of a stranded bundle at the leg's best
+/// bid. `fills` holds each leg's (price, shares) fill in leg order, `bids` its best bid.
+pub fn flatten_intents(legs: &[OrderIntent], fills: &[Option<(Decimal, Decimal)>], bids: &[Decimal]) -> Vec<OrderIntent> {
+    legs.iter().zip(fills).zip(bids)
+        .filter_map(|((leg, fill), bid)| {
+            let (_, qty) = (*fill)?;
+            Some(OrderIntent {
+                side: Side::Sell,
+                price: *bid,
+                size: qty,
+                reason: format!("FLATTEN_LEG bundle={} size={} bid={}", leg.bundle_id, qty, bid),
+                tif: TimeInForce::Fok,
+                post_only: false,
+                avail_size: None,
+                ..leg.clone()
+            })
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleStatus {
+    /// Observer only: logged, nothing sent
+    Logged,
+    /// Dropped by the dedup window before execution
+    Deduplicated,
+    /// Dropped because a token had an intent within the anti-churn interval
+    SuppressedChurn,
+    Filled,
+    /// Some legs filled and are held
+    Partial,
+    /// Some legs filled, the bundle could not complete and the filled legs were sold back
+    Flattened,
+    Failed,
+}
+
+/// What happened to one bundle.
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleResult {
+    pub bundle_id: Uuid,
+    pub market_id: String,
+    pub status: BundleStatus,
+    pub legs: usize,
+    pub legs_filled: usize,
+    /// Sum of intent price * size: what the strategy expected to pay
+    pub theoretical_cost: Decimal,
+    /// Sum of fill price * size over filled legs; None when nothing filled
+    pub fill_cost: Option<Decimal>,
+    /// Gross edge per $ of payout at intent prices (the decision-time sum_ask)
+    pub theoretical_edge_bps: Decimal,
+    /// Gross edge per $ of payout at fill prices; only for fully filled bundles
+    pub realized_edge_bps: Option<Decimal>,
+    /// What selling back the filled legs of a flattened bundle brought in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flatten_proceeds: Option<Decimal>,
+    /// fill_cost - flatten_proceeds: the loss taken flattening
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flatten_loss: Option<Decimal>,
+    /// flatten_loss per $ of fill_cost in bps: the slippage realized flattening
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flatten_slippage_bps: Option<Decimal>,
+}
+
+impl BundleResult {
+    /// Result for `legs` (all of one bundle) with nothing filled yet.
+    pub fn new(bundle_id: Uuid, status: BundleStatus, legs: &[OrderIntent]) -> Self {
+        let theoretical_cost: Decimal = legs.iter().map(|l| l.price * l.size).sum();
+        Self {
+            bundle_id,
+            market_id: legs.first().map(|x| x.market_id.clone()).unwrap_or_default(),
+            status,
+            legs: legs.len(),
+            legs_filled: 0,
+            theoretical_cost,
+            fill_cost: None,
+            theoretical_edge_bps: edge_bps(legs, theoretical_cost).unwrap_or(Decimal::ZERO),
+            realized_edge_bps: None,
+            flatten_proceeds: None,
+            flatten_loss: None,
+            flatten_slippage_bps: None,
+        }
+    }
+
+    /// Records the (price, shares) fill of each leg (None when the leg did not fill), in
+    /// leg order. Realized edge needs every leg filled in full.
+    pub fn with_fills(mut self, legs: &[OrderIntent], fills: &[Option<(Decimal, Decimal)>]) -> Self {
+        let filled: Vec<Decimal> = fills.iter().flatten().map(|(px, qty)| px * qty).collect();
+        self.legs_filled = filled.len();
+        if !filled.is_empty() {
+            let cost: Decimal = filled.iter().sum();
+            self.fill_cost = Some(cost);
+            let complete = legs.iter().zip(fills).all(|(l, f)| f.is_some_and(|(_, qty)| qty >= l.size));
+            if complete {
+                self.realized_edge_bps = edge_bps(legs, cost);
+            }
+        }
+        self
+    }
+
+    /// Records the sells of a flattened bundle (see `flatten_intents`), all filled, and
+    /// marks it `Flattened`.
+    pub fn with_flatten(mut self, sells: &[OrderIntent]) -> Self {
+        let proceeds: Decimal = sells.iter().map(|s| s.price * s.size).sum();
+        self.status = BundleStatus::Flattened;
+        self.flatten_proceeds = Some(proceeds);
+        if let Some(cost) = self.fill_cost {
+            let loss = cost - proceeds;
+            self.flatten_loss = Some(loss);
+            if cost > Decimal::ZERO {
+                self.flatten_slippage_bps = Some((loss / cost * Decimal::from(10_000)).round_dp(2));
+            }
+        }
+        self
+    }
+
+    /// Edge lost between decision and fill in bps; positive means fills were worse.
+    pub fn slippage_bps(&self) -> Option<Decimal> {
+        self.realized_edge_bps.map(|r| self.theoretical_edge_bps - r)
+    }
+}
+
+/// (payout - cost) / payout in bps, where a complete set of `legs` pays 1 per share of
+/// the smallest leg.
+pub fn edge_bps(legs: &[OrderIntent], cost: Decimal) -> Option<Decimal> {
+    let payout = legs.iter().map(|l| l.size).min()?;
+    if payout <= Decimal::ZERO {
+        return None;
+    }
+    Some(((payout - cost) / payout * Decimal::from(10_000)).round_dp(2))
+}
+
+/// Orders still resting after a shutdown attempt, for manual cleanup.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ShutdownReport {
+    pub cancelled: Vec<String>,
+    pub not_cancelled: Vec<String>,
+}
+
+/// Anything that can act on a cycle's intents: log them, simulate them, or trade them.
+pub trait Executor: Send + Sync {
+    fn execute(&self, intents: Vec<OrderIntent>) -> BoxFuture<'_, Result<Vec<BundleResult>>>;
+
+    /// Called once on graceful shutdown. Executors that leave orders resting on the venue
+    /// cancel them here; `main` bounds the call with `shutdown_timeout_ms`. Executors that
+    /// never hold open orders (observer, mock) have nothing to do.
+    fn shutdown(&self) -> BoxFuture<'_, Result<ShutdownReport>> {
+        Box::pin(async { Ok(ShutdownReport::default()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::testutil::intents::{bundle, bundle_legs};
+
+    fn legs() -> Vec<OrderIntent> {
+        bundle_legs("m", &[("a", dec!(0.40)), ("b", dec!(0.50))], dec!(10), bundle(1))
+    }
+
+    #[test]
+    fn better_fills_give_negative_slippage() {
+        let r = BundleResult::new(bundle(1), BundleStatus::Filled, &legs())
+            .with_fills(&legs(), &[Some((dec!(0.39), dec!(10))), Some((dec!(0.50), dec!(10)))]);
+        assert_eq!(r.theoretical_edge_bps, dec!(1000));
+        assert_eq!(r.realized_edge_bps, Some(dec!(1100)));
+        assert_eq!(r.slippage_bps(), Some(dec!(-100)));
+    }
+
+    #[test]
+    fn short_fills_have_no_realized_edge() {
+        let r = BundleResult::new(bundle(1), BundleStatus::Partial, &legs())
+            .with_fills(&legs(), &[Some((dec!(0.40), dec!(10))), Some((dec!(0.50), dec!(4)))]);
+        assert_eq!((r.legs_filled, r.fill_cost), (2, Some(dec!(6.00))));
+        assert_eq!(r.realized_edge_bps, None);
+        assert_eq!(r.slippage_bps(), None);
+    }
+
+    #[test]
+    fn stats_average_the_recorded_slippage() {
+        let stats = crate::stats::Stats::new(0);
+        stats.record_slippage(dec!(92));
+        stats.record_slippage(dec!(-12.5));
+        let snap = stats.snapshot(0);
+        assert_eq!(snap.slippage_bundles, 2);
+        assert_eq!(snap.avg_slippage_bps, 39.75);
+    }
+}
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+use crate::http::HttpClient;
+use crate::pm::market_data::MarketDef;
+
+const EVENTS_PAGE_LIMIT: usize = 100;
+
+/// Event a condition id belongs to, plus the market's own activity, per the Gamma API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventTag {
+    pub event_id: String,
+    pub event_title: String,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    pub liquidity: Option<f64>,
+    pub volume_24h: Option<f64>,
+}
+
+struct Cache {
+    by_condition: HashMap<String, EventTag>,
+    fetched_at: Option<Instant>,
+}
+
+/// Client for Gamma's /events, used only to tag CLOB markets with their event.
+///
+/// The condition id -> event map is refetched when older than `ttl`. If Gamma is down the
+/// previous map (possibly empty) keeps being used, so markets flow untagged rather than
+/// stopping the bot.
+pub struct GammaClient {
+    host: String,
+    http: HttpClient,
+    ttl: Duration,
+    max_pages: usize,
+    cache: Mutex<Cache>,
+}
+
+impl GammaClient {
+    pub fn new(host: String, http: HttpClient, ttl_sec: u64, max_pages: usize) -> Self {
+        Self {
+            host,
+            http,
+            ttl: Duration::from_secs(ttl_sec),
+            max_pages: max_pages.max(1),
+            cache: Mutex::new(Cache { by_condition: HashMap::new(), fetched_at: None }),
+        }
+    }
+
+    /// Sets `event_id`/`event_title` on every market Gamma knows, plus the event's category
+    /// and tags where the CLOB gave none; returns how many were tagged.
+    pub async fn tag_markets(&self, markets: &mut [MarketDef]) -> usize {
+        let stale = {
+            let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+            cache.fetched_at.map(|t| t.elapsed() >= self.ttl).unwrap_or(true)
+        };
+        if stale {
+            match self.fetch_event_map().await {
+                Ok(map) => {
+                    tracing::info!(conditions = map.len(), "gamma events loaded");
+                    let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+                    cache.by_condition = map;
+                    cache.fetched_at = Some(Instant::now());
+                }
+                Err(e) => {
+                    tracing::warn!(err = %format!("{:#}", e), "gamma unreachable, keeping previous event tags");
+                }
+            }
+        }
+
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let mut tagged = 0;
+        for m in markets.iter_mut() {
+            if let Some(tag) = cache.by_condition.get(&m.market_id) {
+                m.event_id = Some(tag.event_id.clone());
+                m.event_title = Some(tag.event_title.clone());
+                if m.category.is_none() {
+                    m.category = tag.category.clone();
+                }
+                if m.tags.is_empty() {
+                    m.tags = tag.tags.clone();
+                }
+                m.liquidity = tag.liquidity;
+                m.volume_24h = tag.volume_24h;
+                tagged += 1;
+            }
+        }
+        tagged
+    }
+
+    /// Open events, paged with limit/offset up to `max_pages`.
+    async fn fetch_event_map(&self) -> Result<HashMap<String, EventTag>> {
+        let mut out = HashMap::new();
+        for page in 0..self.max_pages {
+            let url = format!(
+                "{}/events?closed=false&limit={}&offset={}",
+                self.host.trim_end_matches('/'),
+                EVENTS_PAGE_LIMIT,
+                page * EVENTS_PAGE_LIMIT
+            );
+            let events: Vec<GammaEvent> = self.http.get_json(&url, &[]).await.context("GET gamma /events failed")?;
+            let n = events.len();
+            for ev in events {
+                let tag = EventTag {
+                    event_id: ev.id,
+                    event_title: ev.title,
+                    category: ev.category.filter(|c| !c.trim().is_empty()),
+                    tags: ev.tags.into_iter().map(|t| t.label).filter(|l| !l.is_empty()).collect(),
+                    liquidity: None,
+                    volume_24h: None,
+                };
+                for m in ev.markets.into_iter().filter(|m| !m.condition_id.is_empty()) {
+                    out.insert(m.condition_id, EventTag { liquidity: m.liquidity, volume_24h: m.volume_24h, ..tag.clone() });
+                }
+            }
+            if n < EVENTS_PAGE_LIMIT {
+                break;
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GammaEvent {
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    markets: Vec<GammaMarket>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    tags: Vec<GammaTag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GammaTag {
+    #[serde(default)]
+    label: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct GammaMarket {
+    #[serde(rename = "conditionId", default)]
+    condition_id: String,
+    #[serde(rename = "liquidityNum", default)]
+    liquidity: Option<f64>,
+    #[serde(rename = "volume24hr", default)]
+    volume_24h: Option<f64>,
+}
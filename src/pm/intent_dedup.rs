@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::types::{OrderIntent, Side, TokenId};
+
+/// Prices closer than one tick (0.001) are the same logical order.
+const PRICE_BUCKET_DP: u32 = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct DedupKey {
+    market_id: String,
+    token_id: TokenId,
+    side: Side,
+    price_bucket: Decimal,
+}
+
+impl DedupKey {
+    fn of(i: &OrderIntent) -> Self {
+        Self {
+            market_id: i.market_id.clone(),
+            token_id: i.token_id.clone(),
+            side: i.side.clone(),
+            price_bucket: i.price.round_dp(PRICE_BUCKET_DP),
+        }
+    }
+}
+
+/// Sliding-window duplicate guard in front of the executor.
+///
+/// A bundle is dropped whole if any of its legs matches a leg sent within the window,
+/// so a bundle is never partially executed because of dedup. The store is pruned by
+/// age on every call and hard-capped at `max_entries` (oldest evicted first).
+pub struct IntentDedup {
+    window_ms: u64,
+    max_entries: usize,
+    seen: Mutex<HashMap<DedupKey, u64>>,
+}
+
+impl IntentDedup {
+    pub fn new(window_ms: u64, max_entries: usize) -> Self {
+        Self {
+            window_ms,
+            max_entries: max_entries.max(1),
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the intents to execute and the bundles dropped as duplicates.
+    pub fn filter(&self, intents: Vec<OrderIntent>, now_ms: u64) -> (Vec<OrderIntent>, Vec<(Uuid, Vec<OrderIntent>)>) {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        let window = self.window_ms;
+        seen.retain(|_, ts| now_ms.saturating_sub(*ts) < window);
+
+        // group legs by bundle, keeping first-seen bundle order
+        let mut order: Vec<Uuid> = vec![];
+        let mut by_bundle: HashMap<Uuid, Vec<OrderIntent>> = HashMap::new();
+        for i in intents {
+            if !by_bundle.contains_key(&i.bundle_id) {
+                order.push(i.bundle_id);
+            }
+            by_bundle.entry(i.bundle_id).or_default().push(i);
+        }
+
+        let mut kept: Vec<OrderIntent> = vec![];
+        let mut dropped: Vec<(Uuid, Vec<OrderIntent>)> = vec![];
+        for bid in order {
+            let legs = by_bundle.remove(&bid).unwrap_or_default();
+            let keys: Vec<DedupKey> = legs.iter().map(DedupKey::of).collect();
+            if keys.iter().any(|k| seen.contains_key(k)) {
+                dropped.push((bid, legs));
+                continue;
+            }
+            for k in keys {
+                seen.insert(k, now_ms);
+            }
+            kept.extend(legs);
+        }
+
+        if seen.len() > self.max_entries {
+            let mut by_age: Vec<(DedupKey, u64)> = seen.drain().collect();
+            by_age.sort_by_key(|e| std::cmp::Reverse(e.1));
+            by_age.truncate(self.max_entries);
+            seen.extend(by_age);
+        }
+
+        (kept, dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::testutil::intents::{bundle, bundle_legs};
+
+    fn ids(intents: &[OrderIntent]) -> Vec<Uuid> {
+        let mut out: Vec<Uuid> = intents.iter().map(|i| i.bundle_id).collect();
+        out.dedup();
+        out
+    }
+
+    #[test]
+    fn repeat_within_the_window_is_dropped_and_after_it_is_kept() {
+        let d = IntentDedup::new(1_000, 100);
+        let legs = |n| bundle_legs("m", &[("a", dec!(0.45)), ("b", dec!(0.47))], dec!(10), bundle(n));
+        assert_eq!(ids(&d.filter(legs(1), 0).0), [bundle(1)]);
+        let (kept, dropped) = d.filter(legs(2), 999);
+        assert!(kept.is_empty());
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(ids(&d.filter(legs(3), 2_000).0), [bundle(3)]);
+    }
+
+    #[test]
+    fn a_bundle_sharing_one_leg_is_dropped_whole() {
+        let d = IntentDedup::new(60_000, 100);
+        d.filter(bundle_legs("m", &[("a", dec!(0.45)), ("b", dec!(0.47))], dec!(10), bundle(1)), 0);
+
+        // same first leg (price within a tick), different second leg
+        let partial = bundle_legs("m", &[("a", dec!(0.4502)), ("c", dec!(0.30))], dec!(10), bundle(2));
+        let other = bundle_legs("n", &[("x", dec!(0.2)), ("y", dec!(0.7))], dec!(10), bundle(3));
+        let (kept, dropped) = d.filter(partial.into_iter().chain(other).collect(), 10);
+
+        assert_eq!(ids(&kept), [bundle(3)]);
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].0, bundle(2));
+        // both legs go back with the dropped bundle, none executed alone
+        assert_eq!(dropped[0].1.len(), 2);
+        // and the unmatched leg was not remembered
+        assert_eq!(ids(&d.filter(bundle_legs("m", &[("c", dec!(0.30))], dec!(10), bundle(4)), 20).0), [bundle(4)]);
+    }
+
+    #[test]
+    fn store_is_capped_oldest_first() {
+        let d = IntentDedup::new(60_000, 2);
+        for (n, token) in ["a", "b", "c"].iter().enumerate() {
+            d.filter(bundle_legs("m", &[(token, dec!(0.5))], dec!(1), bundle(n as u128)), n as u64);
+        }
+        // "a" was evicted, "c" is still remembered
+        assert_eq!(d.filter(bundle_legs("m", &[("a", dec!(0.5))], dec!(1), bundle(10)), 3).0.len(), 1);
+        assert!(d.filter(bundle_legs("m", &[("c", dec!(0.5))], dec!(1), bundle(11)), 4).0.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use uuid::Uuid;
+
+use crate::pm::executor::{BundleResult, BundleStatus, Executor, ShutdownReport};
+use crate::stats::Stats;
+use crate::types::{OrderIntent, TokenId};
+
+/// Token-level anti-churn guard: a token gets at most one intent per `min_interval_ms`,
+/// whichever strategy or market emitted it.
+///
+/// Unlike `IntentDedup` this ignores side and price, so two different orders on the same
+/// token inside the window still collide. A bundle touching any token sent within the
+/// window is dropped whole, keeping bundles atomic.
+pub struct IntentGate {
+    min_interval_ms: u64,
+    last_sent: Mutex<HashMap<TokenId, u64>>,
+}
+
+impl IntentGate {
+    pub fn new(min_interval_ms: u64) -> Self {
+        Self { min_interval_ms, last_sent: Mutex::new(HashMap::new()) }
+    }
+
+    /// Returns the intents to execute and the bundles suppressed, in first-seen bundle
+    /// order. Earlier bundles in `intents` win over later ones sharing a token.
+    pub fn filter(&self, intents: Vec<OrderIntent>, now_ms: u64) -> (Vec<OrderIntent>, Vec<(Uuid, Vec<OrderIntent>)>) {
+        let mut last_sent = self.last_sent.lock().unwrap_or_else(|e| e.into_inner());
+        let interval = self.min_interval_ms;
+        last_sent.retain(|_, ts| now_ms.saturating_sub(*ts) < interval);
+
+        let mut order: Vec<Uuid> = vec![];
+        let mut by_bundle: HashMap<Uuid, Vec<OrderIntent>> = HashMap::new();
+        for i in intents {
+            if !by_bundle.contains_key(&i.bundle_id) {
+                order.push(i.bundle_id);
+            }
+            by_bundle.entry(i.bundle_id).or_default().push(i);
+        }
+
+        let mut kept: Vec<OrderIntent> = vec![];
+        let mut suppressed: Vec<(Uuid, Vec<OrderIntent>)> = vec![];
+        for bid in order {
+            let legs = by_bundle.remove(&bid).unwrap_or_default();
+            if legs.iter().any(|l| last_sent.contains_key(&l.token_id)) {
+                suppressed.push((bid, legs));
+                continue;
+            }
+            for l in &legs {
+                last_sent.insert(l.token_id.clone(), now_ms);
+            }
+            kept.extend(legs);
+        }
+        (kept, suppressed)
+    }
+}
+
+/// Executor wrapper applying an `IntentGate` to everything the strategies emit before
+/// it reaches the inner executor.
+pub struct GatedExecutor {
+    inner: Box<dyn Executor>,
+    gate: IntentGate,
+    stats: Arc<Stats>,
+}
+
+impl GatedExecutor {
+    pub fn new(inner: Box<dyn Executor>, gate: IntentGate, stats: Arc<Stats>) -> Self {
+        Self { inner, gate, stats }
+    }
+
+    async fn run(&self, intents: Vec<OrderIntent>) -> Result<Vec<BundleResult>> {
+        let now = chrono::Utc::now().timestamp_millis() as u64;
+        let (kept, suppressed) = self.gate.filter(intents, now);
+        let mut results: Vec<BundleResult> = vec![];
+        for (bid, legs) in &suppressed {
+            tracing::info!(bundle_id=%bid, status="suppressed_churn", "bundle dropped: token had an intent within the anti-churn interval");
+            results.push(BundleResult::new(*bid, BundleStatus::SuppressedChurn, legs));
+        }
+        self.stats.add_bundles_suppressed_churn(suppressed.len() as u64);
+        results.extend(self.inner.execute(kept).await?);
+        Ok(results)
+    }
+}
+
+impl Executor for GatedExecutor {
+    fn execute(&self, intents: Vec<OrderIntent>) -> BoxFuture<'_, Result<Vec<BundleResult>>> {
+        Box::pin(self.run(intents))
+    }
+
+    fn shutdown(&self) -> BoxFuture<'_, Result<ShutdownReport>> {
+        self.inner.shutdown()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::pm::execution_observer::ExecutionObserver;
+    use crate::testutil::intents::{bundle, bundle_legs, buy};
+
+    fn ids(bundles: &[(Uuid, Vec<OrderIntent>)]) -> Vec<Uuid> {
+        bundles.iter().map(|(b, _)| *b).collect()
+    }
+
+    fn kept_bundles(kept: &[OrderIntent]) -> Vec<Uuid> {
+        let mut out: Vec<Uuid> = kept.iter().map(|i| i.bundle_id).collect();
+        out.dedup();
+        out
+    }
+
+    #[test]
+    fn a_second_strategy_on_the_same_token_is_suppressed() {
+        let gate = IntentGate::new(1_000);
+        let arb = bundle_legs("m1", &[("1", dec!(0.45)), ("2", dec!(0.47))], dec!(10), bundle(1));
+        // another strategy's single resting bid on token 2, different price and market
+        let mut maker = buy("m1", "2", dec!(0.40), dec!(5), bundle(2));
+        maker.meta.strategy = "maker".into();
+
+        let mut intents = arb;
+        intents.push(maker);
+        let (kept, suppressed) = gate.filter(intents, 0);
+        assert_eq!(kept_bundles(&kept), [bundle(1)]);
+        assert_eq!(ids(&suppressed), [bundle(2)]);
+    }
+
+    #[test]
+    fn a_later_cycle_inside_the_window_is_suppressed() {
+        let gate = IntentGate::new(1_000);
+        let legs = |n| bundle_legs("m1", &[("1", dec!(0.45)), ("2", dec!(0.47))], dec!(10), bundle(n));
+        assert_eq!(gate.filter(legs(1), 0).0.len(), 2);
+        assert_eq!(ids(&gate.filter(legs(2), 999).1), [bundle(2)]);
+        // the suppressed attempt did not extend the window
+        assert_eq!(gate.filter(legs(3), 1_000).0.len(), 2);
+    }
+
+    #[test]
+    fn a_bundle_sharing_one_leg_is_dropped_whole() {
+        let gate = IntentGate::new(1_000);
+        gate.filter(vec![buy("m1", "2", dec!(0.47), dec!(10), bundle(1))], 0);
+
+        // legs 1 and 3 are free, leg 2 is not: nothing of the bundle goes out
+        let three = bundle_legs("m2", &[("1", dec!(0.3)), ("2", dec!(0.3)), ("3", dec!(0.3))], dec!(10), bundle(2));
+        let (kept, suppressed) = gate.filter(three, 10);
+        assert!(kept.is_empty());
+        assert_eq!(suppressed[0].1.len(), 3);
+        // and its free legs were not marked as sent
+        assert_eq!(gate.filter(vec![buy("m3", "3", dec!(0.3), dec!(10), bundle(3))], 20).0.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn gated_executor_reports_suppressed_bundles() {
+        let stats = Stats::new(0);
+        let ex = GatedExecutor::new(Box::new(ExecutionObserver::new(stats.clone(), 0)), IntentGate::new(60_000), stats.clone());
+        let mut intents = bundle_legs("m1", &[("1", dec!(0.45)), ("2", dec!(0.47))], dec!(10), bundle(1));
+        intents.extend(bundle_legs("m2", &[("2", dec!(0.47)), ("3", dec!(0.47))], dec!(10), bundle(2)));
+        let results = ex.execute(intents).await.unwrap();
+        let status = |b| results.iter().find(|r| r.bundle_id == b).unwrap().status;
+        assert_eq!((status(bundle(1)), status(bundle(2))), (BundleStatus::Logged, BundleStatus::SuppressedChurn));
+        assert_eq!(stats.snapshot(0).bundles_suppressed_churn, 1);
+    }
+}
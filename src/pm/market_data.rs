@@ -1,82 +1,840 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
 use anyhow::{Context, Result};
 use futures::{stream, StreamExt};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 
-use crate::types::{GlobalSnapshot, MarketBook, OutcomeTop};
+use crate::config::Settings;
+use crate::http::{hedged, Hedge, HttpClient};
+use crate::pm::prefilter::PriceBasis;
+use crate::stats::Stats;
+use crate::types::{BookLevel, GlobalSnapshot, MarketBook, OutcomeTop, Polarity, TokenId};
 
-#[derive(Debug, Clone)]
+/// What snapshot assembly does with a market when some of its token books are missing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingBookPolicy {
+    /// Leave the market out of the snapshot
+    #[default]
+    DropMarket,
+    /// Fill missing legs from the last book seen for the token, if it is younger than the
+    /// cached top max age; markets with a leg without such a book are still dropped
+    DropCycleUseCached,
+    /// Include the market with the legs that were found, flagged `partial` so the
+    /// strategy logs but never executes it
+    KeepPartialForLogging,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketDef {
     pub market_id: String,
     pub question: String,
-    pub token_ids: Vec<String>,
+    /// Yes for a venue market's own outcome tokens; No for a synthetic basket of the
+    /// NO tokens of a neg-risk event's markets
+    pub polarity: Polarity,
+    pub token_ids: Vec<TokenId>,
+    /// Gamma event this market belongs to, when known
+    pub event_id: Option<String>,
+    pub event_title: Option<String>,
+    /// Venue category (CLOB market, else its Gamma event); None when neither gives one
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    /// Smallest order the venue accepts per leg, in shares, when known
+    #[serde(default)]
+    pub min_order_size: Option<Decimal>,
+    /// Gamma's current liquidity and 24h volume in USD, when known
+    #[serde(default)]
+    pub liquidity: Option<f64>,
+    #[serde(default)]
+    pub volume_24h: Option<f64>,
+    /// What a complete set pays, overriding the polarity's payoff; None on every venue
+    /// market, set per market with REDEMPTION_VALUES or in a replay market file
+    #[serde(default)]
+    pub redemption_value: Option<Decimal>,
+    /// Most markets listing one of this market's tokens, this one included, when some
+    /// token is shared (TOKEN_COLLISION_POLICY=share); None when no token is
+    #[serde(default)]
+    pub shared_by: Option<usize>,
+}
+
+impl MarketDef {
+    /// Payout per share of a complete set; see `MarketBook::payoff`.
+    pub fn payoff(&self) -> Decimal {
+        self.redemption_value.unwrap_or_else(|| self.polarity.payoff(self.token_ids.len()))
+    }
+}
+
+/// Which CLOB listing a market refresh walks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MarketsEndpoint {
+    /// GET /markets: every field, the heaviest pages
+    #[default]
+    Full,
+    /// GET /simplified-markets: condition id, tokens and trading flags only. No question
+    /// text (backfilled per market on demand, see `MarketData::backfill_questions`), and no
+    /// neg-risk, category, tag or minimum order size fields
+    Simplified,
+    /// GET /sampling-simplified-markets: the simplified shape, limited to the markets in
+    /// the venue's rewards sampling
+    Sampling,
+}
+
+impl MarketsEndpoint {
+    fn path(&self) -> &'static str {
+        match self {
+            MarketsEndpoint::Full => "markets",
+            MarketsEndpoint::Simplified => "simplified-markets",
+            MarketsEndpoint::Sampling => "sampling-simplified-markets",
+        }
+    }
+}
+
+/// Concurrent GET /markets/{id} requests per question backfill.
+const BACKFILL_CONCURRENCY: usize = 4;
+
+/// Venue flags a market needs to be scanned. All on by default: a market is scanned only
+/// when it has an order book, accepts orders and is not closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarketEligibility {
+    pub require_order_book: bool,
+    pub require_accepting_orders: bool,
+    pub skip_closed: bool,
+}
+
+impl Default for MarketEligibility {
+    fn default() -> Self {
+        Self { require_order_book: true, require_accepting_orders: true, skip_closed: true }
+    }
+}
+
+/// What a market refresh does when one token id is listed by more than one market. Books
+/// are keyed by token, so such markets quote the same liquidity.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenCollisionPolicy {
+    /// Keep the first market listing the token and drop the later ones
+    #[default]
+    DropLater,
+    /// Keep them all, marking each with `shared_by` so the strategy sizes its bundles
+    /// against its share of the book. NO baskets reuse the NO tokens of their binary
+    /// markets, so NO_SIDE_ARB needs this
+    Share,
+}
+
+/// Applies `policy` to markets sharing token ids, in list order; returns how many markets
+/// were dropped or marked shared.
+pub fn resolve_token_collisions(markets: &mut Vec<MarketDef>, policy: TokenCollisionPolicy) -> usize {
+    let mut collided = 0;
+    match policy {
+        TokenCollisionPolicy::DropLater => {
+            let mut owner: HashMap<TokenId, String> = HashMap::new();
+            markets.retain(|m| {
+                if let Some((t, kept)) = m.token_ids.iter().find_map(|t| owner.get(t).map(|o| (t, o))) {
+                    tracing::warn!(token_id = %t, kept = %kept, dropped = %m.market_id, "token id listed by more than one market, dropping the later one");
+                    collided += 1;
+                    return false;
+                }
+                for t in &m.token_ids {
+                    owner.insert(t.clone(), m.market_id.clone());
+                }
+                true
+            });
+        }
+        TokenCollisionPolicy::Share => {
+            let mut listed_by: HashMap<TokenId, Vec<&str>> = HashMap::new();
+            for m in markets.iter() {
+                for t in &m.token_ids {
+                    listed_by.entry(t.clone()).or_default().push(&m.market_id);
+                }
+            }
+            let shared: HashMap<TokenId, usize> = listed_by.into_iter()
+                .filter(|(_, ids)| ids.len() > 1)
+                .map(|(t, ids)| {
+                    tracing::debug!(token_id = %t, markets = %ids.join(","), "token id shared by markets");
+                    (t, ids.len())
+                })
+                .collect();
+            for m in markets.iter_mut() {
+                m.shared_by = m.token_ids.iter().filter_map(|t| shared.get(t).copied()).max();
+                collided += m.shared_by.is_some() as usize;
+            }
+        }
+    }
+    collided
+}
+
+/// Parses `"<market_id>:<value>,..."` redemption value overrides; values must be positive.
+pub fn parse_redemption_values(s: &str) -> Result<HashMap<String, Decimal>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let (id, v) = x.rsplit_once(':').with_context(|| format!("redemption value {:?}: expected market_id:value", x))?;
+            let v: Decimal = v.trim().parse().with_context(|| format!("redemption value {:?}: bad value", x))?;
+            anyhow::ensure!(v > Decimal::ZERO, "redemption value {:?}: must be positive", x);
+            Ok((id.trim().to_string(), v))
+        })
+        .collect()
+}
+
+/// Market list saved by `save_universe`; None when `path` does not exist yet.
+pub fn load_universe(path: &Path) -> Result<Option<Vec<MarketDef>>> {
+    match std::fs::read(path) {
+        Ok(bytes) => serde_json::from_slice(&bytes)
+            .map(Some)
+            .with_context(|| format!("decode market universe {}", path.display())),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("read market universe {}", path.display())),
+    }
+}
+
+/// Writes `markets` to `path` (write to temp, then rename).
+pub fn save_universe(path: &Path, markets: &[MarketDef]) -> Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, serde_json::to_vec(markets)?).with_context(|| format!("write {}", tmp.display()))?;
+    std::fs::rename(&tmp, path).with_context(|| format!("replace {}", path.display()))?;
+    Ok(())
+}
+
+/// The NO token of one open market belonging to a neg-risk event.
+#[derive(Debug, Clone)]
+pub struct NoLeg {
+    pub event_id: String,
+    pub question: String,
+    pub token_id: TokenId,
+    pub min_order_size: Option<Decimal>,
+}
+
+/// Cursor the CLOB returns once the last /markets page has been served.
+const END_CURSOR: &str = "LTE=";
+
+/// Why markets were not accepted at refresh. Each market counts under the first
+/// reason that applies, in field order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rejections {
+    pub order_book_disabled: usize,
+    pub closed: usize,
+    pub not_accepting_orders: usize,
+    /// Outcome labels match no allowed set
+    pub label_mismatch: usize,
+    /// Token list is not a usable complete set: an empty or repeated token id or label
+    pub incomplete_set: usize,
+    /// Some token id is not a decimal uint256 once trimmed
+    pub malformed_token_id: usize,
+    /// More than two outcomes while binary-only is set
+    pub multi_outcome: usize,
+    /// Outcome count outside the leg bounds
+    pub outside_leg_bounds: usize,
+}
+
+impl Rejections {
+    pub fn add(&mut self, other: &Rejections) {
+        self.order_book_disabled += other.order_book_disabled;
+        self.closed += other.closed;
+        self.not_accepting_orders += other.not_accepting_orders;
+        self.label_mismatch += other.label_mismatch;
+        self.incomplete_set += other.incomplete_set;
+        self.malformed_token_id += other.malformed_token_id;
+        self.multi_outcome += other.multi_outcome;
+        self.outside_leg_bounds += other.outside_leg_bounds;
+    }
+
+    pub fn total(&self) -> usize {
+        self.order_book_disabled + self.closed + self.not_accepting_orders + self.label_mismatch
+            + self.incomplete_set + self.malformed_token_id + self.multi_outcome + self.outside_leg_bounds
+    }
+}
+
+/// Open markets from one /markets page plus paging info.
+#[derive(Debug, Clone)]
+pub struct MarketsPage {
+    pub open: Vec<MarketDef>,
+    /// Markets on the page before filtering
+    pub total: usize,
+    pub rejected: Rejections,
+    /// NO tokens of open neg-risk markets; empty unless NO baskets are enabled
+    pub no_legs: Vec<NoLeg>,
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Clone)]
 pub struct MarketData {
     host: String,
-    http: reqwest::Client,
+    http: HttpClient,
     books_chunk_size: usize,
     books_concurrency: usize,
+    book_fallback_max: usize,
+    min_legs: usize,
+    max_legs: usize,
+    no_baskets: bool,
+    /// Skip markets with more than two outcomes
+    binary_only: bool,
+    /// Max /markets pages walked per refresh; 0 is unlimited
+    page_limit: usize,
+    /// Allowed outcome label sets, lowercased and sorted; empty allows everything
+    label_sets: Vec<Vec<String>>,
+    missing_book_policy: MissingBookPolicy,
+    token_collisions: TokenCollisionPolicy,
+    eligibility: MarketEligibility,
+    markets_endpoint: MarketsEndpoint,
+    /// Question text by market id, looked up for markets listed without one; an empty
+    /// entry is a lookup in flight
+    questions: Arc<Mutex<HashMap<String, String>>>,
+    /// Ask levels kept per book beyond the best price; 0 keeps the top only
+    ask_depth: usize,
+    /// Single-book fallback fetches slower than this get a second, racing request; None
+    /// disables hedging
+    hedge_after: Option<std::time::Duration>,
+    hedge_max_per_cycle: usize,
+    /// Hedges still allowed this cycle, reset by `fetch_tops`
+    hedges_left: Arc<std::sync::atomic::AtomicUsize>,
+    /// Last top seen per token with its fetch time (ms), for `DropCycleUseCached`; only
+    /// tokens of the last snapshot's markets are kept
+    last_tops: Arc<Mutex<HashMap<TokenId, (i64, OutcomeTop)>>>,
+    /// Cached tops older than this are neither used nor kept
+    cached_top_max_age_ms: i64,
+    /// Raw book JSON by token id for the current cycle; None unless capture_raw is set
+    raw_books: Option<Arc<Mutex<HashMap<String, serde_json::Value>>>>,
+    stats: std::sync::Arc<Stats>,
 }
 
 impl MarketData {
-    pub fn new(host: String, books_chunk_size: usize, books_concurrency: usize) -> Self {
+    /// The CLOB source `s` describes, filters and fetch policies included. `capture_raw`
+    /// keeps raw book bodies for forensics.
+    pub fn from_settings(s: &Settings, http: HttpClient, capture_raw: bool, stats: std::sync::Arc<Stats>) -> Self {
+        MarketData::new(
+            s.clob_host.clone(),
+            http,
+            s.books_chunk_size,
+            s.books_concurrency,
+            s.book_fallback_max,
+            capture_raw,
+            stats,
+        )
+        .with_leg_bounds(s.min_legs, s.max_legs)
+        .with_no_baskets(s.no_side_arb)
+        .with_binary_only(s.binary_only)
+        .with_page_limit(s.refresh_page_limit)
+        .with_missing_book_policy(s.missing_book_policy)
+        .with_cached_top_max_age(s.cached_top_max_age_ms)
+        .with_token_collisions(s.token_collision_policy)
+        .with_eligibility(MarketEligibility {
+            require_order_book: s.require_order_book,
+            require_accepting_orders: s.require_accepting_orders,
+            skip_closed: s.skip_closed_markets,
+        })
+        .with_markets_endpoint(s.pm_markets_endpoint)
+        .with_hedging(s.book_hedge_after_ms, s.book_hedge_max_per_cycle)
+        .with_ask_depth(if s.uses_depth_walk() { s.book_depth_levels } else { 0 })
+        .with_label_allowlist(
+            s.outcome_label_allowlist.as_deref().unwrap_or("")
+                .split(';')
+                .map(|set| set.split('|').map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect::<Vec<_>>())
+                .filter(|set| !set.is_empty())
+                .collect(),
+        )
+    }
+
+    pub fn new(
+        host: String,
+        http: HttpClient,
+        books_chunk_size: usize,
+        books_concurrency: usize,
+        book_fallback_max: usize,
+        capture_raw: bool,
+        stats: std::sync::Arc<Stats>,
+    ) -> Self {
         Self {
             host,
-            http: reqwest::Client::new(),
+            http,
             books_chunk_size: books_chunk_size.max(1),
             books_concurrency: books_concurrency.max(1),
+            book_fallback_max,
+            min_legs: 1,
+            max_legs: 0,
+            no_baskets: false,
+            binary_only: false,
+            page_limit: 0,
+            missing_book_policy: MissingBookPolicy::DropMarket,
+            token_collisions: TokenCollisionPolicy::DropLater,
+            eligibility: MarketEligibility::default(),
+            markets_endpoint: MarketsEndpoint::Full,
+            questions: Default::default(),
+            ask_depth: 0,
+            hedge_after: None,
+            hedge_max_per_cycle: 0,
+            hedges_left: Default::default(),
+            last_tops: Default::default(),
+            cached_top_max_age_ms: 5_000,
+            label_sets: vec![],
+            raw_books: capture_raw.then(Default::default),
+            stats,
         }
     }
 
+    /// Skip markets whose declared outcome count is outside [min_legs, max_legs]
+    /// at refresh, so their books are never fetched. `max_legs == 0` is unlimited.
+    pub fn with_leg_bounds(mut self, min_legs: usize, max_legs: usize) -> Self {
+        self.min_legs = min_legs.max(1);
+        self.max_legs = max_legs;
+        self
+    }
+
+    /// Also scan NO baskets: for every neg-risk event, the NO tokens of its open markets
+    /// as one bundle paying n - 1. Plain binary markets need nothing extra since their two
+    /// tokens already are each other's NO side.
+    pub fn with_no_baskets(mut self, enabled: bool) -> Self {
+        self.no_baskets = enabled;
+        self
+    }
+
+    /// Only scan binary markets; multi-outcome markets are skipped at refresh and counted.
+    pub fn with_binary_only(mut self, enabled: bool) -> Self {
+        self.binary_only = enabled;
+        self
+    }
+
+    /// Stop a refresh after `pages` /markets pages even if more remain. `0` is unlimited.
+    pub fn with_page_limit(mut self, pages: usize) -> Self {
+        self.page_limit = pages;
+        self
+    }
+
+    /// How snapshots treat markets with missing token books; see `MissingBookPolicy`.
+    pub fn with_missing_book_policy(mut self, policy: MissingBookPolicy) -> Self {
+        self.missing_book_policy = policy;
+        self
+    }
+
+    /// How old a cached top `DropCycleUseCached` may fill a missing leg with.
+    pub fn with_cached_top_max_age(mut self, ms: u64) -> Self {
+        self.cached_top_max_age_ms = ms.min(i64::MAX as u64) as i64;
+        self
+    }
+
+    /// Which venue flags a market needs to be scanned; see `MarketEligibility`.
+    pub fn with_eligibility(mut self, eligibility: MarketEligibility) -> Self {
+        self.eligibility = eligibility;
+        self
+    }
+
+    /// Which listing a refresh walks; see `MarketsEndpoint`.
+    pub fn with_markets_endpoint(mut self, endpoint: MarketsEndpoint) -> Self {
+        self.markets_endpoint = endpoint;
+        self
+    }
+
+    /// What a refresh does with markets sharing a token id; see `TokenCollisionPolicy`.
+    pub fn with_token_collisions(mut self, policy: TokenCollisionPolicy) -> Self {
+        self.token_collisions = policy;
+        self
+    }
+
+    /// Keep the best `levels` ask levels of every book in `OutcomeTop::ask_levels`, for
+    /// depth-aware pricing. `0` keeps the top of book only.
+    pub fn with_ask_depth(mut self, levels: usize) -> Self {
+        self.ask_depth = levels;
+        self
+    }
+
+    /// Hedge GET /book fallback fetches: one still pending after `after_ms` is raced by
+    /// an identical request, at most `max_per_cycle` times per cycle so a venue-wide
+    /// slowdown does not double the load. `after_ms == 0` disables hedging.
+    pub fn with_hedging(mut self, after_ms: u64, max_per_cycle: usize) -> Self {
+        self.hedge_after = (after_ms > 0).then(|| std::time::Duration::from_millis(after_ms));
+        self.hedge_max_per_cycle = max_per_cycle;
+        self
+    }
+
+    /// Only keep markets whose outcome labels are exactly one of `sets` (case-insensitive,
+    /// any order), e.g. [["Yes", "No"]]. Guards against mismapped token sets.
+    pub fn with_label_allowlist(mut self, sets: Vec<Vec<String>>) -> Self {
+        self.label_sets = sets.into_iter().map(|s| normalize_labels(s.iter().map(String::as_str))).collect();
+        self
+    }
+
+    fn labels_allowed(&self, tokens: &[TokenItem]) -> bool {
+        if self.label_sets.is_empty() {
+            return true;
+        }
+        let labels = normalize_labels(tokens.iter().map(|t| t.outcome.as_str()));
+        self.label_sets.contains(&labels)
+    }
+
     pub async fn fetch_open_markets(&self, max_markets: usize) -> Result<Vec<MarketDef>> {
+        let started = Instant::now();
         let mut out: Vec<MarketDef> = vec![];
+        let mut no_legs: Vec<NoLeg> = vec![];
         let mut next: Option<String> = None;
+        let mut pages = 0usize;
+        let mut scanned = 0usize;
+        let mut rejected = Rejections::default();
+        // pages can overlap while the market set shifts under the cursor; first copy wins
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut seen_no_legs: HashSet<TokenId> = HashSet::new();
+        let mut duplicates = 0usize;
+        // accepted markets per second, smoothed over pages; the venue gives no total up front
+        let mut rate: Option<f64> = None;
+        let mut page_limit_hit = false;
+
+        'pages: loop {
+            let page_started = Instant::now();
+            let page = self.fetch_markets_page(next.as_deref()).await?;
+            pages += 1;
+            scanned += page.total;
+            rejected.add(&page.rejected);
+            if page.rejected.label_mismatch > 0 {
+                self.stats.add_label_mismatch(page.rejected.label_mismatch as u64);
+            }
+            if page.rejected.multi_outcome > 0 {
+                self.stats.add_multi_outcome(page.rejected.multi_outcome as u64);
+            }
+            no_legs.extend(page.no_legs.into_iter().filter(|l| seen_no_legs.insert(l.token_id.clone())));
+            let before = out.len();
+            for m in page.open.into_iter() {
+                if !seen.insert(m.market_id.clone()) {
+                    duplicates += 1;
+                    continue;
+                }
+                out.push(m);
+                if out.len() >= max_markets {
+                    break 'pages;
+                }
+            }
+
+            let page_rate = (out.len() - before) as f64 / page_started.elapsed().as_secs_f64().max(1e-3);
+            let r = rate.map(|r| 0.7 * r + 0.3 * page_rate).unwrap_or(page_rate);
+            rate = Some(r);
+            let remaining = max_markets - out.len();
+            tracing::debug!(
+                pages,
+                scanned,
+                accepted = out.len(),
+                pct = format!("{:.1}", out.len() as f64 * 100.0 / max_markets.max(1) as f64),
+                eta_sec = (r > 0.0).then(|| format!("{:.0}", remaining as f64 / r)),
+                "market refresh progress"
+            );
+
+            next = page.next_cursor.filter(|c| c != END_CURSOR);
+            if next.is_none() { break; }
+            if self.page_limit > 0 && pages >= self.page_limit {
+                page_limit_hit = true;
+                break;
+            }
+        }
+
+        if page_limit_hit {
+            tracing::warn!(pages, accepted = out.len(), max_markets, "market refresh stopped at REFRESH_PAGE_LIMIT");
+        }
+        if duplicates > 0 {
+            tracing::info!(duplicates, "markets repeated across /markets pages, later copies dropped");
+        }
+        tracing::info!(
+            pages,
+            scanned,
+            accepted = out.len(),
+            rejected = rejected.total(),
+            order_book_disabled = rejected.order_book_disabled,
+            closed = rejected.closed,
+            not_accepting_orders = rejected.not_accepting_orders,
+            label_mismatch = rejected.label_mismatch,
+            incomplete_set = rejected.incomplete_set,
+            malformed_token_id = rejected.malformed_token_id,
+            multi_outcome = rejected.multi_outcome,
+            outside_leg_bounds = rejected.outside_leg_bounds,
+            duplicates,
+            endpoint = ?self.markets_endpoint,
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "market refresh done"
+        );
+
+        if self.no_baskets {
+            let baskets = self.no_baskets_from(no_legs);
+            tracing::debug!(baskets = baskets.len(), "NO baskets built from neg-risk events");
+            out.extend(baskets);
+        }
+        let collided = resolve_token_collisions(&mut out, self.token_collisions);
+        if collided > 0 {
+            self.stats.add_token_collisions(collided as u64);
+            tracing::info!(markets = collided, policy = ?self.token_collisions, "markets sharing token ids");
+        }
+        Ok(out)
+    }
+
+    /// Groups NO legs by event. A basket may miss some of the event's markets (closed or
+    /// beyond max_markets); at most one outcome wins, so k NO legs still pay at least k - 1.
+    fn no_baskets_from(&self, legs: Vec<NoLeg>) -> Vec<MarketDef> {
+        let mut order: Vec<String> = vec![];
+        let mut by_event: HashMap<String, Vec<NoLeg>> = HashMap::new();
+        for l in legs {
+            if !by_event.contains_key(&l.event_id) {
+                order.push(l.event_id.clone());
+            }
+            by_event.entry(l.event_id.clone()).or_default().push(l);
+        }
+
+        let mut out = vec![];
+        for event_id in order {
+            let legs = by_event.remove(&event_id).unwrap_or_default();
+            let n = legs.len();
+            if n < self.min_legs.max(2) || (self.max_legs > 0 && n > self.max_legs) {
+                continue;
+            }
+            // every leg has to clear its own market's minimum
+            let min_order_size = legs.iter().filter_map(|l| l.min_order_size).max();
+            out.push(MarketDef {
+                market_id: format!("{}:no", event_id),
+                question: format!("NO basket ({} outcomes): {}", n, legs[0].question),
+                polarity: Polarity::No,
+                token_ids: legs.into_iter().map(|l| l.token_id).collect(),
+                event_id: None,
+                event_title: None,
+                category: None,
+                tags: vec![],
+                min_order_size,
+                liquidity: None,
+                volume_24h: None,
+                redemption_value: None,
+                shared_by: None,
+            });
+        }
+        out
+    }
+
+    /// One page of the markets listing, keeping only open order-book markets.
+    pub async fn fetch_markets_page(&self, cursor: Option<&str>) -> Result<MarketsPage> {
+        let path = self.markets_endpoint.path();
+        let mut url = format!("{}/{}", self.host.trim_end_matches('/'), path);
+        if let Some(c) = cursor {
+            url = format!("{}?next_cursor={}", url, c);
+        }
+
+        let (data, next_cursor) = match self.markets_endpoint {
+            MarketsEndpoint::Full => {
+                let resp: MarketsResp<MarketItem> = self.http
+                    .get_json(&url, &[])
+                    .await
+                    .with_context(|| format!("GET /{} failed", path))?;
+                (resp.data, resp.next_cursor)
+            }
+            MarketsEndpoint::Simplified | MarketsEndpoint::Sampling => {
+                let resp: MarketsResp<SimplifiedMarketItem> = self.http
+                    .get_json(&url, &[])
+                    .await
+                    .with_context(|| format!("GET /{} failed", path))?;
+                (resp.data.into_iter().map(MarketItem::from).collect(), resp.next_cursor)
+            }
+        };
 
-        loop {
-            let mut url = format!("{}/markets", self.host.trim_end_matches('/'));
-            if let Some(ref c) = next {
-                url = format!("{}?next_cursor={}", url, c);
-            }
-
-            let resp: MarketsResp = self.http
-                .get(url)
-                .send()
-                .await
-                .context("GET /markets failed")?
-                .error_for_status()
-                .context("GET /markets non-200")?
-                .json()
-                .await
-                .context("decode /markets json failed")?;
-
-            for m in resp.data.into_iter() {
-                if m.enable_order_book && m.accepting_orders && !m.closed {
-                    let token_ids: Vec<String> = m.tokens.into_iter().map(|t| t.token_id).collect();
-                    if !token_ids.is_empty() {
-                        out.push(MarketDef {
-                            market_id: m.condition_id,
-                            question: m.question,
-                            token_ids,
+        let total = data.len();
+        let mut open = vec![];
+        let mut rejected = Rejections::default();
+        let mut no_legs = vec![];
+        for mut m in data.into_iter() {
+            let e = self.eligibility;
+            if e.require_order_book && !m.enable_order_book {
+                rejected.order_book_disabled += 1;
+            } else if e.skip_closed && m.closed {
+                rejected.closed += 1;
+            } else if e.require_accepting_orders && !m.accepting_orders {
+                rejected.not_accepting_orders += 1;
+            } else {
+                if !self.labels_allowed(&m.tokens) {
+                    rejected.label_mismatch += 1;
+                    continue;
+                }
+                if !normalize_tokens(&mut m.tokens) {
+                    tracing::debug!(market_id = %m.condition_id, question = %m.question, "malformed token id, skipping market");
+                    rejected.malformed_token_id += 1;
+                    continue;
+                }
+                if !complete_set(&m.tokens) {
+                    tracing::debug!(market_id = %m.condition_id, question = %m.question, tokens = m.tokens.len(), "incomplete token set, skipping market");
+                    rejected.incomplete_set += 1;
+                    continue;
+                }
+                if self.binary_only && m.tokens.len() > 2 {
+                    rejected.multi_outcome += 1;
+                    continue;
+                }
+                if self.no_baskets && m.neg_risk {
+                    let no = m.tokens.iter().find(|t| t.outcome.eq_ignore_ascii_case("no"));
+                    if let (Some(event_id), Some(no)) = (&m.neg_risk_market_id, no) {
+                        no_legs.push(NoLeg {
+                            event_id: event_id.clone(),
+                            question: m.question.clone(),
+                            token_id: TokenId::from(no.token_id.as_str()),
+                            min_order_size: m.minimum_order_size,
                         });
                     }
-                    if out.len() >= max_markets {
-                        return Ok(out);
+                }
+                let token_ids: Vec<TokenId> = m.tokens.into_iter().map(|t| TokenId::from(t.token_id)).collect();
+                let category = m.category.filter(|c| !c.trim().is_empty());
+                let n = token_ids.len();
+                if n < self.min_legs || (self.max_legs > 0 && n > self.max_legs) {
+                    rejected.outside_leg_bounds += 1;
+                    continue;
+                }
+                open.push(MarketDef {
+                    market_id: m.condition_id,
+                    question: m.question,
+                    polarity: Polarity::Yes,
+                    token_ids,
+                    event_id: None,
+                    event_title: None,
+                    category,
+                    tags: m.tags,
+                    min_order_size: m.minimum_order_size,
+                    liquidity: None,
+                    volume_24h: None,
+                    redemption_value: None,
+                    shared_by: None,
+                });
+            }
+        }
+
+        Ok(MarketsPage { open, total, rejected, no_legs, next_cursor })
+    }
+
+    /// Looks up the question text of `market_ids` via GET /markets/{id}, for markets a
+    /// simplified listing gave none; later snapshots carry it. Ids already known or in
+    /// flight are skipped, and failed lookups are retried on a later call.
+    pub async fn backfill_questions(&self, market_ids: &[String]) {
+        let wanted: Vec<String> = {
+            let mut questions = self.questions.lock().unwrap_or_else(|e| e.into_inner());
+            market_ids.iter()
+                .filter(|id| match questions.entry((*id).clone()) {
+                    std::collections::hash_map::Entry::Occupied(_) => false,
+                    std::collections::hash_map::Entry::Vacant(v) => {
+                        v.insert(String::new());
+                        true
                     }
+                })
+                .cloned()
+                .collect()
+        };
+        let lookups: Vec<_> = wanted.iter().map(|id| async move { (id, self.fetch_question(id).await) }).collect();
+        let mut results = stream::iter(lookups).buffer_unordered(BACKFILL_CONCURRENCY);
+        while let Some((id, res)) = results.next().await {
+            let mut questions = self.questions.lock().unwrap_or_else(|e| e.into_inner());
+            match res {
+                Ok(q) => {
+                    tracing::debug!(market_id = %id, question = %q, "question backfilled");
+                    questions.insert(id.clone(), q);
+                }
+                Err(e) => {
+                    tracing::debug!(market_id = %id, err = %format!("{:#}", e), "question lookup failed");
+                    questions.remove(id);
                 }
             }
+        }
+    }
 
-            next = resp.next_cursor;
-            if next.is_none() { break; }
+    async fn fetch_question(&self, market_id: &str) -> Result<String> {
+        let url = format!("{}/markets/{}", self.host.trim_end_matches('/'), market_id);
+        let m: MarketQuestionResp = self.http.get_json(&url, &[]).await.context("GET /markets/{id} failed")?;
+        anyhow::ensure!(!m.question.trim().is_empty(), "market {} has no question", market_id);
+        Ok(m.question)
+    }
+
+    /// Fills questions the market list lacks from the backfilled ones.
+    fn fill_questions(&self, snap: &mut GlobalSnapshot) {
+        let questions = self.questions.lock().unwrap_or_else(|e| e.into_inner());
+        if questions.is_empty() {
+            return;
+        }
+        for m in snap.markets.iter_mut().filter(|m| m.question.is_empty()) {
+            if let Some(q) = questions.get(&m.market_id) {
+                m.question = q.clone();
+            }
         }
+    }
 
-        Ok(out)
+    /// Top of book for a single token via GET /book.
+    pub async fn fetch_book(&self, token_id: &TokenId) -> Result<OutcomeTop> {
+        let url = format!("{}/book?token_id={}", self.host.trim_end_matches('/'), token_id);
+        let raw: serde_json::Value = self.http.get_json(&url, &[]).await.context("GET /book failed")?;
+        let b = self.decode_book(raw)?;
+        if b.asset_id.trim() != token_id.as_str() {
+            self.stats.inc_book_id_mismatch();
+            anyhow::bail!("GET /book for {} returned the book of {}", token_id, b.asset_id);
+        }
+        Ok(convert_book_to_top(b, token_id.clone(), self.ask_depth))
+    }
+
+    /// Raw book bodies from the last `fetch_tops` for the given tokens, as returned by
+    /// the venue. Empty unless the source was built with `capture_raw`.
+    pub fn raw_books(&self, token_ids: &[TokenId]) -> HashMap<String, serde_json::Value> {
+        let Some(cache) = &self.raw_books else { return HashMap::new() };
+        let cache = cache.lock().unwrap_or_else(|e| e.into_inner());
+        token_ids.iter()
+            .filter_map(|t| cache.get(t.as_str()).map(|v| (t.to_string(), v.clone())))
+            .collect()
+    }
+
+    fn decode_book(&self, raw: serde_json::Value) -> Result<BookSummary> {
+        let Some(cache) = &self.raw_books else {
+            return serde_json::from_value(raw).context("decode order book failed");
+        };
+        let b: BookSummary = serde_json::from_value(raw.clone()).context("decode order book failed")?;
+        cache.lock().unwrap_or_else(|e| e.into_inner()).insert(b.asset_id.clone(), raw);
+        Ok(b)
     }
 
     pub async fn snapshot_for_markets(&self, markets: &[MarketDef]) -> Result<GlobalSnapshot> {
-        let mut all_tokens: Vec<String> = Vec::new();
-        let mut seen = std::collections::HashSet::new();
+        let mut top_map = self.fetch_tops(markets).await?;
+        let now = chrono::Utc::now().timestamp_millis();
+        // judged on fresh books only; tops carried over from the last cycle don't count
+        let coverage = token_coverage(markets, &top_map);
+        if self.missing_book_policy == MissingBookPolicy::DropCycleUseCached {
+            let filled = self.fill_from_cache(markets, &mut top_map, now);
+            if filled > 0 {
+                self.stats.add_legs_filled_from_cache(filled as u64);
+                tracing::debug!(filled, max_age_ms = self.cached_top_max_age_ms, "missing books filled from cached tops");
+            }
+        }
+        let mut snap = assemble_snapshot(markets, &top_map, self.missing_book_policy, now);
+        snap.coverage = coverage;
+        self.fill_questions(&mut snap);
+        Ok(snap)
+    }
+
+    /// Caches the fresh tops of `top_map` at `now`, drops cached tops too old or of tokens
+    /// no longer in `markets`, and fills tokens missing from `top_map` with what is left.
+    /// Returns the legs filled.
+    fn fill_from_cache(&self, markets: &[MarketDef], top_map: &mut HashMap<TokenId, OutcomeTop>, now: i64) -> usize {
+        let wanted: HashSet<&TokenId> = markets.iter().flat_map(|m| m.token_ids.iter()).collect();
+        let mut last = self.last_tops.lock().unwrap_or_else(|e| e.into_inner());
+        last.extend(top_map.iter().map(|(k, v)| (k.clone(), (now, v.clone()))));
+        last.retain(|t, (ts, _)| wanted.contains(t) && now - *ts <= self.cached_top_max_age_ms);
+        let mut filled = 0;
+        for t in wanted {
+            if !top_map.contains_key(t) {
+                if let Some((_, top)) = last.get(t) {
+                    top_map.insert(t.clone(), top.clone());
+                    filled += 1;
+                }
+            }
+        }
+        filled
+    }
+
+    /// Top of book for every distinct token of `markets`, keyed by token id.
+    /// Tokens the venue returned no book for are absent from the map.
+    pub async fn fetch_tops(&self, markets: &[MarketDef]) -> Result<HashMap<TokenId, OutcomeTop>> {
+        // raw capture only covers the current cycle
+        if let Some(cache) = &self.raw_books {
+            cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        }
+        self.hedges_left.store(self.hedge_max_per_cycle, std::sync::atomic::Ordering::Relaxed);
+
+        let mut all_tokens: Vec<TokenId> = Vec::new();
+        let mut seen: HashSet<TokenId> = HashSet::new();
         for m in markets {
             for t in &m.token_ids {
                 if seen.insert(t.clone()) {
@@ -87,38 +845,136 @@ impl MarketData {
 
         let books = self.fetch_books_chunked(&all_tokens).await?;
 
-        let mut top_map: std::collections::HashMap<String, OutcomeTop> = std::collections::HashMap::new();
+        let mut top_map: HashMap<TokenId, OutcomeTop> = HashMap::with_capacity(all_tokens.len());
         for b in books.into_iter() {
-            top_map.insert(b.token_id.clone(), b);
+            // key by the interned id from the market list, not a fresh copy of asset_id
+            let id = seen.get(b.asset_id.as_str()).cloned().unwrap_or_else(|| TokenId::from(b.asset_id.as_str()));
+            top_map.insert(id.clone(), convert_book_to_top(b, id, self.ask_depth));
         }
 
-        let mut mbooks: Vec<MarketBook> = Vec::with_capacity(markets.len());
-        for m in markets {
-            let outcomes: Vec<OutcomeTop> = m.token_ids.iter()
-                .filter_map(|tid| top_map.get(tid).cloned())
-                .collect();
-            if outcomes.len() == m.token_ids.len() {
-                mbooks.push(MarketBook {
-                    market_id: m.market_id.clone(),
-                    question: m.question.clone(),
-                    outcomes,
-                });
+        // /books occasionally omits requested tokens; retry those one by one
+        let missing: Vec<TokenId> = all_tokens.iter().filter(|t| !top_map.contains_key(*t)).cloned().collect();
+        if !missing.is_empty() && tracing::enabled!(tracing::Level::DEBUG) {
+            let missing_set: HashSet<&TokenId> = missing.iter().collect();
+            for m in markets {
+                for t in m.token_ids.iter().filter(|t| missing_set.contains(t)) {
+                    tracing::debug!(token_id = %t, market_id = %m.market_id, question = %m.question, "token missing from /books");
+                }
             }
         }
+        if !missing.is_empty() {
+            let (attempt, skipped) = missing.split_at(missing.len().min(self.book_fallback_max));
+            let found = self.fetch_books_single(attempt).await;
+            let misses = (attempt.len() - found.len() + skipped.len()) as u64;
+            self.stats.add_book_fallback(attempt.len() as u64, misses);
+            tracing::debug!(
+                missing = missing.len(),
+                fallback_fetched = attempt.len(),
+                recovered = found.len(),
+                residual_misses = misses,
+                "single /book fallback for tokens missing from /books"
+            );
+            for b in found {
+                top_map.insert(b.token_id.clone(), b);
+            }
+        }
+        self.stats.add_books_fetched(top_map.len() as u64, all_tokens.len().saturating_sub(top_map.len()) as u64);
+        Ok(top_map)
+    }
 
-        Ok(GlobalSnapshot {
-            ts_ms: chrono::Utc::now().timestamp_millis(),
-            markets: mbooks,
-        })
+    /// Light-weight price per distinct token of `markets`: POST /midpoints for `Mid`,
+    /// POST /prices with side SELL (the ask side of the book) for `Ask`. Chunked like
+    /// /books; tokens without a price are absent from the map.
+    pub async fn fetch_prices(&self, markets: &[MarketDef], basis: PriceBasis) -> Result<HashMap<TokenId, Decimal>> {
+        let mut seen: HashSet<TokenId> = HashSet::new();
+        let all_tokens: Vec<TokenId> = markets.iter()
+            .flat_map(|m| m.token_ids.iter())
+            .filter(|t| seen.insert((*t).clone()))
+            .cloned()
+            .collect();
+        if all_tokens.is_empty() { return Ok(HashMap::new()); }
+
+        let (path, side) = match basis {
+            PriceBasis::Mid => ("midpoints", None),
+            PriceBasis::Ask => ("prices", Some("SELL")),
+        };
+        let url = format!("{}/{}", self.host.trim_end_matches('/'), path);
+
+        // collected first: a lazily mapped iterator here keeps the future from being Send
+        // for spawned callers
+        let requests: Vec<_> = all_tokens.chunks(self.books_chunk_size).map(|chunk| {
+            let url = url.as_str();
+            let http = &self.http;
+            async move {
+                let body: Vec<PriceReqItem> = chunk.iter().map(|t| PriceReqItem { token_id: t.as_str(), side }).collect();
+                let resp: HashMap<String, serde_json::Value> = http
+                    .post_json(url, &body, &[])
+                    .await
+                    .with_context(|| format!("POST /{} failed", path))?;
+                Ok::<HashMap<String, serde_json::Value>, anyhow::Error>(resp)
+            }
+        }).collect();
+        let mut stream = stream::iter(requests).buffer_unordered(self.books_concurrency);
+
+        let mut out: HashMap<TokenId, Decimal> = HashMap::with_capacity(all_tokens.len());
+        while let Some(res) = stream.next().await {
+            for (tok, v) in res? {
+                // /midpoints: {"<token>": "0.5"}; /prices: {"<token>": {"SELL": "0.5"}}
+                let px = match (&v, side) {
+                    (serde_json::Value::String(p), None) => parse_dec(p),
+                    (serde_json::Value::Object(o), Some(sd)) => o.get(sd).and_then(|p| p.as_str()).and_then(parse_dec),
+                    _ => None,
+                };
+                if let Some(px) = px {
+                    let id = seen.get(tok.as_str()).cloned().unwrap_or_else(|| TokenId::from(tok));
+                    out.insert(id, px);
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    /// `fetch_book`, raced by a second request when slow and the cycle's hedge budget
+    /// allows it.
+    async fn fetch_book_hedged(&self, token_id: &TokenId) -> Result<OutcomeTop> {
+        let Some(after) = self.hedge_after else { return self.fetch_book(token_id).await };
+        let take = || {
+            self.hedges_left
+                .fetch_update(std::sync::atomic::Ordering::Relaxed, std::sync::atomic::Ordering::Relaxed, |n| n.checked_sub(1))
+                .is_ok()
+        };
+        let (res, hedge) = hedged(after, take, || self.fetch_book(token_id)).await;
+        if hedge != Hedge::NotFired {
+            self.stats.add_hedge(hedge == Hedge::HedgeWon);
+            tracing::debug!(token_id = %token_id, ?hedge, "GET /book hedged");
+        }
+        res
+    }
+
+    /// GET /book per token with `books_concurrency`; tokens that fail are left out.
+    async fn fetch_books_single(&self, token_ids: &[TokenId]) -> Vec<OutcomeTop> {
+        let requests: Vec<_> = token_ids.iter().map(|t| async move {
+            match self.fetch_book_hedged(t).await {
+                Ok(top) => Some(top),
+                Err(e) => {
+                    tracing::debug!(token_id = %t, err = %format!("{:#}", e), "GET /book fallback failed");
+                    None
+                }
+            }
+        }).collect();
+        let mut stream = stream::iter(requests).buffer_unordered(self.books_concurrency);
+
+        let mut out = vec![];
+        while let Some(res) = stream.next().await {
+            out.extend(res);
+        }
+        out
     }
 
-    async fn fetch_books_chunked(&self, token_ids: &[String]) -> Result<Vec<OutcomeTop>> {
+    async fn fetch_books_chunked(&self, token_ids: &[TokenId]) -> Result<Vec<BookSummary>> {
         if token_ids.is_empty() { return Ok(vec![]); }
 
-        let chunks: Vec<Vec<String>> = token_ids
-            .chunks(self.books_chunk_size)
-            .map(|c| c.to_vec())
-            .collect();
+        let chunks: Vec<&[TokenId]> = token_ids.chunks(self.books_chunk_size).collect();
 
         tracing::debug!(
             total_tokens = token_ids.len(),
@@ -128,35 +984,44 @@ impl MarketData {
             "fetching books in chunks"
         );
 
-        let host = self.host.clone();
-        let http = self.http.clone();
-
-        let mut out: Vec<OutcomeTop> = Vec::with_capacity(token_ids.len());
+        let url = format!("{}/books", self.host.trim_end_matches('/'));
+        let mut out: Vec<BookSummary> = Vec::with_capacity(token_ids.len());
 
-        let mut stream = stream::iter(chunks.into_iter().map(|chunk| {
-            let url = format!("{}/books", host.trim_end_matches('/'));
-            let http = http.clone();
+        let requests: Vec<_> = chunks.into_iter().map(|chunk| {
+            let url = url.as_str();
+            let http = &self.http;
             async move {
-                let body: Vec<BooksReqItem> = chunk.into_iter().map(|t| BooksReqItem { token_id: t }).collect();
-                let resp: Vec<BookSummary> = http
-                    .post(url)
-                    .json(&body)
-                    .send()
+                let body: Vec<BooksReqItem> = chunk.iter().map(|t| BooksReqItem { token_id: t.as_str() }).collect();
+                let resp: Vec<serde_json::Value> = http
+                    .post_json(url, &body, &[])
                     .await
-                    .context("POST /books failed")?
-                    .error_for_status()
-                    .context("POST /books non-200")?
-                    .json()
-                    .await
-                    .context("decode /books json failed")?;
-                Ok::<Vec<BookSummary>, anyhow::Error>(resp)
+                    .context("POST /books failed")?;
+                Ok::<_, anyhow::Error>((chunk, resp))
             }
-        })).buffer_unordered(self.books_concurrency);
+        }).collect();
+        let mut stream = stream::iter(requests).buffer_unordered(self.books_concurrency);
 
         while let Some(res) = stream.next().await {
-            let page = res?;
-            for b in page {
-                out.push(convert_book_to_top(b));
+            let (chunk, page) = res?;
+            let returned = page.len();
+            let requested: HashSet<&str> = chunk.iter().map(|t| t.as_str()).collect();
+            let mut ids: HashSet<String> = HashSet::with_capacity(returned);
+            for raw in page {
+                let mut b = self.decode_book(raw)?;
+                b.asset_id = b.asset_id.trim().to_string();
+                if !requested.contains(b.asset_id.as_str()) {
+                    self.stats.inc_book_id_mismatch();
+                    tracing::warn!(asset_id = %b.asset_id, "/books returned a book for a token not requested, dropping it");
+                    continue;
+                }
+                ids.insert(b.asset_id.clone());
+                out.push(b);
+            }
+            // a token without a book is omitted from the response, not returned empty
+            let missing = chunk.iter().filter(|t| !ids.contains(t.as_str())).count();
+            if missing > 0 {
+                self.stats.add_books_missing_tokens(missing as u64);
+                tracing::debug!(requested = chunk.len(), returned, missing, "/books chunk omitted requested tokens");
             }
         }
 
@@ -164,7 +1029,51 @@ impl MarketData {
     }
 }
 
-fn convert_book_to_top(b: BookSummary) -> OutcomeTop {
+/// Joins fetched tops back onto the market list; markets missing any book are left out.
+pub fn assemble_snapshot(
+    markets: &[MarketDef],
+    top_map: &HashMap<TokenId, OutcomeTop>,
+    policy: MissingBookPolicy,
+    ts_ms: i64,
+) -> GlobalSnapshot {
+    let mut mbooks: Vec<MarketBook> = Vec::with_capacity(markets.len());
+    for m in markets {
+        let outcomes: Vec<OutcomeTop> = m.token_ids.iter()
+            .filter_map(|tid| top_map.get(tid).cloned())
+            .collect();
+        let partial = outcomes.len() < m.token_ids.len();
+        if partial && (policy != MissingBookPolicy::KeepPartialForLogging || outcomes.is_empty()) {
+            continue;
+        }
+        mbooks.push(MarketBook {
+            market_id: m.market_id.clone(),
+            question: m.question.clone(),
+            polarity: m.polarity,
+            event_id: m.event_id.clone(),
+            category: m.category.clone(),
+            min_order_size: m.min_order_size,
+            redemption_value: m.redemption_value,
+            shared_by: m.shared_by,
+            outcomes,
+            partial,
+        });
+    }
+
+    GlobalSnapshot { ts_ms, markets: mbooks, coverage: token_coverage(markets, top_map) }
+}
+
+/// Distinct tokens of `markets` with a top in `top_map`, over all distinct tokens; 1 when
+/// nothing was requested.
+fn token_coverage(markets: &[MarketDef], top_map: &HashMap<TokenId, OutcomeTop>) -> f64 {
+    let requested: HashSet<&TokenId> = markets.iter().flat_map(|m| m.token_ids.iter()).collect();
+    if requested.is_empty() {
+        return 1.0;
+    }
+    let returned = requested.iter().filter(|t| top_map.contains_key(**t)).count();
+    returned as f64 / requested.len() as f64
+}
+
+fn convert_book_to_top(b: BookSummary, token_id: TokenId, ask_depth: usize) -> OutcomeTop {
     let best_bid = b.bids.iter()
         .filter_map(|lvl| parse_dec(&lvl.price).zip(parse_dec(&lvl.size)))
         .max_by(|a,b| a.0.cmp(&b.0));
@@ -175,19 +1084,43 @@ fn convert_book_to_top(b: BookSummary) -> OutcomeTop {
     let (best_bid_px, best_bid_sz) = best_bid.map(|(p,s)| (Some(p), Some(s))).unwrap_or((None,None));
     let (best_ask_px, best_ask_sz) = best_ask.map(|(p,s)| (Some(p), Some(s))).unwrap_or((None,None));
 
+    let mut ask_levels = vec![];
+    if ask_depth > 0 {
+        ask_levels = b.asks.iter()
+            .filter_map(|lvl| parse_dec(&lvl.price).zip(parse_dec(&lvl.size)))
+            .map(|(px, sz)| BookLevel { px, sz })
+            .collect();
+        ask_levels.sort_by_key(|l| l.px);
+        ask_levels.truncate(ask_depth);
+    }
+
+    // the CLOB sends ms; a seconds value is accepted too
+    let book_ts_ms = b.timestamp.as_deref()
+        .and_then(|t| t.trim().parse::<i64>().ok())
+        .map(|t| if t < 100_000_000_000 { t * 1000 } else { t });
+
     OutcomeTop {
-        token_id: b.asset_id,
+        token_id,
         best_bid_px,
         best_bid_sz,
         best_ask_px,
         best_ask_sz,
+        ask_levels,
+        book_ts_ms,
     }
 }
 
 #[derive(Debug, Clone, Serialize)]
-struct BooksReqItem {
+struct BooksReqItem<'a> {
     #[serde(rename = "token_id")]
-    token_id: String,
+    token_id: &'a str,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct PriceReqItem<'a> {
+    token_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    side: Option<&'static str>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -202,6 +1135,15 @@ struct BookSummary {
     asset_id: String,
     bids: Vec<BookLvl>,
     asks: Vec<BookLvl>,
+    /// Venue-side update time, ms (or s) since epoch as a string
+    #[serde(default)]
+    timestamp: Option<String>,
+}
+
+fn normalize_labels<'a>(labels: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut v: Vec<String> = labels.map(|l| l.trim().to_lowercase()).collect();
+    v.sort();
+    v
 }
 
 fn parse_dec(s: &str) -> Option<Decimal> {
@@ -209,8 +1151,8 @@ fn parse_dec(s: &str) -> Option<Decimal> {
 }
 
 #[derive(Debug, Clone, Deserialize)]
-struct MarketsResp {
-    data: Vec<MarketItem>,
+struct MarketsResp<T> {
+    data: Vec<T>,
     #[serde(default)]
     next_cursor: Option<String>,
 }
@@ -223,9 +1165,598 @@ struct MarketItem {
     condition_id: String,
     question: String,
     tokens: Vec<TokenItem>,
+    #[serde(default)]
+    neg_risk: bool,
+    #[serde(default)]
+    neg_risk_market_id: Option<String>,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    minimum_order_size: Option<Decimal>,
+}
+
+/// A market as /simplified-markets and /sampling-simplified-markets list it.
+#[derive(Debug, Clone, Deserialize)]
+struct SimplifiedMarketItem {
+    condition_id: String,
+    tokens: Vec<TokenItem>,
+    #[serde(default)]
+    accepting_orders: bool,
+    #[serde(default)]
+    closed: bool,
+}
+
+impl From<SimplifiedMarketItem> for MarketItem {
+    /// Fields the simplified shape lacks are left empty. Both listings only cover CLOB
+    /// markets, so the order book is taken as enabled.
+    fn from(m: SimplifiedMarketItem) -> Self {
+        MarketItem {
+            enable_order_book: true,
+            accepting_orders: m.accepting_orders,
+            closed: m.closed,
+            condition_id: m.condition_id,
+            question: String::new(),
+            tokens: m.tokens,
+            neg_risk: false,
+            neg_risk_market_id: None,
+            category: None,
+            tags: vec![],
+            minimum_order_size: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MarketQuestionResp {
+    #[serde(default)]
+    question: String,
+}
+
+/// Longest Polymarket token id: a uint256 in decimal.
+const MAX_TOKEN_ID_DIGITS: usize = 78;
+
+/// `raw` trimmed, when it has the shape of a Polymarket token id (a uint256 in decimal).
+/// Anything else would silently map to no book, or to the wrong one.
+pub fn normalize_token_id(raw: &str) -> Option<&str> {
+    let t = raw.trim();
+    (!t.is_empty() && t.len() <= MAX_TOKEN_ID_DIGITS && t.bytes().all(|b| b.is_ascii_digit())).then_some(t)
+}
+
+/// Trims every token id in place; false when one is malformed.
+fn normalize_tokens(tokens: &mut [TokenItem]) -> bool {
+    tokens.iter_mut().all(|t| match normalize_token_id(&t.token_id) {
+        Some(id) => {
+            t.token_id = id.to_string();
+            true
+        }
+        None => false,
+    })
+}
+
+/// A bundle must hold exactly one token per outcome: at least one token, and no empty
+/// or repeated token id or outcome label (which would double-buy or miss an outcome).
+fn complete_set(tokens: &[TokenItem]) -> bool {
+    let mut ids = HashSet::new();
+    let mut labels = HashSet::new();
+    !tokens.is_empty() && tokens.iter().all(|t| {
+        let label = t.outcome.trim().to_lowercase();
+        !t.token_id.is_empty() && !label.is_empty() && ids.insert(t.token_id.as_str()) && labels.insert(label)
+    })
 }
 
 #[derive(Debug, Clone, Deserialize)]
 struct TokenItem {
     token_id: String,
+    #[serde(default)]
+    outcome: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{body_partial_json, method, path, query_param, query_param_is_missing};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::testutil::markets::{book_json, http_client, market_def, market_json};
+    use crate::testutil::snapshot_builder::{outcome, SnapshotBuilder};
+    use crate::testutil::strategy::{run, sum_arb};
+
+    fn tops(ids: &[&str]) -> HashMap<TokenId, OutcomeTop> {
+        ids.iter().map(|t| (TokenId::from(*t), outcome(t, dec!(0.4), dec!(0.45), dec!(10)))).collect()
+    }
+
+    fn source(max_age_ms: u64) -> MarketData {
+        MarketData::new("http://127.0.0.1:1".into(), http_client(0), 10, 1, 0, false, Stats::new(0))
+            .with_missing_book_policy(MissingBookPolicy::DropCycleUseCached)
+            .with_cached_top_max_age(max_age_ms)
+    }
+
+    /// Serves `pages` as consecutive /markets pages linked by cursor.
+    async fn mount_markets(server: &MockServer, pages: Vec<Vec<serde_json::Value>>) {
+        let n = pages.len();
+        for (k, data) in pages.into_iter().enumerate() {
+            let next = if k + 1 == n { END_CURSOR.to_string() } else { format!("c{}", k + 1) };
+            let body = serde_json::json!({ "data": data, "next_cursor": next });
+            let mock = Mock::given(method("GET")).and(path("/markets"));
+            let mock = if k == 0 { mock.and(query_param_is_missing("next_cursor")) } else { mock.and(query_param("next_cursor", format!("c{}", k))) };
+            mock.respond_with(ResponseTemplate::new(200).set_body_json(body)).mount(server).await;
+        }
+    }
+
+    fn ids(markets: &[MarketDef]) -> Vec<&str> {
+        markets.iter().map(|m| m.market_id.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn leg_bounds_are_inclusive_at_refresh() {
+        let server = MockServer::start().await;
+        let yes_no = |a, b| [(a, "Yes"), (b, "No")];
+        mount_markets(&server, vec![vec![
+            market_json("one", &[("11", "Yes")]),
+            market_json("two", &yes_no("21", "22")),
+            market_json("three", &[("31", "A"), ("32", "B"), ("33", "C")]),
+            market_json("four", &[("41", "A"), ("42", "B"), ("43", "C"), ("44", "D")]),
+        ]])
+        .await;
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0)).with_leg_bounds(2, 3);
+
+        let page = md.fetch_markets_page(None).await.unwrap();
+        assert_eq!(ids(&page.open), ["two", "three"]);
+        assert_eq!(page.rejected.outside_leg_bounds, 2);
+    }
+
+    #[tokio::test]
+    async fn label_allowlist_skips_nonstandard_labels() {
+        let server = MockServer::start().await;
+        mount_markets(&server, vec![vec![
+            market_json("binary", &[("11", "Yes"), ("12", "No")]),
+            market_json("reordered", &[("21", "no"), ("22", "YES")]),
+            market_json("named", &[("31", "Trump"), ("32", "Harris")]),
+            market_json("three", &[("41", "Yes"), ("42", "No"), ("43", "Maybe")]),
+        ]])
+        .await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, stats.clone())
+            .with_label_allowlist(vec![vec!["Yes".into(), "No".into()]]);
+
+        let markets = md.fetch_open_markets(100).await.unwrap();
+        assert_eq!(ids(&markets), ["binary", "reordered"]);
+        assert_eq!(stats.snapshot(0).skipped_label_mismatch, 2);
+    }
+
+    /// A market in the simplified listing shape: no question, no order-book flag.
+    fn simplified_json(condition_id: &str, yes: &str, no: &str, closed: bool) -> serde_json::Value {
+        serde_json::json!({
+            "condition_id": condition_id,
+            "accepting_orders": true,
+            "closed": closed,
+            "tokens": [{ "token_id": yes, "outcome": "Yes" }, { "token_id": no, "outcome": "No" }],
+        })
+    }
+
+    #[tokio::test]
+    async fn simplified_and_sampling_listings_decode_into_market_defs() {
+        for (endpoint, route) in [(MarketsEndpoint::Simplified, "/simplified-markets"), (MarketsEndpoint::Sampling, "/sampling-simplified-markets")] {
+            let server = MockServer::start().await;
+            let body = serde_json::json!({
+                "data": [simplified_json("a", "11", "12", false), simplified_json("b", "21", "22", true)],
+                "next_cursor": END_CURSOR,
+            });
+            Mock::given(method("GET")).and(path(route)).respond_with(ResponseTemplate::new(200).set_body_json(body)).mount(&server).await;
+            let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0)).with_markets_endpoint(endpoint);
+
+            let page = md.fetch_markets_page(None).await.unwrap();
+            assert_eq!(ids(&page.open), ["a"], "{:?}", endpoint);
+            assert_eq!(page.open[0].token_ids, [TokenId::from("11"), TokenId::from("12")]);
+            assert!(page.open[0].question.is_empty());
+            assert_eq!(page.rejected.closed, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn simplified_questions_are_backfilled_from_the_full_endpoint() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET")).and(path("/markets/a"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "question": "Will A?" })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0))
+            .with_markets_endpoint(MarketsEndpoint::Simplified);
+
+        md.backfill_questions(&["a".to_string()]).await;
+        md.backfill_questions(&["a".to_string()]).await;
+        let mut snap = SnapshotBuilder::new().markets(1).build();
+        snap.markets[0].market_id = "a".into();
+        snap.markets[0].question.clear();
+        md.fill_questions(&mut snap);
+        assert_eq!(snap.markets[0].question, "Will A?");
+    }
+
+    #[tokio::test]
+    async fn order_book_disabled_market_follows_eligibility_policy() {
+        let server = MockServer::start().await;
+        let mut disabled = market_json("disabled", &[("11", "Yes"), ("12", "No")]);
+        disabled["enable_order_book"] = serde_json::json!(false);
+        mount_markets(&server, vec![vec![disabled, market_json("open", &[("21", "Yes"), ("22", "No")])]]).await;
+        let with = |eligibility| MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0)).with_eligibility(eligibility);
+
+        let page = with(MarketEligibility::default()).fetch_markets_page(None).await.unwrap();
+        assert_eq!(ids(&page.open), ["open"]);
+        assert_eq!(page.rejected.order_book_disabled, 1);
+
+        let lenient = MarketEligibility { require_order_book: false, ..Default::default() };
+        let page = with(lenient).fetch_markets_page(None).await.unwrap();
+        assert_eq!(ids(&page.open), ["disabled", "open"]);
+        assert_eq!(page.rejected.order_book_disabled, 0);
+
+        let off = MarketEligibility { require_order_book: false, require_accepting_orders: false, skip_closed: false };
+        let page = with(off).fetch_markets_page(None).await.unwrap();
+        assert_eq!(ids(&page.open), ["disabled", "open"]);
+    }
+
+    #[tokio::test]
+    async fn a_market_repeated_on_the_next_page_is_kept_once() {
+        let server = MockServer::start().await;
+        let mut moved = market_json("b", &[("21", "Yes"), ("22", "No")]);
+        moved["question"] = "b, listed again?".into();
+        mount_markets(&server, vec![
+            vec![market_json("a", &[("11", "Yes"), ("12", "No")]), market_json("b", &[("21", "Yes"), ("22", "No")])],
+            vec![moved, market_json("c", &[("31", "Yes"), ("32", "No")])],
+        ])
+        .await;
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0));
+
+        let markets = md.fetch_open_markets(100).await.unwrap();
+        assert_eq!(ids(&markets), ["a", "b", "c"]);
+        // the first copy wins
+        assert_eq!(markets[1].question, "b?");
+        // the duplicate does not count against max_markets
+        assert_eq!(ids(&md.fetch_open_markets(3).await.unwrap()), ["a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn no_allowlist_keeps_any_labels() {
+        let server = MockServer::start().await;
+        mount_markets(&server, vec![vec![market_json("named", &[("31", "Trump"), ("32", "Harris")])]]).await;
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0));
+        assert_eq!(ids(&md.fetch_open_markets(100).await.unwrap()), ["named"]);
+    }
+
+    #[tokio::test]
+    async fn three_token_market_respects_binary_only() {
+        let server = MockServer::start().await;
+        mount_markets(&server, vec![vec![
+            market_json("binary", &[("11", "Yes"), ("12", "No")]),
+            market_json("three", &[("21", "A"), ("22", "B"), ("23", "C")]),
+            market_json("partial", &[("31", "A"), ("32", "A"), ("33", "C")]),
+        ]])
+        .await;
+
+        for (binary_only, expected, skipped) in [(false, vec!["binary", "three"], 0), (true, vec!["binary"], 1)] {
+            let stats = Stats::new(0);
+            let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, stats.clone())
+                .with_binary_only(binary_only);
+            let markets = md.fetch_open_markets(100).await.unwrap();
+            assert_eq!(ids(&markets), expected, "binary_only={binary_only}");
+            assert_eq!(stats.snapshot(0).skipped_multi_outcome, skipped);
+            // a duplicated label is never a complete set, whatever the setting
+            assert_eq!(md.fetch_markets_page(None).await.unwrap().rejected.incomplete_set, 1);
+        }
+    }
+
+    /// Bundle sizes per market when sum_arb runs on `snap`.
+    fn bundle_sizes(snap: &GlobalSnapshot) -> Vec<(String, Decimal)> {
+        let (intents, _) = run(&sum_arb(), snap);
+        let mut seen = HashSet::new();
+        intents.iter().filter(|i| seen.insert(i.bundle_id)).map(|i| (i.market_id.clone(), i.size)).collect()
+    }
+
+    #[tokio::test]
+    async fn markets_sharing_a_token_follow_the_collision_policy() {
+        let server = MockServer::start().await;
+        mount_markets(&server, vec![vec![
+            market_json("a", &[("11", "Yes"), ("12", "No")]),
+            market_json("b", &[("11", "Yes"), ("22", "No")]),
+        ]])
+        .await;
+        // every book asks 0.41 for 10 shares
+        Mock::given(method("POST")).and(path("/books")).respond_with(BooksOmitting(&[])).mount(&server).await;
+
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, stats.clone());
+        let markets = md.fetch_open_markets(100).await.unwrap();
+        assert_eq!(ids(&markets), ["a"]);
+        assert_eq!(stats.snapshot(0).token_collisions, 1);
+        let snap = md.snapshot_for_markets(&markets).await.unwrap();
+        assert_eq!(bundle_sizes(&snap), [("a".to_string(), dec!(10))]);
+
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, stats.clone())
+            .with_token_collisions(TokenCollisionPolicy::Share);
+        let markets = md.fetch_open_markets(100).await.unwrap();
+        assert_eq!(ids(&markets), ["a", "b"]);
+        assert!(markets.iter().all(|m| m.shared_by == Some(2)));
+        assert_eq!(stats.snapshot(0).token_collisions, 2);
+        // each market only counts on half the shared book
+        let snap = md.snapshot_for_markets(&markets).await.unwrap();
+        assert_eq!(bundle_sizes(&snap), [("a".to_string(), dec!(5)), ("b".to_string(), dec!(5))]);
+    }
+
+    #[tokio::test]
+    async fn prices_come_from_the_endpoint_of_the_basis() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/midpoints"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({ "1": "0.49", "2": "0.51" })))
+            .mount(&server).await;
+        Mock::given(method("POST")).and(path("/prices"))
+            .and(body_partial_json(serde_json::json!([{ "token_id": "1", "side": "SELL" }])))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "1": { "SELL": "0.50" },
+                "2": { "SELL": "0.52" },
+            })))
+            .mount(&server).await;
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0));
+        let markets = [market_def("m", &["1", "2"])];
+
+        let mids = md.fetch_prices(&markets, PriceBasis::Mid).await.unwrap();
+        assert_eq!((mids[&TokenId::from("1")], mids[&TokenId::from("2")]), (dec!(0.49), dec!(0.51)));
+        let asks = md.fetch_prices(&markets, PriceBasis::Ask).await.unwrap();
+        assert_eq!((asks[&TokenId::from("1")], asks[&TokenId::from("2")]), (dec!(0.50), dec!(0.52)));
+    }
+
+    async fn mount_books(server: &MockServer, books: serde_json::Value) {
+        Mock::given(method("POST"))
+            .and(path("/books"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(books))
+            .mount(server)
+            .await;
+    }
+
+    async fn mount_book(server: &MockServer, token_id: &str) {
+        Mock::given(method("GET"))
+            .and(path("/book"))
+            .and(query_param("token_id", token_id))
+            .respond_with(ResponseTemplate::new(200).set_body_json(book_json(token_id, &[("0.40", "10")], &[("0.41", "10")])))
+            .mount(server)
+            .await;
+    }
+
+    /// POST /books answering each chunk with a book for every requested token except `omit`.
+    struct BooksOmitting(&'static [&'static str]);
+
+    impl wiremock::Respond for BooksOmitting {
+        fn respond(&self, req: &wiremock::Request) -> ResponseTemplate {
+            let asked: Vec<serde_json::Value> = req.body_json().unwrap();
+            let books: Vec<serde_json::Value> = asked.iter()
+                .filter_map(|item| item["token_id"].as_str())
+                .filter(|t| !self.0.contains(t))
+                .map(|t| book_json(t, &[("0.40", "10")], &[("0.41", "10")]))
+                .collect();
+            ResponseTemplate::new(200).set_body_json(books)
+        }
+    }
+
+    #[tokio::test]
+    async fn a_chunk_omitting_a_token_is_counted() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/books")).respond_with(BooksOmitting(&["2"])).expect(2).mount(&server).await;
+        let stats = Stats::new(0);
+        // chunks of two: [1, 2] comes back without 2, [3, 4] in full
+        let md = MarketData::new(server.uri(), http_client(0), 2, 1, 0, false, stats.clone());
+
+        let snap = md.snapshot_for_markets(&[market_def("m1", &["1", "2"]), market_def("m2", &["3", "4"])]).await.unwrap();
+        assert_eq!(snap.markets.iter().map(|m| m.market_id.as_str()).collect::<Vec<_>>(), ["m2"]);
+        assert_eq!(snap.coverage, 0.75);
+        assert_eq!(stats.snapshot(0).books_missing_tokens, 1);
+    }
+
+    #[tokio::test]
+    async fn half_the_books_is_half_coverage() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST")).and(path("/books")).respond_with(BooksOmitting(&["2", "4"])).mount(&server).await;
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0));
+
+        let snap = md.snapshot_for_markets(&[market_def("m1", &["1", "2"]), market_def("m2", &["3", "4"])]).await.unwrap();
+        assert!(snap.markets.is_empty());
+        assert_eq!(snap.coverage, 0.5);
+    }
+
+    /// GET /book for `token_id` answering once after `slow`, then straight away.
+    async fn mount_straggler(server: &MockServer, token_id: &str, slow: std::time::Duration) {
+        let book = book_json(token_id, &[("0.40", "10")], &[("0.41", "10")]);
+        Mock::given(method("GET"))
+            .and(path("/book"))
+            .and(query_param("token_id", token_id))
+            .respond_with(ResponseTemplate::new(200).set_body_json(book.clone()).set_delay(slow))
+            .up_to_n_times(1)
+            .mount(server)
+            .await;
+        mount_book(server, token_id).await;
+    }
+
+    #[tokio::test]
+    async fn slow_fallback_fetch_is_won_by_the_hedge() {
+        let server = MockServer::start().await;
+        mount_books(&server, serde_json::json!([])).await;
+        mount_straggler(&server, "1", std::time::Duration::from_secs(1)).await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 10, false, stats.clone()).with_hedging(50, 5);
+
+        let started = std::time::Instant::now();
+        let top_map = md.fetch_tops(&[market_def("m1", &["1"])]).await.unwrap();
+        assert!(top_map.contains_key(&TokenId::from("1")));
+        assert!(started.elapsed() < std::time::Duration::from_millis(800), "took {:?}", started.elapsed());
+        let ss = stats.snapshot(0);
+        assert_eq!((ss.hedges_fired, ss.hedges_won), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn fast_fallback_fetch_fires_no_hedge() {
+        let server = MockServer::start().await;
+        mount_books(&server, serde_json::json!([])).await;
+        mount_book(&server, "1").await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 10, false, stats.clone()).with_hedging(500, 5);
+
+        assert!(md.fetch_tops(&[market_def("m1", &["1"])]).await.unwrap().contains_key(&TokenId::from("1")));
+        assert_eq!(stats.snapshot(0).hedges_fired, 0);
+    }
+
+    #[tokio::test]
+    async fn spent_hedge_budget_waits_for_the_straggler() {
+        let server = MockServer::start().await;
+        mount_books(&server, serde_json::json!([])).await;
+        mount_straggler(&server, "1", std::time::Duration::from_millis(300)).await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 10, false, stats.clone()).with_hedging(50, 0);
+
+        let started = std::time::Instant::now();
+        assert!(md.fetch_tops(&[market_def("m1", &["1"])]).await.unwrap().contains_key(&TokenId::from("1")));
+        assert!(started.elapsed() >= std::time::Duration::from_millis(300));
+        assert_eq!(stats.snapshot(0).hedges_fired, 0);
+    }
+
+    #[tokio::test]
+    async fn a_book_for_an_unrequested_token_is_rejected() {
+        let server = MockServer::start().await;
+        mount_books(&server, serde_json::json!([
+            book_json("1", &[("0.40", "10")], &[("0.41", "10")]),
+            book_json("9", &[("0.40", "10")], &[("0.41", "10")]),
+        ]))
+        .await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, stats.clone());
+
+        let top_map = md.fetch_tops(&[market_def("m1", &["1", "2"])]).await.unwrap();
+        assert_eq!(top_map.keys().map(|t| t.as_str()).collect::<Vec<_>>(), ["1"]);
+        assert_eq!(stats.snapshot(0).book_id_mismatches, 1);
+    }
+
+    #[tokio::test]
+    async fn a_single_book_of_another_token_is_rejected() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/book"))
+            .and(query_param("token_id", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(book_json("2", &[("0.40", "10")], &[("0.41", "10")])))
+            .mount(&server)
+            .await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, stats.clone());
+
+        let err = md.fetch_book(&TokenId::from("1")).await.unwrap_err();
+        assert_eq!(err.to_string(), "GET /book for 1 returned the book of 2");
+        assert_eq!(stats.snapshot(0).book_id_mismatches, 1);
+    }
+
+    #[tokio::test]
+    async fn token_ids_are_trimmed_and_malformed_ones_rejected() {
+        let server = MockServer::start().await;
+        mount_markets(&server, vec![vec![
+            market_json("padded", &[(" 11 ", "Yes"), ("12\n", "No")]),
+            market_json("truncated", &[("21…", "Yes"), ("22", "No")]),
+            market_json("hex", &[("0x31", "Yes"), ("32", "No")]),
+        ]])
+        .await;
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 0, false, Stats::new(0));
+
+        let page = md.fetch_markets_page(None).await.unwrap();
+        assert_eq!(ids(&page.open), ["padded"]);
+        assert_eq!(page.open[0].token_ids, [TokenId::from("11"), TokenId::from("12")]);
+        assert_eq!(page.rejected.malformed_token_id, 2);
+    }
+
+    #[tokio::test]
+    async fn single_book_fallback_fills_tokens_missing_from_books() {
+        let server = MockServer::start().await;
+        mount_books(&server, serde_json::json!([
+            book_json("1", &[("0.40", "10")], &[("0.41", "10")]),
+            book_json("3", &[("0.40", "10")], &[("0.41", "10")]),
+            book_json("5", &[("0.40", "10")], &[("0.41", "10")]),
+        ]))
+        .await;
+        mount_book(&server, "2").await;
+        mount_book(&server, "4").await;
+        let stats = Stats::new(0);
+        let md = MarketData::new(server.uri(), http_client(0), 100, 1, 10, false, stats.clone());
+
+        let top_map = md.fetch_tops(&[market_def("m1", &["1", "2", "3"]), market_def("m2", &["4", "5"])]).await.unwrap();
+        let mut found: Vec<&str> = top_map.keys().map(|t| t.as_str()).collect();
+        found.sort();
+        assert_eq!(found, ["1", "2", "3", "4", "5"]);
+        assert_eq!(top_map[&TokenId::from("4")].best_ask_px, Some(dec!(0.41)));
+        let ss = stats.snapshot(0);
+        assert_eq!((ss.book_fallback_fetches, ss.book_fallback_misses), (2, 0));
+    }
+
+    #[test]
+    fn redemption_values_parse_by_market_id() {
+        let v = parse_redemption_values(" 0xabc:2 , 0xdef:0.5,").unwrap();
+        assert_eq!(v, HashMap::from([("0xabc".to_string(), dec!(2)), ("0xdef".to_string(), dec!(0.5))]));
+        for bad in ["0xabc", "0xabc:two", "0xabc:0", "0xabc:-1"] {
+            assert!(parse_redemption_values(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn drop_market_leaves_out_markets_missing_a_book() {
+        let markets = [market_def("full", &["a", "b"]), market_def("half", &["c", "d"])];
+        let snap = assemble_snapshot(&markets, &tops(&["a", "b", "c"]), MissingBookPolicy::DropMarket, 1);
+        assert_eq!(snap.markets.len(), 1);
+        assert_eq!(snap.markets[0].market_id, "full");
+        assert!(!snap.markets[0].partial);
+        assert_eq!(snap.coverage, 0.75);
+    }
+
+    #[test]
+    fn keep_partial_for_logging_flags_partial_markets() {
+        let markets = [market_def("half", &["c", "d"]), market_def("none", &["e", "f"])];
+        let snap = assemble_snapshot(&markets, &tops(&["c"]), MissingBookPolicy::KeepPartialForLogging, 1);
+        // a market with no book at all has nothing to log
+        assert_eq!(snap.markets.len(), 1);
+        assert_eq!(snap.markets[0].market_id, "half");
+        assert!(snap.markets[0].partial);
+        assert_eq!(snap.markets[0].outcomes.len(), 1);
+    }
+
+    #[test]
+    fn drop_cycle_use_cached_fills_from_a_fresh_enough_top() {
+        let md = source(1_000);
+        let markets = [market_def("m", &["a", "b"])];
+        let mut first = tops(&["a", "b"]);
+        assert_eq!(md.fill_from_cache(&markets, &mut first, 10_000), 0);
+
+        let mut second = tops(&["a"]);
+        assert_eq!(md.fill_from_cache(&markets, &mut second, 10_500), 1);
+        let snap = assemble_snapshot(&markets, &second, MissingBookPolicy::DropCycleUseCached, 10_500);
+        assert_eq!(snap.markets.len(), 1);
+        assert!(!snap.markets[0].partial);
+    }
+
+    #[test]
+    fn drop_cycle_use_cached_drops_tops_past_max_age() {
+        let md = source(1_000);
+        let markets = [market_def("m", &["a", "b"])];
+        md.fill_from_cache(&markets, &mut tops(&["a", "b"]), 10_000);
+
+        let mut late = tops(&["a"]);
+        assert_eq!(md.fill_from_cache(&markets, &mut late, 11_001), 0);
+        assert!(assemble_snapshot(&markets, &late, MissingBookPolicy::DropCycleUseCached, 11_001).markets.is_empty());
+        // the expired top is gone, not just skipped
+        assert!(!md.last_tops.lock().unwrap().contains_key("b"));
+    }
+
+    #[test]
+    fn drop_cycle_use_cached_forgets_tokens_of_removed_markets() {
+        let md = source(60_000);
+        md.fill_from_cache(&[market_def("m1", &["a", "b"]), market_def("m2", &["c", "d"])], &mut tops(&["a", "b", "c", "d"]), 0);
+        md.fill_from_cache(&[market_def("m2", &["c", "d"])], &mut tops(&["c", "d"]), 1);
+        let mut cached: Vec<_> = md.last_tops.lock().unwrap().keys().map(|t| t.to_string()).collect();
+        cached.sort();
+        assert_eq!(cached, ["c", "d"]);
+    }
 }
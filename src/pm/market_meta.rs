@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use futures::{stream, StreamExt};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use crate::http::HttpClient;
+use crate::stats::Stats;
+use crate::types::{GlobalSnapshot, Polarity, TokenId};
+
+/// Concurrent GET /markets/{id} requests per check round.
+const CHECK_CONCURRENCY: usize = 4;
+
+/// Current state of one market per GET /markets/{condition_id}.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketMeta {
+    pub closed: bool,
+    /// Token flagged as the winner, once the market has resolved
+    pub winner: Option<TokenId>,
+}
+
+impl MarketMeta {
+    pub fn is_resolved(&self) -> bool {
+        self.closed || self.winner.is_some()
+    }
+}
+
+/// Cache of market state used to catch resolved markets between refreshes.
+///
+/// After resolution a market's books can linger at degenerate prices (winner near 1,
+/// losers near 0) that look like a tight bundle. Markets with a leg quoted at or above
+/// `suspect_px` are looked up (at most `max_checks` per round); answers are cached for
+/// `ttl`, and a resolved answer is kept until the next market refresh clears it.
+pub struct MarketMetaCache {
+    host: String,
+    http: HttpClient,
+    ttl: Duration,
+    suspect_px: Decimal,
+    max_checks: usize,
+    entries: Mutex<HashMap<String, (Instant, MarketMeta)>>,
+}
+
+impl MarketMetaCache {
+    pub fn new(host: String, http: HttpClient, ttl_sec: u64, suspect_px: Decimal, max_checks: usize) -> Self {
+        Self {
+            host,
+            http,
+            ttl: Duration::from_secs(ttl_sec),
+            suspect_px,
+            max_checks: max_checks.max(1),
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// True if `market_id` was seen resolved.
+    pub fn is_resolved(&self, market_id: &str) -> bool {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        entries.get(market_id).map(|(_, m)| m.is_resolved()).unwrap_or(false)
+    }
+
+    /// Forgets every cached answer; called when the market list is refreshed.
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Venue markets in `snap` with a leg bid or ask at or above `suspect_px` and no fresh
+    /// cached answer. NO baskets are synthetic and skipped; their legs' own markets are not.
+    pub fn suspects(&self, snap: &GlobalSnapshot) -> Vec<String> {
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        snap.markets.iter()
+            .filter(|m| m.polarity == Polarity::Yes)
+            .filter(|m| m.outcomes.iter().any(|o| {
+                o.best_ask_px.map(|p| p >= self.suspect_px).unwrap_or(false)
+                    || o.best_bid_px.map(|p| p >= self.suspect_px).unwrap_or(false)
+            }))
+            .filter(|m| match entries.get(&m.market_id) {
+                Some((at, meta)) => !meta.is_resolved() && at.elapsed() >= self.ttl,
+                None => true,
+            })
+            .map(|m| m.market_id.clone())
+            .take(self.max_checks)
+            .collect()
+    }
+
+    /// Looks up `market_ids` and returns those newly found resolved. Lookup failures are
+    /// logged and retried on a later round.
+    pub async fn check(&self, market_ids: &[String]) -> Vec<String> {
+        let lookups: Vec<_> = market_ids.iter().map(|id| async move { (id, self.fetch(id).await) }).collect();
+        let mut results = stream::iter(lookups).buffer_unordered(CHECK_CONCURRENCY);
+        let mut resolved = vec![];
+        while let Some((id, res)) = results.next().await {
+            match res {
+                Ok(meta) => {
+                    if meta.is_resolved() {
+                        tracing::info!(market_id = %id, closed = meta.closed, winner = ?meta.winner.as_ref().map(|t| t.to_string()), "market resolved, dropping from scan");
+                        resolved.push(id.clone());
+                    }
+                    self.entries.lock().unwrap_or_else(|e| e.into_inner()).insert(id.clone(), (Instant::now(), meta));
+                }
+                Err(e) => tracing::debug!(market_id = %id, err = %format!("{:#}", e), "market state lookup failed"),
+            }
+        }
+        resolved
+    }
+
+    /// One round over `snap`: checks the suspects and returns the markets newly found
+    /// resolved, counted in `markets_resolved_detected`.
+    pub async fn detect_resolved(&self, snap: &GlobalSnapshot, stats: &Stats) -> Vec<String> {
+        let suspects = self.suspects(snap);
+        if suspects.is_empty() {
+            return vec![];
+        }
+        let resolved = self.check(&suspects).await;
+        stats.add_markets_resolved_detected(resolved.len() as u64);
+        resolved
+    }
+
+    async fn fetch(&self, market_id: &str) -> Result<MarketMeta> {
+        let url = format!("{}/markets/{}", self.host.trim_end_matches('/'), market_id);
+        let m: MarketStateResp = self.http.get_json(&url, &[]).await.context("GET /markets/{id} failed")?;
+        Ok(MarketMeta {
+            closed: m.closed,
+            winner: m.tokens.into_iter().find(|t| t.winner).map(|t| TokenId::from(t.token_id)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MarketStateResp {
+    #[serde(default)]
+    closed: bool,
+    #[serde(default)]
+    tokens: Vec<MarketStateToken>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MarketStateToken {
+    token_id: String,
+    #[serde(default)]
+    winner: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use super::*;
+    use crate::history::PriceHistory;
+    use crate::strategy::{SkipReason, Strategy, StrategyContext};
+    use crate::testutil::markets::http_client;
+    use crate::testutil::snapshot_builder::SnapshotBuilder;
+    use crate::testutil::strategy::sum_arb;
+
+    async fn mount_state(server: &MockServer, market_id: &str, body: serde_json::Value) {
+        Mock::given(method("GET"))
+            .and(path(format!("/markets/{}", market_id)))
+            .respond_with(ResponseTemplate::new(200).set_body_json(body))
+            .mount(server)
+            .await;
+    }
+
+    /// mkt-0 at winner/loser prices, mkt-1 with one leg at 0.995, mkt-2 ordinary.
+    fn snapshot() -> GlobalSnapshot {
+        let mut snap = SnapshotBuilder::new().markets(3).build();
+        let degenerate = [(dec!(0.998), dec!(0.999)), (dec!(0.000), dec!(0.001))];
+        for (o, (bid, ask)) in snap.markets[0].outcomes.iter_mut().zip(degenerate) {
+            o.best_bid_px = Some(bid);
+            o.best_ask_px = Some(ask);
+        }
+        snap.markets[1].outcomes[0].best_ask_px = Some(dec!(0.995));
+        snap
+    }
+
+    async fn cache(server: &MockServer) -> MarketMetaCache {
+        mount_state(server, "mkt-0", serde_json::json!({
+            "closed": true,
+            "tokens": [{ "token_id": "tok-0-0", "winner": true }, { "token_id": "tok-0-1", "winner": false }],
+        }))
+        .await;
+        mount_state(server, "mkt-1", serde_json::json!({
+            "closed": false,
+            "tokens": [{ "token_id": "tok-1-0", "winner": false }, { "token_id": "tok-1-1", "winner": false }],
+        }))
+        .await;
+        MarketMetaCache::new(server.uri(), http_client(0), 3600, dec!(0.99), 10)
+    }
+
+    #[tokio::test]
+    async fn a_resolved_market_is_detected_and_counted() {
+        let server = MockServer::start().await;
+        let meta = cache(&server).await;
+        let stats = Stats::new(0);
+        let snap = snapshot();
+
+        assert_eq!(meta.suspects(&snap), ["mkt-0", "mkt-1"]);
+        assert_eq!(meta.detect_resolved(&snap, &stats).await, ["mkt-0"]);
+        assert!(meta.is_resolved("mkt-0"));
+        assert!(!meta.is_resolved("mkt-1"));
+        assert_eq!(stats.snapshot(0).markets_resolved_detected, 1);
+
+        // both answers are cached: nothing to look up again within the ttl
+        assert!(meta.suspects(&snap).is_empty());
+        assert!(meta.detect_resolved(&snap, &stats).await.is_empty());
+        assert_eq!(stats.snapshot(0).markets_resolved_detected, 1);
+        meta.clear();
+        assert!(!meta.is_resolved("mkt-0"));
+    }
+
+    #[tokio::test]
+    async fn the_strategy_skips_resolved_markets() {
+        let server = MockServer::start().await;
+        let meta = cache(&server).await;
+        let snap = snapshot();
+        meta.detect_resolved(&snap, &Stats::new(0)).await;
+
+        let history = PriceHistory::new(0, 0);
+        let ctx = StrategyContext { history: &history, meta: Some(&meta), reference: None };
+        let (intents, summary) = sum_arb().on_snapshot(&snap, &ctx);
+        // 0.999 + 0.001 would otherwise read as a fair bundle, not a skip
+        assert!(intents.is_empty());
+        assert_eq!(summary.skipped(SkipReason::Resolved), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_lookups_are_retried_later() {
+        let server = MockServer::start().await;
+        let meta = MarketMetaCache::new(server.uri(), http_client(0), 3600, dec!(0.99), 10);
+        let snap = snapshot();
+        assert!(meta.check(&["mkt-0".to_string()]).await.is_empty());
+        assert_eq!(meta.suspects(&snap), ["mkt-0", "mkt-1"]);
+    }
+}
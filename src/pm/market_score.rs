@@ -0,0 +1,52 @@
+use crate::pm::market_data::MarketDef;
+
+/// Weights of the scan-priority score. Liquidity, volume and hits enter as ln(1 + x) so a
+/// handful of huge markets cannot drown the rest; legs as 1 / legs so binaries rank first.
+#[derive(Debug, Clone, Copy)]
+pub struct ScoreWeights {
+    pub liquidity: f64,
+    pub volume: f64,
+    pub legs: f64,
+    pub hits: f64,
+}
+
+impl ScoreWeights {
+    /// Score of `m` given its near-arb/opportunity count so far. Missing data adds zero.
+    pub fn score(&self, m: &MarketDef, hits: u64) -> f64 {
+        let log = |v: Option<f64>| v.filter(|v| v.is_finite() && *v > 0.0).map(f64::ln_1p).unwrap_or(0.0);
+        let legs = if m.token_ids.is_empty() { 0.0 } else { 1.0 / m.token_ids.len() as f64 };
+        self.liquidity * log(m.liquidity)
+            + self.volume * log(m.volume_24h)
+            + self.legs * legs
+            + self.hits * (hits as f64).ln_1p()
+    }
+}
+
+/// Score distribution of one ranking pass.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ScoreSummary {
+    pub candidates: usize,
+    pub kept: usize,
+    pub min: f64,
+    pub median: f64,
+    pub max: f64,
+    /// Score of the last market kept; None when nothing was dropped
+    pub cutoff: Option<f64>,
+}
+
+/// Keeps the `keep` highest-scoring markets, ties in their original order.
+pub fn keep_top(markets: Vec<MarketDef>, keep: usize, score: impl Fn(&MarketDef) -> f64) -> (Vec<MarketDef>, ScoreSummary) {
+    let mut scored: Vec<(f64, MarketDef)> = markets.into_iter().map(|m| (score(&m), m)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    let n = scored.len();
+    let summary = ScoreSummary {
+        candidates: n,
+        kept: n.min(keep),
+        min: scored.last().map(|s| s.0).unwrap_or_default(),
+        median: scored.get(n / 2).map(|s| s.0).unwrap_or_default(),
+        max: scored.first().map(|s| s.0).unwrap_or_default(),
+        cutoff: (n > keep && keep > 0).then(|| scored[keep - 1].0),
+    };
+    scored.truncate(keep);
+    (scored.into_iter().map(|(_, m)| m).collect(), summary)
+}
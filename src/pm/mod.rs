@@ -1,2 +1,12 @@
 pub mod market_data;
+pub mod market_meta;
+pub mod market_score;
+pub mod bundle_ledger;
+pub mod executor;
+pub mod gamma;
 pub mod execution_observer;
+pub mod execution_mock;
+pub mod intent_dedup;
+pub mod intent_gate;
+pub mod prefilter;
+pub mod reference;
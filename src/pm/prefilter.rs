@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+use crate::pm::market_data::MarketDef;
+use crate::types::TokenId;
+
+/// Per-token price the prefilter sums before deciding whether a market is worth a full
+/// order-book fetch.
+///
+/// `Ask` is conservative: it is what a bundle would actually cost, so it promotes few
+/// markets but may miss one whose asks are just moving into range. `Mid` casts a wider
+/// net: midpoints sit below the asks, so the sum underestimates executable cost and
+/// promotes more markets that the full books then reject.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PriceBasis {
+    Mid,
+    #[default]
+    Ask,
+}
+
+/// Cheap pass over light-weight prices (POST /midpoints or /prices) that picks the
+/// markets whose summed price is within `margin_bps` of the bundle payoff.
+#[derive(Debug, Clone)]
+pub struct Prefilter {
+    pub basis: PriceBasis,
+    /// Promote when sum < payoff * (1 + margin_bps / 10000)
+    pub margin_bps: i64,
+}
+
+/// Markets promoted to the full book fetch, plus how many could be priced at all.
+#[derive(Debug, Clone)]
+pub struct Promoted {
+    pub markets: Vec<MarketDef>,
+    /// Markets with a price for every token
+    pub priced: usize,
+}
+
+impl Prefilter {
+    /// Markets missing any token price are not promoted.
+    pub fn promote(&self, markets: &[MarketDef], prices: &HashMap<TokenId, Decimal>) -> Promoted {
+        let limit_factor = dec!(1) + Decimal::from(self.margin_bps) / dec!(10000);
+        let mut out = vec![];
+        let mut priced = 0;
+        for m in markets {
+            let sum: Option<Decimal> = m.token_ids.iter().map(|t| prices.get(t).copied()).sum();
+            let Some(sum) = sum else { continue };
+            priced += 1;
+            if sum < m.payoff() * limit_factor {
+                out.push(m.clone());
+            }
+        }
+        Promoted { markets: out, priced }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::markets::market_def;
+
+    fn prices(px: &[(&str, Decimal)]) -> HashMap<TokenId, Decimal> {
+        px.iter().map(|(t, p)| (TokenId::from(*t), *p)).collect()
+    }
+
+    fn markets() -> Vec<MarketDef> {
+        vec![
+            market_def("wide", &["1", "2"]),
+            market_def("close", &["3", "4"]),
+            market_def("unpriced", &["5", "6"]),
+        ]
+    }
+
+    fn promoted(basis: PriceBasis, px: &HashMap<TokenId, Decimal>) -> (Vec<String>, usize) {
+        let p = Prefilter { basis, margin_bps: 50 }.promote(&markets(), px);
+        (p.markets.into_iter().map(|m| m.market_id).collect(), p.priced)
+    }
+
+    // "wide" asks 0.50 + 0.52 = 1.02 with mids 0.49 + 0.51 = 1.00; "close" asks
+    // 0.50 + 0.50 = 1.00 with mids 0.49 + 0.49 = 0.98. Promotion needs sum < 1.005.
+
+    #[test]
+    fn ask_basis_promotes_only_markets_whose_asks_are_close() {
+        let asks = prices(&[("1", dec!(0.50)), ("2", dec!(0.52)), ("3", dec!(0.50)), ("4", dec!(0.50)), ("5", dec!(0.1))]);
+        assert_eq!(promoted(PriceBasis::Ask, &asks), (vec!["close".to_string()], 2));
+    }
+
+    #[test]
+    fn mid_basis_casts_a_wider_net() {
+        let mids = prices(&[("1", dec!(0.49)), ("2", dec!(0.51)), ("3", dec!(0.49)), ("4", dec!(0.49)), ("5", dec!(0.1))]);
+        assert_eq!(promoted(PriceBasis::Mid, &mids), (vec!["wide".to_string(), "close".to_string()], 2));
+    }
+
+    #[test]
+    fn promotion_is_strictly_below_the_margin() {
+        let f = Prefilter { basis: PriceBasis::Ask, margin_bps: 0 };
+        let at = prices(&[("1", dec!(0.50)), ("2", dec!(0.50))]);
+        assert!(f.promote(&markets()[..1], &at).markets.is_empty());
+        let under = prices(&[("1", dec!(0.50)), ("2", dec!(0.4999))]);
+        assert_eq!(f.promote(&markets()[..1], &under).markets.len(), 1);
+    }
+}
@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+use tokio::task::JoinHandle;
+
+use crate::http::HttpClient;
+use crate::types::TokenId;
+
+/// Independent price source the strategy checks venue books against, to catch books that
+/// stopped updating.
+pub trait ReferenceFeed: Send + Sync {
+    /// Reference price for `token_id`; None when the feed has none, and the leg goes unchecked.
+    fn reference_px(&self, token_id: &TokenId) -> Option<Decimal>;
+}
+
+/// Reference prices polled from an HTTP endpoint answering a JSON object of token id to
+/// price (number or string). A poll replaces every price; after `max_age` without a
+/// successful poll the feed answers nothing rather than serve old prices.
+pub struct HttpReferenceFeed {
+    url: String,
+    http: HttpClient,
+    max_age: Duration,
+    prices: RwLock<Option<(Instant, HashMap<TokenId, Decimal>)>>,
+}
+
+impl HttpReferenceFeed {
+    pub fn new(url: String, http: HttpClient, max_age_sec: u64) -> Self {
+        Self { url, http, max_age: Duration::from_secs(max_age_sec), prices: RwLock::new(None) }
+    }
+
+    /// One poll; returns how many prices the feed gave.
+    pub async fn refresh(&self) -> Result<usize> {
+        let raw: HashMap<String, Decimal> = self.http.get_json(&self.url, &[]).await.context("GET reference prices failed")?;
+        let prices: HashMap<TokenId, Decimal> = raw.into_iter()
+            .filter(|(_, px)| *px > Decimal::ZERO)
+            .map(|(t, px)| (TokenId::from(t), px))
+            .collect();
+        let n = prices.len();
+        *self.prices.write().unwrap_or_else(|e| e.into_inner()) = Some((Instant::now(), prices));
+        Ok(n)
+    }
+
+    /// Polls every `every_sec` until aborted; failures keep the last prices until they age out.
+    pub fn spawn(self: Arc<Self>, every_sec: u64) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut tick = tokio::time::interval(Duration::from_secs(every_sec.max(1)));
+            loop {
+                tick.tick().await;
+                match self.refresh().await {
+                    Ok(n) => tracing::debug!(prices = n, "reference prices refreshed"),
+                    Err(e) => tracing::warn!(url = %self.url, err = %format!("{:#}", e), "reference price poll failed"),
+                }
+            }
+        })
+    }
+}
+
+impl ReferenceFeed for HttpReferenceFeed {
+    fn reference_px(&self, token_id: &TokenId) -> Option<Decimal> {
+        let prices = self.prices.read().unwrap_or_else(|e| e.into_inner());
+        let (at, prices) = prices.as_ref()?;
+        if at.elapsed() > self.max_age {
+            return None;
+        }
+        prices.get(token_id).copied()
+    }
+}
@@ -0,0 +1,159 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::jitter::Jitter;
+
+/// Interval is halved on every active cycle (near-arb hit or opportunity)...
+const SPEEDUP: f64 = 0.5;
+/// ...and grows by this factor on every quiet cycle once the quiet streak reaches `quiet_cycles`.
+const SLOWDOWN: f64 = 1.25;
+
+/// Decides how long the main loop sleeps between cycles.
+///
+/// Fixed mode always returns `poll_ms`. Adaptive mode starts at `poll_ms` (clamped to
+/// [min, max]), moves toward `min_ms` while cycles are active and back toward `max_ms`
+/// after `quiet_cycles` consecutive quiet ones.
+#[derive(Debug, Clone)]
+pub struct PollController {
+    /// Sleep spread (percent either way) and its source; the interval itself is not jittered
+    jitter: Option<(u32, Arc<Jitter>)>,
+    current_ms: u64,
+    min_ms: u64,
+    max_ms: u64,
+    quiet_cycles: u32,
+    quiet_streak: u32,
+    adaptive: bool,
+}
+
+impl PollController {
+    pub fn fixed(poll_ms: u64) -> Self {
+        Self {
+            jitter: None,
+            current_ms: poll_ms,
+            min_ms: poll_ms,
+            max_ms: poll_ms,
+            quiet_cycles: 0,
+            quiet_streak: 0,
+            adaptive: false,
+        }
+    }
+
+    pub fn adaptive(poll_ms: u64, min_ms: u64, max_ms: u64, quiet_cycles: u32) -> Self {
+        let min_ms = min_ms.max(1);
+        let max_ms = max_ms.max(min_ms);
+        Self {
+            jitter: None,
+            current_ms: poll_ms.clamp(min_ms, max_ms),
+            min_ms,
+            max_ms,
+            quiet_cycles: quiet_cycles.max(1),
+            quiet_streak: 0,
+            adaptive: true,
+        }
+    }
+
+    /// Spread each sleep by up to `pct` percent either way, so polls do not line up with
+    /// other clients on a fixed period. `0` disables.
+    pub fn with_jitter(mut self, pct: u32, jitter: Arc<Jitter>) -> Self {
+        self.jitter = (pct > 0).then_some((pct, jitter));
+        self
+    }
+
+    /// How long to sleep before the next cycle: the current interval with jitter applied.
+    pub fn sleep_duration(&self) -> Duration {
+        let d = Duration::from_millis(self.current_ms);
+        match &self.jitter {
+            Some((pct, j)) => j.spread(d, *pct),
+            None => d,
+        }
+    }
+
+    /// Current interval in milliseconds.
+    pub fn current_ms(&self) -> u64 {
+        self.current_ms
+    }
+
+    /// Records whether the last cycle was active and returns the next interval.
+    pub fn observe(&mut self, active: bool) -> u64 {
+        if !self.adaptive {
+            return self.current_ms;
+        }
+        if active {
+            self.quiet_streak = 0;
+            self.current_ms = self.scaled(SPEEDUP);
+        } else {
+            self.quiet_streak = self.quiet_streak.saturating_add(1);
+            if self.quiet_streak >= self.quiet_cycles {
+                self.current_ms = self.scaled(SLOWDOWN);
+            }
+        }
+        self.current_ms
+    }
+
+    fn scaled(&self, factor: f64) -> u64 {
+        let next = (self.current_ms as f64 * factor).round() as u64;
+        // make sure a slowdown always moves even at tiny intervals
+        let next = if factor > 1.0 { next.max(self.current_ms + 1) } else { next };
+        next.clamp(self.min_ms, self.max_ms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_ignores_activity() {
+        let mut p = PollController::fixed(1000);
+        assert_eq!(p.observe(true), 1000);
+        assert_eq!(p.observe(false), 1000);
+        assert_eq!(p.sleep_duration(), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn active_cycles_ramp_down_to_the_minimum() {
+        let mut p = PollController::adaptive(1000, 200, 5000, 3);
+        let ramp: Vec<u64> = (0..4).map(|_| p.observe(true)).collect();
+        assert_eq!(ramp, [500, 250, 200, 200]);
+    }
+
+    #[test]
+    fn quiet_cycles_decay_to_the_maximum_after_the_streak() {
+        let mut p = PollController::adaptive(1000, 200, 2000, 3);
+        let decay: Vec<u64> = (0..7).map(|_| p.observe(false)).collect();
+        // nothing moves until the third quiet cycle, then x1.25 per cycle up to the max
+        assert_eq!(decay, [1000, 1000, 1250, 1563, 1954, 2000, 2000]);
+    }
+
+    #[test]
+    fn an_active_cycle_resets_the_quiet_streak() {
+        let mut p = PollController::adaptive(1000, 100, 5000, 2);
+        p.observe(false);
+        assert_eq!(p.observe(true), 500);
+        assert_eq!(p.observe(false), 500);
+        assert_eq!(p.observe(false), 625);
+    }
+
+    #[test]
+    fn slowdown_moves_even_at_tiny_intervals() {
+        let mut p = PollController::adaptive(1, 1, 10, 1);
+        assert_eq!(p.observe(false), 2);
+        assert_eq!(p.observe(false), 3);
+    }
+
+    #[test]
+    fn start_is_clamped_into_bounds() {
+        assert_eq!(PollController::adaptive(50, 200, 2000, 1).current_ms(), 200);
+        assert_eq!(PollController::adaptive(9000, 200, 2000, 1).current_ms(), 2000);
+    }
+
+    #[test]
+    fn jitter_spreads_the_sleep_but_not_the_interval() {
+        let p = PollController::fixed(1000).with_jitter(10, Arc::new(Jitter::seeded(7)));
+        for _ in 0..50 {
+            let d = p.sleep_duration();
+            assert!(d >= Duration::from_millis(900) && d <= Duration::from_millis(1100), "{:?}", d);
+        }
+        assert_eq!(p.current_ms(), 1000);
+    }
+}
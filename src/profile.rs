@@ -0,0 +1,124 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::pm::executor::ExecutionMode;
+
+/// How far the bot is allowed to go toward real orders. Gates which executors `main`
+/// may build, independently of `execution_mode`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// Observer executor only
+    Observe,
+    /// Observer or simulated (mock) execution; nothing reaches the venue
+    #[default]
+    Paper,
+    /// Real orders; needs live_armed and credentials
+    Live,
+}
+
+impl std::fmt::Display for Profile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Profile::Observe => "observe",
+            Profile::Paper => "paper",
+            Profile::Live => "live",
+        })
+    }
+}
+
+/// The profile actually in force and the executor it allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Resolved {
+    pub profile: Profile,
+    pub mode: ExecutionMode,
+}
+
+/// Checks the requested profile and execution mode against arming and credentials.
+///
+/// A request the profile does not allow is downgraded with a warning, or is an error
+/// when `strict`. Live needs `armed` and `has_credentials`; this build has no live
+/// executor, so even an armed live profile runs as paper (or fails when strict).
+pub fn resolve(requested: Profile, mode: ExecutionMode, armed: bool, has_credentials: bool, strict: bool) -> Result<Resolved> {
+    let profile = match requested {
+        Profile::Live => {
+            let why = if !armed {
+                "LIVE_ARMED is not true"
+            } else if !has_credentials {
+                "no POLYMARKET_PRIVATE_KEY"
+            } else {
+                "this build has no live executor"
+            };
+            if strict {
+                bail!("PROFILE=live refused: {} (STRICT_PROFILE=true)", why);
+            }
+            tracing::warn!("!!! PROFILE=live refused: {}; running as PAPER, no orders will be sent !!!", why);
+            Profile::Paper
+        }
+        p => p,
+    };
+
+    let mode = match (profile, mode) {
+        (Profile::Observe, ExecutionMode::Mock) => {
+            if strict {
+                bail!("EXECUTION_MODE=mock is not allowed under PROFILE=observe (STRICT_PROFILE=true)");
+            }
+            tracing::warn!("EXECUTION_MODE=mock is not allowed under PROFILE=observe; using observer");
+            ExecutionMode::Observer
+        }
+        (_, m) => m,
+    };
+    Ok(Resolved { profile, mode })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolved(profile: Profile, mode: ExecutionMode) -> Resolved {
+        Resolved { profile, mode }
+    }
+
+    #[test]
+    fn observe_and_paper_keep_their_modes() {
+        use ExecutionMode::*;
+        for strict in [false, true] {
+            assert_eq!(resolve(Profile::Observe, Observer, false, false, strict).unwrap(), resolved(Profile::Observe, Observer));
+            assert_eq!(resolve(Profile::Paper, Observer, false, false, strict).unwrap(), resolved(Profile::Paper, Observer));
+            assert_eq!(resolve(Profile::Paper, Mock, false, false, strict).unwrap(), resolved(Profile::Paper, Mock));
+        }
+    }
+
+    #[test]
+    fn observe_refuses_mock_execution() {
+        assert_eq!(resolve(Profile::Observe, ExecutionMode::Mock, false, false, false).unwrap(), resolved(Profile::Observe, ExecutionMode::Observer));
+        assert!(resolve(Profile::Observe, ExecutionMode::Mock, false, false, true).is_err());
+    }
+
+    #[test]
+    fn live_falls_back_to_paper_unless_strict() {
+        // unarmed, armed without credentials, and armed with credentials (no live executor yet)
+        for (armed, creds) in [(false, false), (false, true), (true, false), (true, true)] {
+            for mode in [ExecutionMode::Observer, ExecutionMode::Mock] {
+                assert_eq!(resolve(Profile::Live, mode, armed, creds, false).unwrap(), resolved(Profile::Paper, mode));
+                assert!(resolve(Profile::Live, mode, armed, creds, true).is_err());
+            }
+        }
+    }
+
+    #[test]
+    fn strict_error_names_what_is_missing() {
+        let err = |armed, creds| resolve(Profile::Live, ExecutionMode::Observer, armed, creds, true).unwrap_err().to_string();
+        assert!(err(false, true).contains("LIVE_ARMED"));
+        assert!(err(true, false).contains("POLYMARKET_PRIVATE_KEY"));
+        assert!(err(true, true).contains("no live executor"));
+    }
+
+    #[test]
+    fn profile_reads_and_prints_lowercase() {
+        let p: Profile = serde_json::from_str("\"observe\"").unwrap();
+        assert_eq!(p, Profile::Observe);
+        assert_eq!(Profile::default().to_string(), "paper");
+        assert!(serde_json::from_str::<Profile>("\"LIVE\"").is_err());
+    }
+}
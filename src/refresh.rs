@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use tokio::sync::{watch, Notify};
+
+/// How one market list refresh ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RefreshOutcome {
+    /// Number of markets in the published list
+    Done(usize),
+    /// The previous list was kept
+    Failed(String),
+}
+
+/// Lets something outside the refresher (POST /refresh) ask for a market list refresh
+/// ahead of schedule and wait for it to finish.
+#[derive(Debug)]
+pub struct RefreshTrigger {
+    requested: Notify,
+    /// Refreshes finished so far and how the last one ended
+    done: watch::Sender<(u64, Option<RefreshOutcome>)>,
+}
+
+impl Default for RefreshTrigger {
+    fn default() -> Self {
+        Self { requested: Notify::new(), done: watch::channel((0, None)).0 }
+    }
+}
+
+impl RefreshTrigger {
+    /// Asks for a refresh. Requests made while one is running coalesce into one more.
+    pub fn request(&self) {
+        self.requested.notify_one();
+    }
+
+    /// Resolves once a refresh has been asked for.
+    pub async fn requested(&self) {
+        self.requested.notified().await
+    }
+
+    /// Records how a refresh ended, scheduled or not.
+    pub fn finished(&self, outcome: RefreshOutcome) {
+        self.done.send_modify(|(n, last)| {
+            *n += 1;
+            *last = Some(outcome);
+        });
+    }
+
+    /// Asks for a refresh and waits up to `wait` for one to finish after the request.
+    /// None when it took longer; the refresh still runs.
+    pub async fn request_and_wait(&self, wait: Duration) -> Option<RefreshOutcome> {
+        let mut rx = self.done.subscribe();
+        let seen = rx.borrow_and_update().0;
+        self.request();
+        let finished = tokio::time::timeout(wait, rx.wait_for(|(n, _)| *n > seen)).await.ok()?.ok()?;
+        finished.1.clone()
+    }
+}
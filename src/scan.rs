@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::Settings;
+use crate::history::PriceHistory;
+use crate::http::HttpClient;
+use crate::jitter::Jitter;
+use crate::pm::market_data::{MarketData, MarketDef};
+use crate::source;
+use crate::stats::Stats;
+use crate::strategy::fees::FeeModel;
+use crate::strategy::sum_arb::SumArbStrategy;
+use crate::strategy::{Strategy, StrategyContext};
+use crate::types::{GlobalSnapshot, OrderIntent};
+
+/// One emitted bundle of a scan.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OpportunityRecord {
+    pub bundle_id: Uuid,
+    pub market_id: String,
+    pub question: String,
+    pub legs: usize,
+    pub sum_ask: Decimal,
+    pub edge_bps: Decimal,
+    /// Shares per leg
+    pub size: Decimal,
+    pub net_profit: Decimal,
+}
+
+/// What one scan found. Nothing in it was executed.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanResult {
+    /// Fetch time of the snapshot the strategy ran on (ms)
+    pub snapshot_ts: i64,
+    pub markets_evaluated: u64,
+    pub opportunities: Vec<OpportunityRecord>,
+    pub intents: Vec<OrderIntent>,
+}
+
+/// One record per bundle among `intents`, in emission order; the question comes from
+/// `snap`, or is the market id when the snapshot has none.
+pub fn opportunity_records(snap: &GlobalSnapshot, intents: &[OrderIntent]) -> Vec<OpportunityRecord> {
+    let mut out: Vec<OpportunityRecord> = vec![];
+    let mut seen = std::collections::HashSet::new();
+    for i in intents {
+        if !seen.insert(i.bundle_id) {
+            continue;
+        }
+        let question = snap.markets.iter()
+            .find(|m| m.market_id == i.market_id)
+            .map(|m| m.question.clone())
+            .filter(|q| !q.is_empty())
+            .unwrap_or_else(|| i.market_id.clone());
+        out.push(OpportunityRecord {
+            bundle_id: i.bundle_id,
+            market_id: i.market_id.clone(),
+            question,
+            legs: intents.iter().filter(|l| l.bundle_id == i.bundle_id).count(),
+            sum_ask: i.meta.sum_ask,
+            edge_bps: i.meta.edge_bps,
+            size: i.size,
+            net_profit: i.meta.net_profit,
+        });
+    }
+    out
+}
+
+/// Fetches the open markets, takes one snapshot and runs one strategy pass, as the bot
+/// would with `settings`, without executing anything.
+pub async fn scan_once(settings: &Settings) -> Result<ScanResult> {
+    scan_markets(settings, None).await
+}
+
+/// Like `scan_once`, on `markets` when given instead of a fresh market list.
+///
+/// The market list is the venue's (or the replay's) with REDEMPTION_VALUES applied; the
+/// long-running bot's Gamma tagging, event and category filters and market scoring are not.
+/// Every call starts from empty state: no price history, no sum_ask EMA, and its own stats.
+pub async fn scan_markets(settings: &Settings, markets: Option<Vec<MarketDef>>) -> Result<ScanResult> {
+    let s = settings;
+    let parsed = s.parsed()?;
+    let now_ms = chrono::Utc::now().timestamp_millis() as u64;
+    let stats = Stats::new(now_ms);
+    let jitter = Arc::new(match parsed.jitter_seed {
+        Some(seed) => Jitter::seeded(seed),
+        None => Jitter::from_entropy(),
+    });
+    let clob_http = HttpClient::from_settings(s, jitter)?.with_rate_limit(s.clob_requests_per_sec, s.clob_burst);
+    let md = MarketData::from_settings(s, clob_http, false, stats.clone());
+    let data = source::from_settings(s, &md)?;
+
+    let mut markets = match markets {
+        Some(m) => m,
+        None => data.fetch_open_markets(s.max_markets).await?,
+    };
+    for m in markets.iter_mut() {
+        if let Some(v) = parsed.redemption_values.get(&m.market_id) {
+            m.redemption_value = Some(*v);
+        }
+    }
+    let snap = data.snapshot_for_markets(&markets).await?;
+
+    let fees = Arc::new(FeeModel::tiered(s.fee_bps, parsed.fee_tiers.clone(), parsed.fee_seed_volume, now_ms));
+    let strat = SumArbStrategy::from_settings(s, &parsed, fees, stats);
+    let mut history = PriceHistory::new(s.history_depth, s.history_evict_cycles);
+    history.record(&snap);
+    let ctx = StrategyContext { history: &history, meta: None, reference: None };
+    let (intents, summary) = strat.on_snapshot(&snap, &ctx);
+
+    Ok(ScanResult {
+        snapshot_ts: snap.ts_ms,
+        markets_evaluated: summary.markets_evaluated,
+        opportunities: opportunity_records(&snap, &intents),
+        intents,
+    })
+}
@@ -0,0 +1,135 @@
+use std::time::Instant;
+
+use rust_decimal::Decimal;
+
+use crate::config::Settings;
+use crate::http::HttpClient;
+use crate::pm::market_data::MarketData;
+use crate::types::OutcomeTop;
+
+/// Result of one self-test check.
+#[derive(Debug, Clone)]
+pub struct CheckResult {
+    pub name: &'static str,
+    /// A failing critical check makes the whole self-test fail
+    pub critical: bool,
+    /// None when the check was skipped (e.g. no credentials configured)
+    pub passed: Option<bool>,
+    pub latency_ms: u64,
+    pub detail: String,
+}
+
+impl CheckResult {
+    fn timed(name: &'static str, critical: bool, started: Instant, res: Result<String, String>) -> Self {
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let (passed, detail) = match res {
+            Ok(d) => (Some(true), d),
+            Err(d) => (Some(false), d),
+        };
+        Self { name, critical, passed, latency_ms, detail }
+    }
+
+    fn skipped(name: &'static str, detail: &str) -> Self {
+        Self { name, critical: false, passed: None, latency_ms: 0, detail: detail.to_string() }
+    }
+}
+
+/// Connectivity and configuration checks against the configured CLOB host, in order:
+/// reachability, one /markets page, one order book, sane decoded prices, credentials.
+/// Later checks are skipped once an earlier critical one fails.
+pub async fn run(s: &Settings, http: &HttpClient, md: &MarketData) -> Vec<CheckResult> {
+    let mut out = vec![];
+
+    let t = Instant::now();
+    let url = format!("{}/time", s.clob_host.trim_end_matches('/'));
+    let reach = http
+        .get_json::<serde_json::Value>(&url, &[])
+        .await
+        .map(|v| format!("server time {}", v))
+        .map_err(|e| e.to_string());
+    let reachable = reach.is_ok();
+    out.push(CheckResult::timed("host_reachable", true, t, reach));
+    if !reachable {
+        out.push(CheckResult::skipped("markets_page", "host unreachable"));
+        out.push(CheckResult::skipped("order_book", "host unreachable"));
+        out.push(CheckResult::skipped("book_sanity", "host unreachable"));
+    } else {
+        let t = Instant::now();
+        let page = md.fetch_markets_page(None).await;
+        let token = page.as_ref().ok().and_then(|p| p.open.first()).and_then(|m| m.token_ids.first().cloned());
+        let res = match &page {
+            Ok(p) if token.is_some() => Ok(format!("{} markets on first page, {} open", p.total, p.open.len())),
+            Ok(p) => Err(format!("{} markets on first page, none open with tokens", p.total)),
+            Err(e) => Err(format!("{:#}", e)),
+        };
+        out.push(CheckResult::timed("markets_page", true, t, res));
+
+        match token {
+            None => {
+                out.push(CheckResult::skipped("order_book", "no open market token to query"));
+                out.push(CheckResult::skipped("book_sanity", "no open market token to query"));
+            }
+            Some(tok) => {
+                let t = Instant::now();
+                let book = md.fetch_book(&tok).await;
+                let res = book.as_ref().map(|_| format!("token {}", tok)).map_err(|e| format!("{:#}", e));
+                out.push(CheckResult::timed("order_book", true, t, res));
+
+                let t = Instant::now();
+                match book {
+                    Ok(top) => out.push(CheckResult::timed("book_sanity", true, t, check_top(&top))),
+                    Err(_) => out.push(CheckResult::skipped("book_sanity", "order book fetch failed")),
+                }
+            }
+        }
+    }
+
+    out.push(match &s.polymarket_private_key {
+        None => CheckResult::skipped("credentials", "POLYMARKET_PRIVATE_KEY not set"),
+        Some(k) => {
+            let t = Instant::now();
+            CheckResult::timed("credentials", true, t, check_private_key(k.expose()))
+        }
+    });
+
+    out
+}
+
+/// Prints a PASS/FAIL/SKIP line per check; returns true if every critical check passed.
+pub fn print_report(results: &[CheckResult]) -> bool {
+    let mut ok = true;
+    for r in results {
+        let status = match r.passed {
+            Some(true) => "PASS",
+            Some(false) => "FAIL",
+            None => "SKIP",
+        };
+        if r.critical && r.passed == Some(false) {
+            ok = false;
+        }
+        println!("{:<4} {:<15} {:>6} ms  {}", status, r.name, r.latency_ms, r.detail);
+    }
+    println!("selftest {}", if ok { "passed" } else { "FAILED" });
+    ok
+}
+
+fn check_top(top: &OutcomeTop) -> Result<String, String> {
+    let in_range = |p: Option<Decimal>| p.map(|v| v >= Decimal::ZERO && v <= Decimal::ONE).unwrap_or(true);
+    if top.best_bid_px.is_none() && top.best_ask_px.is_none() {
+        return Err("book decoded with no bids and no asks".to_string());
+    }
+    if !in_range(top.best_bid_px) || !in_range(top.best_ask_px) {
+        return Err(format!("prices outside [0, 1]: bid={:?} ask={:?}", top.best_bid_px, top.best_ask_px));
+    }
+    Ok(format!("bid={:?} ask={:?}", top.best_bid_px, top.best_ask_px))
+}
+
+/// Shape check only: 32 bytes of hex, optionally 0x-prefixed. The key itself is never printed.
+fn check_private_key(k: &str) -> Result<String, String> {
+    let hex = k.trim().trim_start_matches("0x");
+    if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Ok("private key is well-formed".to_string())
+    } else {
+        Err("private key is not 32 bytes of hex".to_string())
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::config::Settings;
+use crate::pm::market_data::{MarketData, MarketDef};
+use crate::types::GlobalSnapshot;
+
+pub mod replay;
+
+/// Where market lists and book snapshots come from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    /// The Polymarket CLOB
+    #[default]
+    Clob,
+    /// Canned markets and snapshots from files; see `replay::ReplaySource`
+    Replay,
+}
+
+/// What the main loop reads markets and books through.
+pub trait MarketDataSource: Send + Sync {
+    /// Up to `max_markets` tradable markets.
+    fn fetch_open_markets(&self, max_markets: usize) -> BoxFuture<'_, Result<Vec<MarketDef>>>;
+
+    /// Books for `markets`, as one snapshot.
+    fn snapshot_for_markets<'a>(&'a self, markets: &'a [MarketDef]) -> BoxFuture<'a, Result<GlobalSnapshot>>;
+}
+
+/// The source DATA_SOURCE selects: `md` itself, or the replay files.
+pub fn from_settings(s: &Settings, md: &MarketData) -> Result<Arc<dyn MarketDataSource>> {
+    Ok(match s.data_source {
+        DataSource::Clob => Arc::new(md.clone()),
+        DataSource::Replay => {
+            let path = |v: &Option<String>, name: &str| {
+                v.as_deref().map(str::trim).filter(|p| !p.is_empty()).map(std::path::PathBuf::from)
+                    .with_context(|| format!("DATA_SOURCE=replay needs {}", name))
+            };
+            Arc::new(replay::ReplaySource::open(
+                &path(&s.replay_markets_path, "REPLAY_MARKETS_PATH")?,
+                &path(&s.replay_snapshots_path, "REPLAY_SNAPSHOTS_PATH")?,
+                s.replay_at_eof,
+            )?)
+        }
+    })
+}
+
+impl MarketDataSource for MarketData {
+    fn fetch_open_markets(&self, max_markets: usize) -> BoxFuture<'_, Result<Vec<MarketDef>>> {
+        Box::pin(MarketData::fetch_open_markets(self, max_markets))
+    }
+
+    fn snapshot_for_markets<'a>(&'a self, markets: &'a [MarketDef]) -> BoxFuture<'a, Result<GlobalSnapshot>> {
+        Box::pin(MarketData::snapshot_for_markets(self, markets))
+    }
+}
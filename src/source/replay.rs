@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::pm::market_data::{load_universe, MarketDef};
+use crate::types::GlobalSnapshot;
+
+use super::MarketDataSource;
+
+/// What a replay does once every recorded snapshot has been served.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReplayEof {
+    /// Fail with `ReplayExhausted`; the main loop takes that as a shutdown
+    #[default]
+    Stop,
+    /// Start over from the first snapshot
+    Loop,
+    /// Serve the last snapshot again
+    Hold,
+}
+
+/// Every recorded snapshot was served and the replay is set to stop.
+#[derive(Debug)]
+pub struct ReplayExhausted;
+
+impl std::fmt::Display for ReplayExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("replay finished: no snapshots left")
+    }
+}
+
+impl std::error::Error for ReplayExhausted {}
+
+/// Canned data for running the full bot offline: a fixed market list (the market cache
+/// format, a JSON array of `MarketDef`) and a sequence of snapshots (one `GlobalSnapshot`
+/// per JSONL line), served one per fetch.
+///
+/// Each snapshot is re-stamped as fetched now, with its leg book times moved by the same
+/// amount, so age and staleness checks see the ages that were recorded.
+pub struct ReplaySource {
+    markets: Vec<MarketDef>,
+    snapshots: Vec<GlobalSnapshot>,
+    at_eof: ReplayEof,
+    next: Mutex<usize>,
+}
+
+impl ReplaySource {
+    pub fn new(markets: Vec<MarketDef>, snapshots: Vec<GlobalSnapshot>, at_eof: ReplayEof) -> Result<Self> {
+        anyhow::ensure!(!snapshots.is_empty(), "replay has no snapshots");
+        Ok(Self { markets, snapshots, at_eof, next: Mutex::new(0) })
+    }
+
+    pub fn open(markets_path: &Path, snapshots_path: &Path, at_eof: ReplayEof) -> Result<Self> {
+        let markets = load_universe(markets_path)?
+            .with_context(|| format!("replay markets {} not found", markets_path.display()))?;
+        let raw = std::fs::read_to_string(snapshots_path)
+            .with_context(|| format!("read replay snapshots {}", snapshots_path.display()))?;
+        let snapshots = raw.lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| {
+                serde_json::from_str(l).with_context(|| format!("decode {} line {}", snapshots_path.display(), i + 1))
+            })
+            .collect::<Result<Vec<GlobalSnapshot>>>()?;
+        tracing::info!(markets = markets.len(), snapshots = snapshots.len(), at_eof = ?at_eof, "replay loaded");
+        Self::new(markets, snapshots, at_eof)
+    }
+
+    /// Index of the snapshot to serve next; None when stopped at the end.
+    fn advance(&self) -> Option<usize> {
+        let mut next = self.next.lock().unwrap_or_else(|e| e.into_inner());
+        let n = self.snapshots.len();
+        let i = match (*next < n, self.at_eof) {
+            (true, _) => *next,
+            (false, ReplayEof::Stop) => return None,
+            (false, ReplayEof::Loop) => {
+                tracing::info!(snapshots = n, "replay looping to the first snapshot");
+                0
+            }
+            (false, ReplayEof::Hold) => n - 1,
+        };
+        *next = i + 1;
+        Some(i)
+    }
+}
+
+impl MarketDataSource for ReplaySource {
+    fn fetch_open_markets(&self, max_markets: usize) -> BoxFuture<'_, Result<Vec<MarketDef>>> {
+        Box::pin(async move { Ok(self.markets.iter().take(max_markets).cloned().collect()) })
+    }
+
+    /// The next recorded snapshot, limited to `markets`.
+    fn snapshot_for_markets<'a>(&'a self, markets: &'a [MarketDef]) -> BoxFuture<'a, Result<GlobalSnapshot>> {
+        Box::pin(async move {
+            let i = self.advance().ok_or(ReplayExhausted)?;
+            let wanted: HashSet<&str> = markets.iter().map(|m| m.market_id.as_str()).collect();
+            let mut snap = self.snapshots[i].clone();
+            snap.markets.retain(|m| wanted.contains(m.market_id.as_str()));
+            let now = chrono::Utc::now().timestamp_millis();
+            let shift = now - snap.ts_ms;
+            snap.ts_ms = now;
+            for o in snap.markets.iter_mut().flat_map(|m| m.outcomes.iter_mut()) {
+                o.book_ts_ms = o.book_ts_ms.map(|t| t + shift);
+            }
+            Ok(snap)
+        })
+    }
+}
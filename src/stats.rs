@@ -1,6 +1,28 @@
 use serde::Serialize;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::profile::Profile;
+use crate::types::category_bucket;
+
+/// Upper bounds (ms, exclusive) of the snapshot-age histogram buckets; one more bucket
+/// holds everything above the last bound.
+pub const SNAPSHOT_AGE_BUCKETS_MS: [u64; 5] = [100, 250, 500, 1000, 2500];
+
+/// Cumulative values of the interval counters as of the last logged snapshot.
+#[derive(Default, Clone, Copy)]
+struct IntervalBase {
+    heartbeats: u64,
+    degraded_snapshots: u64,
+    near_arb_hits: u64,
+    opportunities: u64,
+    intents_emitted: u64,
+    bundles_deduplicated: u64,
+    bundles_suppressed_churn: u64,
+    books_fetched: u64,
+    books_missed: u64,
+}
 
 #[derive(Default)]
 pub struct Stats {
@@ -10,10 +32,61 @@ pub struct Stats {
     heartbeats: AtomicU64,
     markets_loaded: AtomicU64,
     markets_in_snapshot: AtomicU64,
+    degraded_snapshots: AtomicU64,
+    cycles_low_coverage: AtomicU64,
+    bus_events_dropped: AtomicU64,
 
     near_arb_hits: AtomicU64,
     opportunities: AtomicU64,
     intents_emitted: AtomicU64,
+    bundles_deduplicated: AtomicU64,
+    bundles_suppressed_churn: AtomicU64,
+    opportunity_episodes: AtomicU64,
+    /// Durations of the episodes closed since the last logged snapshot, oldest first
+    episode_durations_ms: Mutex<Vec<u64>>,
+
+    books_fetched: AtomicU64,
+    books_missed: AtomicU64,
+    books_missing_tokens: AtomicU64,
+    book_id_mismatches: AtomicU64,
+    legs_filled_from_cache: AtomicU64,
+    token_collisions: AtomicU64,
+    book_fallback_fetches: AtomicU64,
+    book_fallback_misses: AtomicU64,
+    hedges_fired: AtomicU64,
+    hedges_won: AtomicU64,
+    skipped_label_mismatch: AtomicU64,
+    skipped_multi_outcome: AtomicU64,
+    markets_resolved_detected: AtomicU64,
+    snapshots_dropped_stale: AtomicU64,
+    markets_refreshed_ms: AtomicU64,
+    markets_refresh_failures: AtomicU64,
+    markets_added: AtomicU64,
+    markets_removed: AtomicU64,
+    shadow_primary_only: AtomicU64,
+    shadow_only: AtomicU64,
+
+    max_snapshot_age_ms: AtomicU64,
+    snapshot_age_hist: [AtomicU64; SNAPSHOT_AGE_BUCKETS_MS.len() + 1],
+
+    poll_ms: AtomicU64,
+    profile: Mutex<Profile>,
+
+    slippage_bundles: AtomicU64,
+    /// Sum of per-bundle slippage in hundredths of a bp
+    slippage_bps_x100: AtomicI64,
+    bundles_flattened: AtomicU64,
+
+    interval_base: Mutex<IntervalBase>,
+    /// Near-arbs and opportunities per `category_bucket`
+    by_category: Mutex<BTreeMap<String, CategoryCounts>>,
+    skipped_by_reason: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct CategoryCounts {
+    pub near_arb_hits: u64,
+    pub opportunities: u64,
 }
 
 impl Stats {
@@ -24,6 +97,22 @@ impl Stats {
         s
     }
 
+    pub fn inc_category_near_arb(&self, category: Option<&str>) {
+        let mut by = self.by_category.lock().unwrap_or_else(|e| e.into_inner());
+        by.entry(category_bucket(category)).or_default().near_arb_hits += 1;
+    }
+
+    pub fn add_skipped(&self, reason: &'static str, n: u64) {
+        if n > 0 {
+            *self.skipped_by_reason.lock().unwrap_or_else(|e| e.into_inner()).entry(reason).or_default() += n;
+        }
+    }
+
+    pub fn inc_category_opportunity(&self, category: Option<&str>) {
+        let mut by = self.by_category.lock().unwrap_or_else(|e| e.into_inner());
+        by.entry(category_bucket(category)).or_default().opportunities += 1;
+    }
+
     pub fn inc_heartbeat(&self) {
         self.heartbeats.fetch_add(1, Ordering::Relaxed);
     }
@@ -32,10 +121,36 @@ impl Stats {
         self.markets_loaded.store(n, Ordering::Relaxed);
     }
 
+    pub fn mark_markets_refreshed(&self, now_ms: u64) {
+        self.markets_refreshed_ms.store(now_ms, Ordering::Relaxed);
+    }
+
+    pub fn add_markets_churn(&self, added: u64, removed: u64) {
+        self.markets_added.fetch_add(added, Ordering::Relaxed);
+        self.markets_removed.fetch_add(removed, Ordering::Relaxed);
+    }
+
+    pub fn add_shadow_diff(&self, primary_only: u64, shadow_only: u64) {
+        self.shadow_primary_only.fetch_add(primary_only, Ordering::Relaxed);
+        self.shadow_only.fetch_add(shadow_only, Ordering::Relaxed);
+    }
+
+    pub fn inc_markets_refresh_failure(&self) {
+        self.markets_refresh_failures.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn set_markets_in_snapshot(&self, n: u64) {
         self.markets_in_snapshot.store(n, Ordering::Relaxed);
     }
 
+    pub fn inc_degraded_snapshot(&self) {
+        self.degraded_snapshots.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn inc_cycle_low_coverage(&self) {
+        self.cycles_low_coverage.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn inc_near_arb(&self) {
         self.near_arb_hits.fetch_add(1, Ordering::Relaxed);
     }
@@ -44,43 +159,584 @@ impl Stats {
         self.opportunities.fetch_add(1, Ordering::Relaxed);
     }
 
+    /// An opportunity episode closed after lasting `duration_ms`.
+    pub fn add_opportunity_episode(&self, duration_ms: u64) {
+        // pushed under the lock so a snapshot never counts an episode it has no duration for
+        let mut d = self.episode_durations_ms.lock().unwrap_or_else(|e| e.into_inner());
+        self.opportunity_episodes.fetch_add(1, Ordering::Relaxed);
+        d.push(duration_ms);
+    }
+
     pub fn add_intents(&self, n: u64) {
         self.intents_emitted.fetch_add(n, Ordering::Relaxed);
     }
 
+    pub fn add_bundles_deduplicated(&self, n: u64) {
+        self.bundles_deduplicated.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn add_bundles_suppressed_churn(&self, n: u64) {
+        self.bundles_suppressed_churn.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Requested tokens a /books response left out.
+    pub fn add_books_missing_tokens(&self, n: u64) {
+        self.books_missing_tokens.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Markets dropped or marked shared at refresh for listing a token another market lists.
+    pub fn add_token_collisions(&self, n: u64) {
+        self.token_collisions.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// A book came back for a token that was not requested.
+    pub fn inc_book_id_mismatch(&self) {
+        self.book_id_mismatches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Missing legs filled from a cached top under MISSING_BOOK_POLICY=drop_cycle_use_cached.
+    pub fn add_legs_filled_from_cache(&self, n: u64) {
+        self.legs_filled_from_cache.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn add_markets_resolved_detected(&self, n: u64) {
+        self.markets_resolved_detected.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Events lost by in-process bus subscribers that fell behind.
+    pub fn add_bus_events_dropped(&self, n: u64) {
+        self.bus_events_dropped.fetch_add(n, Ordering::Relaxed);
+    }
+
+    /// Pipelined snapshots replaced by a newer one before evaluation took them.
+    pub fn add_snapshots_dropped_stale(&self, n: u64) {
+        self.snapshots_dropped_stale.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn add_hedge(&self, won: bool) {
+        self.hedges_fired.fetch_add(1, Ordering::Relaxed);
+        if won {
+            self.hedges_won.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Distinct tokens of one snapshot fetch with a book, and without one after the fallback.
+    pub fn add_books_fetched(&self, fetched: u64, missed: u64) {
+        self.books_fetched.fetch_add(fetched, Ordering::Relaxed);
+        self.books_missed.fetch_add(missed, Ordering::Relaxed);
+    }
+
+    pub fn add_book_fallback(&self, fetches: u64, misses: u64) {
+        self.book_fallback_fetches.fetch_add(fetches, Ordering::Relaxed);
+        self.book_fallback_misses.fetch_add(misses, Ordering::Relaxed);
+    }
+
+    /// Age of a snapshot (now - fetch time) when the strategy evaluated it.
+    pub fn record_snapshot_age(&self, age_ms: u64) {
+        self.max_snapshot_age_ms.fetch_max(age_ms, Ordering::Relaxed);
+        let bucket = SNAPSHOT_AGE_BUCKETS_MS.iter().position(|b| age_ms < *b).unwrap_or(SNAPSHOT_AGE_BUCKETS_MS.len());
+        self.snapshot_age_hist[bucket].fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add_label_mismatch(&self, n: u64) {
+        self.skipped_label_mismatch.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn add_multi_outcome(&self, n: u64) {
+        self.skipped_multi_outcome.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub fn set_poll_ms(&self, ms: u64) {
+        self.poll_ms.store(ms, Ordering::Relaxed);
+    }
+
+    /// The profile in force, carried on every snapshot.
+    pub fn set_profile(&self, profile: Profile) {
+        *self.profile.lock().unwrap_or_else(|e| e.into_inner()) = profile;
+    }
+
+    pub fn record_slippage(&self, bps: rust_decimal::Decimal) {
+        use rust_decimal::prelude::ToPrimitive;
+        let x100 = (bps * rust_decimal::Decimal::from(100)).round().to_i64().unwrap_or(0);
+        self.slippage_bundles.fetch_add(1, Ordering::Relaxed);
+        self.slippage_bps_x100.fetch_add(x100, Ordering::Relaxed);
+    }
+
+    /// A partially filled bundle had its filled legs sold back.
+    pub fn inc_bundles_flattened(&self) {
+        self.bundles_flattened.fetch_add(1, Ordering::Relaxed);
+    }
+
     pub fn should_log(&self, now_ms: u64, every_sec: u64) -> bool {
         if every_sec == 0 { return false; }
         let last = self.last_log_ms.load(Ordering::Relaxed);
         now_ms.saturating_sub(last) >= every_sec.saturating_mul(1000)
     }
 
-    pub fn mark_logged(&self, now_ms: u64) {
-        self.last_log_ms.store(now_ms, Ordering::Relaxed);
+    /// Starts a new interval at `logged`. The interval counters are the cumulative ones
+    /// minus their values in the last logged snapshot, so increments landing between
+    /// `snapshot` and this call are counted in the next interval rather than lost.
+    pub fn mark_logged(&self, logged: &StatsSnapshot) {
+        self.last_log_ms.store(logged.now_ms, Ordering::Relaxed);
+        *self.interval_base.lock().unwrap_or_else(|e| e.into_inner()) = IntervalBase {
+            heartbeats: logged.heartbeats,
+            degraded_snapshots: logged.degraded_snapshots,
+            near_arb_hits: logged.near_arb_hits,
+            opportunities: logged.opportunities,
+            intents_emitted: logged.intents_emitted,
+            bundles_deduplicated: logged.bundles_deduplicated,
+            bundles_suppressed_churn: logged.bundles_suppressed_churn,
+            books_fetched: logged.books_fetched,
+            books_missed: logged.books_missed,
+        };
+        let mut d = self.episode_durations_ms.lock().unwrap_or_else(|e| e.into_inner());
+        let n = (logged.opportunity_episodes_interval as usize).min(d.len());
+        d.drain(..n);
     }
 
     pub fn snapshot(&self, now_ms: u64) -> StatsSnapshot {
         let start = self.start_ms.load(Ordering::Relaxed);
+        let last_log = self.last_log_ms.load(Ordering::Relaxed);
+        let base = *self.interval_base.lock().unwrap_or_else(|e| e.into_inner());
+        let slippage_bundles = self.slippage_bundles.load(Ordering::Relaxed);
+        let heartbeats = self.heartbeats.load(Ordering::Relaxed);
+        let degraded_snapshots = self.degraded_snapshots.load(Ordering::Relaxed);
+        let near_arb_hits = self.near_arb_hits.load(Ordering::Relaxed);
+        let opportunities = self.opportunities.load(Ordering::Relaxed);
+        let intents_emitted = self.intents_emitted.load(Ordering::Relaxed);
+        let bundles_deduplicated = self.bundles_deduplicated.load(Ordering::Relaxed);
+        let bundles_suppressed_churn = self.bundles_suppressed_churn.load(Ordering::Relaxed);
+        let books_fetched = self.books_fetched.load(Ordering::Relaxed);
+        let books_missed = self.books_missed.load(Ordering::Relaxed);
+        let (opportunity_episodes, mut durations) = {
+            let d = self.episode_durations_ms.lock().unwrap_or_else(|e| e.into_inner());
+            (self.opportunity_episodes.load(Ordering::Relaxed), d.clone())
+        };
+        durations.sort_unstable();
         StatsSnapshot {
             now_ms,
-            up_sec: ((now_ms.saturating_sub(start)) / 1000) as u64,
-            heartbeats: self.heartbeats.load(Ordering::Relaxed),
+            up_sec: (now_ms.saturating_sub(start)) / 1000,
+            interval_sec: (now_ms.saturating_sub(last_log)) / 1000,
+            heartbeats,
+            heartbeats_interval: heartbeats.saturating_sub(base.heartbeats),
             markets_loaded: self.markets_loaded.load(Ordering::Relaxed),
+            markets_refresh_age_sec: match self.markets_refreshed_ms.load(Ordering::Relaxed) {
+                0 => None,
+                at => Some(now_ms.saturating_sub(at) / 1000),
+            },
+            markets_refresh_failures: self.markets_refresh_failures.load(Ordering::Relaxed),
+            markets_added: self.markets_added.load(Ordering::Relaxed),
+            markets_removed: self.markets_removed.load(Ordering::Relaxed),
+            shadow_primary_only: self.shadow_primary_only.load(Ordering::Relaxed),
+            shadow_only: self.shadow_only.load(Ordering::Relaxed),
             markets_in_snapshot: self.markets_in_snapshot.load(Ordering::Relaxed),
-            near_arb_hits: self.near_arb_hits.load(Ordering::Relaxed),
-            opportunities: self.opportunities.load(Ordering::Relaxed),
-            intents_emitted: self.intents_emitted.load(Ordering::Relaxed),
+            degraded_snapshots,
+            degraded_snapshots_interval: degraded_snapshots.saturating_sub(base.degraded_snapshots),
+            cycles_low_coverage: self.cycles_low_coverage.load(Ordering::Relaxed),
+            bus_events_dropped: self.bus_events_dropped.load(Ordering::Relaxed),
+            near_arb_hits,
+            near_arb_hits_interval: near_arb_hits.saturating_sub(base.near_arb_hits),
+            opportunities,
+            opportunities_interval: opportunities.saturating_sub(base.opportunities),
+            intents_emitted,
+            intents_emitted_interval: intents_emitted.saturating_sub(base.intents_emitted),
+            bundles_deduplicated,
+            bundles_deduplicated_interval: bundles_deduplicated.saturating_sub(base.bundles_deduplicated),
+            bundles_suppressed_churn,
+            bundles_suppressed_churn_interval: bundles_suppressed_churn.saturating_sub(base.bundles_suppressed_churn),
+            books_fetched,
+            books_fetched_interval: books_fetched.saturating_sub(base.books_fetched),
+            books_missed,
+            books_missed_interval: books_missed.saturating_sub(base.books_missed),
+            per_min_interval: {
+                let per_min = |n: u64| {
+                    let ms = now_ms.saturating_sub(last_log);
+                    if ms == 0 { 0.0 } else { (n as f64 * 60_000.0 / ms as f64 * 100.0).round() / 100.0 }
+                };
+                IntervalRates {
+                    heartbeats: per_min(heartbeats.saturating_sub(base.heartbeats)),
+                    opportunities: per_min(opportunities.saturating_sub(base.opportunities)),
+                    intents_emitted: per_min(intents_emitted.saturating_sub(base.intents_emitted)),
+                    books_fetched: per_min(books_fetched.saturating_sub(base.books_fetched)),
+                    books_missed: per_min(books_missed.saturating_sub(base.books_missed)),
+                }
+            },
+            opportunity_episodes,
+            opportunity_episodes_interval: durations.len() as u64,
+            episode_median_ms_interval: durations.get(durations.len() / 2).copied(),
+            books_missing_tokens: self.books_missing_tokens.load(Ordering::Relaxed),
+            book_id_mismatches: self.book_id_mismatches.load(Ordering::Relaxed),
+            legs_filled_from_cache: self.legs_filled_from_cache.load(Ordering::Relaxed),
+            token_collisions: self.token_collisions.load(Ordering::Relaxed),
+            book_fallback_fetches: self.book_fallback_fetches.load(Ordering::Relaxed),
+            book_fallback_misses: self.book_fallback_misses.load(Ordering::Relaxed),
+            hedges_fired: self.hedges_fired.load(Ordering::Relaxed),
+            hedges_won: self.hedges_won.load(Ordering::Relaxed),
+            skipped_label_mismatch: self.skipped_label_mismatch.load(Ordering::Relaxed),
+            skipped_multi_outcome: self.skipped_multi_outcome.load(Ordering::Relaxed),
+            markets_resolved_detected: self.markets_resolved_detected.load(Ordering::Relaxed),
+            snapshots_dropped_stale: self.snapshots_dropped_stale.load(Ordering::Relaxed),
+            max_snapshot_age_ms: self.max_snapshot_age_ms.load(Ordering::Relaxed),
+            snapshot_age_hist: self.snapshot_age_hist.iter().map(|c| c.load(Ordering::Relaxed)).collect(),
+            poll_ms: self.poll_ms.load(Ordering::Relaxed),
+            profile: *self.profile.lock().unwrap_or_else(|e| e.into_inner()),
+            slippage_bundles,
+            avg_slippage_bps: if slippage_bundles == 0 {
+                0.0
+            } else {
+                self.slippage_bps_x100.load(Ordering::Relaxed) as f64 / 100.0 / slippage_bundles as f64
+            },
+            bundles_flattened: self.bundles_flattened.load(Ordering::Relaxed),
+            by_category: self.by_category.lock().unwrap_or_else(|e| e.into_inner()).clone(),
+            skipped_by_reason: self.skipped_by_reason.lock().unwrap_or_else(|e| e.into_inner()).clone(),
         }
     }
 }
 
+/// Per-minute rates of the interval counters.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct IntervalRates {
+    pub heartbeats: f64,
+    pub opportunities: f64,
+    pub intents_emitted: f64,
+    pub books_fetched: f64,
+    pub books_missed: f64,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct StatsSnapshot {
     pub now_ms: u64,
     pub up_sec: u64,
+    /// Seconds since the last logged snapshot; the `*_interval` counters cover this span
+    pub interval_sec: u64,
     pub heartbeats: u64,
+    pub heartbeats_interval: u64,
     pub markets_loaded: u64,
+    /// Seconds since the market list was last refreshed; None before the first refresh
+    pub markets_refresh_age_sec: Option<u64>,
+    /// Background refreshes that failed (the previous list was kept)
+    pub markets_refresh_failures: u64,
+    /// Markets that appeared in / dropped out of the list across refreshes
+    pub markets_added: u64,
+    pub markets_removed: u64,
+    /// Market-cycles only the primary (resp. only the shadow strategy) bundled
+    pub shadow_primary_only: u64,
+    pub shadow_only: u64,
     pub markets_in_snapshot: u64,
+    pub degraded_snapshots: u64,
+    pub degraded_snapshots_interval: u64,
+    /// Cycles skipped because too few of the requested books came back
+    pub cycles_low_coverage: u64,
+    /// Events lost by in-process bus subscribers that fell behind
+    pub bus_events_dropped: u64,
     pub near_arb_hits: u64,
+    pub near_arb_hits_interval: u64,
     pub opportunities: u64,
+    pub opportunities_interval: u64,
     pub intents_emitted: u64,
+    pub intents_emitted_interval: u64,
+    pub bundles_deduplicated: u64,
+    pub bundles_deduplicated_interval: u64,
+    pub bundles_suppressed_churn: u64,
+    pub bundles_suppressed_churn_interval: u64,
+    /// Closed runs of consecutive snapshots in which a market was executable
+    pub opportunity_episodes: u64,
+    pub opportunity_episodes_interval: u64,
+    /// Median duration of the episodes closed this interval; None when none closed
+    pub episode_median_ms_interval: Option<u64>,
+    /// Requested tokens omitted from /books responses (before the single-book fallback)
+    pub books_missing_tokens: u64,
+    /// Books whose asset_id was not a requested token; dropped
+    pub book_id_mismatches: u64,
+    /// Missing legs filled from a cached top (drop_cycle_use_cached)
+    pub legs_filled_from_cache: u64,
+    /// Distinct tokens fetched with a book, and left without one after the fallback
+    pub books_fetched: u64,
+    pub books_fetched_interval: u64,
+    pub books_missed: u64,
+    pub books_missed_interval: u64,
+    /// The main interval counters as rates over this interval
+    pub per_min_interval: IntervalRates,
+    /// Markets dropped or marked shared at refresh for sharing a token id, over all refreshes
+    pub token_collisions: u64,
+    pub book_fallback_fetches: u64,
+    pub book_fallback_misses: u64,
+    /// Slow GET /book fallbacks raced by a second request, and how often it answered first
+    pub hedges_fired: u64,
+    pub hedges_won: u64,
+    /// Markets dropped at refresh for outcome labels outside the allowlist
+    pub skipped_label_mismatch: u64,
+    /// Markets dropped at refresh for having more than two outcomes (BINARY_ONLY)
+    pub skipped_multi_outcome: u64,
+    /// Markets dropped between refreshes because the venue reported them resolved
+    pub markets_resolved_detected: u64,
+    /// Pipelined snapshots replaced by a newer one before evaluation (PIPELINE_ENABLED)
+    pub snapshots_dropped_stale: u64,
+    pub max_snapshot_age_ms: u64,
+    /// Counts per SNAPSHOT_AGE_BUCKETS_MS bucket, last entry is the overflow bucket
+    pub snapshot_age_hist: Vec<u64>,
+    /// Effective sleep between cycles (varies in adaptive poll mode)
+    pub poll_ms: u64,
+    /// Profile in force, so rows read without the journal header keep it
+    pub profile: Profile,
+    /// Fully filled bundles with a realized edge
+    pub slippage_bundles: u64,
+    /// Mean of theoretical_edge_bps - realized_edge_bps over those bundles
+    pub avg_slippage_bps: f64,
+    /// Partially filled bundles whose filled legs were sold back (STRANDED_LEG_POLICY)
+    pub bundles_flattened: u64,
+    /// near_arb_hits and opportunities per market category, "uncategorized" when unknown
+    pub by_category: BTreeMap<String, CategoryCounts>,
+    /// Markets the strategy skipped, by `SkipReason`; reasons never hit are absent
+    pub skipped_by_reason: BTreeMap<&'static str, u64>,
+}
+
+/// Interval snapshots kept for a CSV export, oldest dropped beyond `max_rows`.
+pub struct StatsCsv {
+    rows: std::collections::VecDeque<serde_json::Map<String, serde_json::Value>>,
+    max_rows: usize,
+}
+
+impl StatsCsv {
+    pub fn new(max_rows: usize) -> Self {
+        Self { rows: Default::default(), max_rows: max_rows.max(1) }
+    }
+
+    pub fn push(&mut self, ss: &StatsSnapshot) {
+        let serde_json::Value::Object(row) = serde_json::to_value(ss).unwrap_or_default() else { return };
+        if self.rows.len() == self.max_rows {
+            self.rows.pop_front();
+        }
+        self.rows.push_back(row);
+    }
+
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// One `interval` row per snapshot, then a `summary` row: the latest cumulative
+    /// totals (per-interval columns left empty) with opportunities_per_hour and
+    /// intents_per_opportunity. Nested fields are JSON in a single cell.
+    pub fn render(&self) -> String {
+        let Some(last) = self.rows.back() else { return String::new() };
+        let columns: Vec<&String> = last.keys().collect();
+        let mut out = String::from("row");
+        for c in &columns {
+            out.push(',');
+            out.push_str(c);
+        }
+        out.push_str(",opportunities_per_hour,intents_per_opportunity\n");
+        for row in &self.rows {
+            out.push_str("interval");
+            for c in &columns {
+                out.push(',');
+                out.push_str(&csv_cell(row.get(*c)));
+            }
+            out.push_str(",,\n");
+        }
+
+        out.push_str("summary");
+        for c in &columns {
+            out.push(',');
+            if !(c.ends_with("_interval") || c.as_str() == "interval_sec") {
+                out.push_str(&csv_cell(last.get(*c)));
+            }
+        }
+        let num = |k: &str| last.get(k).and_then(|v| v.as_f64()).unwrap_or(0.0);
+        let (up_sec, opportunities) = (num("up_sec"), num("opportunities"));
+        let per_hour = if up_sec > 0.0 { opportunities * 3600.0 / up_sec } else { 0.0 };
+        let per_opp = if opportunities > 0.0 { num("intents_emitted") / opportunities } else { 0.0 };
+        out.push_str(&format!(",{:.3},{:.3}\n", per_hour, per_opp));
+        out
+    }
+}
+
+fn csv_cell(v: Option<&serde_json::Value>) -> String {
+    let raw = match v {
+        None | Some(serde_json::Value::Null) => return String::new(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(v) => v.to_string(),
+    };
+    if raw.contains([',', '"', '\n']) {
+        format!("\"{}\"", raw.replace('"', "\"\""))
+    } else {
+        raw
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bump(stats: &Stats, opportunities: u64, books: u64) {
+        for _ in 0..opportunities {
+            stats.inc_opportunity();
+        }
+        stats.add_books_fetched(books, 1);
+        stats.inc_heartbeat();
+    }
+
+    #[test]
+    fn interval_counters_reset_on_each_log() {
+        let stats = Stats::new(0);
+        bump(&stats, 3, 10);
+        let first = stats.snapshot(60_000);
+        assert_eq!((first.opportunities, first.opportunities_interval), (3, 3));
+        assert_eq!((first.books_fetched_interval, first.books_missed_interval), (10, 1));
+        assert_eq!(first.interval_sec, 60);
+        stats.mark_logged(&first);
+
+        bump(&stats, 2, 4);
+        let second = stats.snapshot(90_000);
+        assert_eq!((second.opportunities, second.opportunities_interval), (5, 2));
+        assert_eq!((second.books_fetched, second.books_fetched_interval), (14, 4));
+        assert_eq!((second.heartbeats, second.heartbeats_interval), (2, 1));
+        assert_eq!(second.interval_sec, 30);
+        // 2 in 30 s
+        assert_eq!(second.per_min_interval.opportunities, 4.0);
+        stats.mark_logged(&second);
+
+        let quiet = stats.snapshot(120_000);
+        assert_eq!((quiet.opportunities, quiet.opportunities_interval), (5, 0));
+        // last logged at 90 s
+        assert!(!stats.should_log(149_999, 60));
+        assert!(stats.should_log(150_000, 60));
+        assert!(!stats.should_log(150_000, 0));
+    }
+
+    /// Bumps every counter with an interval twin by `n`.
+    fn bump_all(stats: &Stats, n: u64) {
+        for _ in 0..n {
+            stats.inc_heartbeat();
+            stats.inc_degraded_snapshot();
+            stats.inc_near_arb();
+            stats.inc_opportunity();
+            stats.add_opportunity_episode(n * 100);
+        }
+        stats.add_intents(2 * n);
+        stats.add_bundles_deduplicated(n);
+        stats.add_bundles_suppressed_churn(n);
+        stats.add_books_fetched(10 * n, n);
+    }
+
+    /// (cumulative, interval) of every counter with an interval twin.
+    fn pairs(ss: &StatsSnapshot) -> [(u64, u64); 10] {
+        [
+            (ss.heartbeats, ss.heartbeats_interval),
+            (ss.degraded_snapshots, ss.degraded_snapshots_interval),
+            (ss.near_arb_hits, ss.near_arb_hits_interval),
+            (ss.opportunities, ss.opportunities_interval),
+            (ss.intents_emitted / 2, ss.intents_emitted_interval / 2),
+            (ss.bundles_deduplicated, ss.bundles_deduplicated_interval),
+            (ss.bundles_suppressed_churn, ss.bundles_suppressed_churn_interval),
+            (ss.opportunity_episodes, ss.opportunity_episodes_interval),
+            (ss.books_fetched / 10, ss.books_fetched_interval / 10),
+            (ss.books_missed, ss.books_missed_interval),
+        ]
+    }
+
+    #[test]
+    fn every_interval_counter_resets_while_cumulative_ones_grow() {
+        let stats = Stats::new(0);
+        bump_all(&stats, 3);
+        let first = stats.snapshot(60_000);
+        assert_eq!(pairs(&first), [(3, 3); 10]);
+        assert_eq!(first.episode_median_ms_interval, Some(300));
+        stats.mark_logged(&first);
+
+        let empty = stats.snapshot(60_000);
+        assert_eq!(pairs(&empty), [(3, 0); 10]);
+        assert_eq!(empty.episode_median_ms_interval, None);
+
+        bump_all(&stats, 2);
+        let second = stats.snapshot(90_000);
+        assert_eq!(pairs(&second), [(5, 2); 10]);
+        assert_eq!(second.episode_median_ms_interval, Some(200));
+        // 2 of each in 30 s
+        let r = second.per_min_interval;
+        assert_eq!((r.heartbeats, r.opportunities, r.intents_emitted, r.books_fetched, r.books_missed), (4.0, 4.0, 8.0, 40.0, 4.0));
+    }
+
+    #[test]
+    fn increments_between_snapshot_and_mark_are_not_lost() {
+        let stats = Stats::new(0);
+        bump(&stats, 1, 0);
+        let logged = stats.snapshot(1_000);
+        // lands after the logged snapshot was taken, before the interval is reset
+        stats.inc_opportunity();
+        stats.mark_logged(&logged);
+        let next = stats.snapshot(2_000);
+        assert_eq!((next.opportunities, next.opportunities_interval), (2, 1));
+    }
+
+    /// Splits one CSV line, honouring quoted cells.
+    fn cells(line: &str) -> Vec<String> {
+        let (mut out, mut cur, mut quoted) = (vec![], String::new(), false);
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && chars.peek() == Some(&'"') => {
+                    cur.push('"');
+                    chars.next();
+                }
+                '"' => quoted = !quoted,
+                ',' if !quoted => out.push(std::mem::take(&mut cur)),
+                c => cur.push(c),
+            }
+        }
+        out.push(cur);
+        out
+    }
+
+    /// Three logged 60 s intervals with 2, 0 and 4 opportunities and one intent each.
+    fn three_intervals() -> StatsCsv {
+        let stats = Stats::new(0);
+        let mut csv = StatsCsv::new(10);
+        for (k, opportunities) in [2, 0, 4].into_iter().enumerate() {
+            bump(&stats, opportunities, 0);
+            stats.add_intents(1);
+            let ss = stats.snapshot((k as u64 + 1) * 60_000);
+            stats.mark_logged(&ss);
+            csv.push(&ss);
+        }
+        csv
+    }
+
+    #[test]
+    fn csv_has_a_row_per_interval_and_a_summary() {
+        let out = three_intervals().render();
+        let rows: Vec<Vec<String>> = out.lines().map(cells).collect();
+        let header = &rows[0];
+        assert_eq!(header[0], "row");
+        assert_eq!(header[header.len() - 2..], ["opportunities_per_hour", "intents_per_opportunity"]);
+        assert!(rows.iter().all(|r| r.len() == header.len()), "ragged rows in {}", out);
+        let col = |name: &str| header.iter().position(|h| h == name).unwrap_or_else(|| panic!("no {} column", name));
+        let (kind, total, interval) = (col("row"), col("opportunities"), col("opportunities_interval"));
+
+        let body: Vec<(&str, &str, &str)> = rows[1..].iter().map(|r| (r[kind].as_str(), r[total].as_str(), r[interval].as_str())).collect();
+        assert_eq!(body, [("interval", "2", "2"), ("interval", "2", "0"), ("interval", "6", "4"), ("summary", "6", "")]);
+        // 6 opportunities in 3 minutes, 3 intents for 6 opportunities
+        let summary = &rows[4];
+        assert_eq!((summary[col("opportunities_per_hour")].as_str(), summary[col("intents_per_opportunity")].as_str()), ("120.000", "0.500"));
+        assert_eq!((summary[col("up_sec")].as_str(), summary[col("interval_sec")].as_str()), ("180", ""));
+    }
+
+    #[test]
+    fn csv_keeps_the_newest_rows() {
+        let stats = Stats::new(0);
+        let mut csv = StatsCsv::new(2);
+        assert_eq!(csv.render(), "");
+        for k in 1..=3 {
+            csv.push(&stats.snapshot(k * 60_000));
+        }
+        assert_eq!(csv.len(), 2);
+        let out = csv.render();
+        let up = cells(out.lines().next().unwrap()).iter().position(|h| h == "up_sec").unwrap();
+        let ups: Vec<String> = out.lines().skip(1).map(|l| cells(l)[up].clone()).collect();
+        assert_eq!(ups, ["120", "180", "180"]);
+    }
 }
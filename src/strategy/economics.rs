@@ -0,0 +1,177 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// One leg of a bundle: the price paid, the size bought and the taker fee on it.
+#[derive(Debug, Clone)]
+pub struct LegCost {
+    pub price: Decimal,
+    pub size: Decimal,
+    pub fee_bps: i64,
+}
+
+/// How a bought bundle is turned back into cash.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExitMode {
+    /// Hold to resolution and redeem the set: no exit fee, but capital is locked until then
+    #[default]
+    Redeem,
+    /// Sell the set back into the book right away, paying the taker fee on the proceeds
+    SellBack,
+}
+
+/// Money flows of buying a bundle and exiting it.
+///
+/// Every number the strategy logs about a bundle comes from here so the decision and
+/// the reporting never disagree.
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleEconomics {
+    /// Bundle size: the smallest leg size
+    pub size: Decimal,
+    /// Sum of price * size over legs, before fees
+    pub leg_cost: Decimal,
+    /// Sum of leg fees
+    pub fees: Decimal,
+    /// Fixed per-bundle cost (gas / relayer)
+    pub fixed_cost: Decimal,
+    /// Fee on the payout when exiting (zero when redeeming)
+    pub exit_fees: Decimal,
+    /// leg_cost + fees + fixed_cost + exit_fees
+    pub gross_cost: Decimal,
+    /// payoff_per_share * size
+    pub payout: Decimal,
+    /// payout - gross_cost
+    pub net_profit: Decimal,
+    /// net_profit / payout in bps
+    pub edge_bps: Decimal,
+    /// Smallest size at which the fixed cost is covered; None if no size is profitable
+    pub breakeven_size: Option<Decimal>,
+}
+
+impl BundleEconomics {
+    /// None for an empty bundle, one with no size, or one whose money flows overflow a
+    /// Decimal (only a corrupt book gets there). `exit_fee_bps` is charged on the payout,
+    /// for bundles sold back rather than redeemed.
+    pub fn compute(legs: &[LegCost], fixed_cost: Decimal, payoff_per_share: Decimal, exit_fee_bps: i64) -> Option<Self> {
+        let size = legs.iter().map(|l| l.size).min()?;
+        if size <= Decimal::ZERO || payoff_per_share <= Decimal::ZERO {
+            return None;
+        }
+
+        let mut leg_cost = Decimal::ZERO;
+        let mut fees = Decimal::ZERO;
+        let mut unit_cost = Decimal::ZERO;
+        for l in legs {
+            let fee_rate = Decimal::from(l.fee_bps) / dec!(10000);
+            let cost = l.price.checked_mul(size)?;
+            leg_cost = leg_cost.checked_add(cost)?;
+            fees = fees.checked_add(cost.checked_mul(fee_rate)?)?;
+            unit_cost = unit_cost.checked_add(l.price.checked_mul(dec!(1) + fee_rate)?)?;
+        }
+
+        let exit_rate = Decimal::from(exit_fee_bps) / dec!(10000);
+        let payout = payoff_per_share.checked_mul(size)?;
+        let exit_fees = payout.checked_mul(exit_rate)?;
+        let gross_cost = leg_cost.checked_add(fees)?.checked_add(fixed_cost)?.checked_add(exit_fees)?;
+        let net_profit = payout.checked_sub(gross_cost)?;
+        let edge_bps = (net_profit / payout).checked_mul(dec!(10000))?;
+
+        let unit_margin = payoff_per_share * (dec!(1) - exit_rate) - unit_cost;
+        let breakeven_size = if unit_margin > Decimal::ZERO {
+            Some(fixed_cost.max(Decimal::ZERO) / unit_margin)
+        } else {
+            None
+        };
+
+        Some(Self {
+            size,
+            leg_cost,
+            fees,
+            fixed_cost,
+            exit_fees,
+            gross_cost,
+            payout,
+            net_profit,
+            edge_bps,
+            breakeven_size,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legs(prices: &[Decimal], size: Decimal, fee_bps: i64) -> Vec<LegCost> {
+        prices.iter().map(|p| LegCost { price: *p, size, fee_bps }).collect()
+    }
+
+    #[test]
+    fn binary_bundle_without_costs() {
+        let e = BundleEconomics::compute(&legs(&[dec!(0.45), dec!(0.47)], dec!(100), 0), Decimal::ZERO, Decimal::ONE, 0).unwrap();
+        assert_eq!((e.leg_cost, e.fees, e.gross_cost), (dec!(92.00), dec!(0), dec!(92.00)));
+        assert_eq!((e.payout, e.net_profit, e.edge_bps), (dec!(100), dec!(8.00), dec!(800)));
+        assert_eq!(e.breakeven_size, Some(dec!(0)));
+    }
+
+    #[test]
+    fn taker_fees_are_charged_per_leg() {
+        let e = BundleEconomics::compute(&legs(&[dec!(0.45), dec!(0.47)], dec!(100), 20), Decimal::ZERO, Decimal::ONE, 0).unwrap();
+        assert_eq!(e.fees, dec!(0.184));
+        assert_eq!(e.gross_cost, dec!(92.184));
+        assert_eq!(e.net_profit, dec!(7.816));
+        assert_eq!(e.edge_bps, dec!(781.6));
+    }
+
+    #[test]
+    fn fixed_cost_sinks_a_small_bundle_but_not_a_large_one() {
+        let prices = [dec!(0.49), dec!(0.50)];
+        let small = BundleEconomics::compute(&legs(&prices, dec!(10), 0), dec!(0.50), Decimal::ONE, 0).unwrap();
+        assert_eq!(small.gross_cost, dec!(10.40));
+        assert_eq!(small.net_profit, dec!(-0.40));
+        assert_eq!(small.edge_bps, dec!(-400));
+
+        let large = BundleEconomics::compute(&legs(&prices, dec!(100), 0), dec!(0.50), Decimal::ONE, 0).unwrap();
+        assert_eq!(large.net_profit, dec!(0.50));
+        assert_eq!(large.edge_bps, dec!(50));
+        // 0.50 fixed over a 0.01 margin per share
+        assert_eq!(large.breakeven_size, Some(dec!(50)));
+    }
+
+    #[test]
+    fn sell_back_pays_the_exit_fee_on_the_payout() {
+        let e = BundleEconomics::compute(&legs(&[dec!(0.45), dec!(0.47)], dec!(100), 0), Decimal::ZERO, Decimal::ONE, 100).unwrap();
+        assert_eq!(e.exit_fees, dec!(1.00));
+        assert_eq!(e.net_profit, dec!(7.00));
+        assert_eq!(e.edge_bps, dec!(700));
+    }
+
+    #[test]
+    fn no_profitable_size_has_no_breakeven() {
+        let e = BundleEconomics::compute(&legs(&[dec!(0.50), dec!(0.51)], dec!(100), 0), dec!(0.1), Decimal::ONE, 0).unwrap();
+        assert!(e.net_profit < Decimal::ZERO);
+        assert_eq!(e.breakeven_size, None);
+    }
+
+    #[test]
+    fn bundle_size_is_the_smallest_leg() {
+        let mut l = legs(&[dec!(0.45), dec!(0.47)], dec!(100), 0);
+        l[1].size = dec!(40);
+        let e = BundleEconomics::compute(&l, Decimal::ZERO, Decimal::ONE, 0).unwrap();
+        assert_eq!((e.size, e.leg_cost, e.net_profit), (dec!(40), dec!(36.80), dec!(3.20)));
+    }
+
+    #[test]
+    fn overflowing_bundles_have_no_economics() {
+        assert!(BundleEconomics::compute(&legs(&[dec!(1), dec!(1)], Decimal::MAX, 0), Decimal::ZERO, Decimal::ONE, 0).is_none());
+        assert!(BundleEconomics::compute(&legs(&[dec!(0.5)], Decimal::MAX, 0), Decimal::ZERO, dec!(2), 0).is_none());
+    }
+
+    #[test]
+    fn empty_or_sizeless_bundles_have_no_economics() {
+        assert!(BundleEconomics::compute(&[], Decimal::ZERO, Decimal::ONE, 0).is_none());
+        assert!(BundleEconomics::compute(&legs(&[dec!(0.5)], Decimal::ZERO, 0), Decimal::ZERO, Decimal::ONE, 0).is_none());
+        assert!(BundleEconomics::compute(&legs(&[dec!(0.5)], dec!(1), 0), Decimal::ZERO, Decimal::ZERO, 0).is_none());
+    }
+}
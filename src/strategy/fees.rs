@@ -0,0 +1,144 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Mutex;
+
+use anyhow::{Context, Result};
+use rust_decimal::Decimal;
+
+/// Rolling window the venue uses for volume tiers.
+const VOLUME_WINDOW_MS: u64 = 30 * 24 * 3600 * 1000;
+
+/// Taker fee from `min_volume` (USD, 30-day notional) upward.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeeTier {
+    pub min_volume: Decimal,
+    pub fee_bps: i64,
+}
+
+/// Taker fee schedule keyed on rolling 30-day notional volume.
+///
+/// Fills are fed in with `record_fill`; the fee for the current tier is cached so the
+/// strategy can read it per market without locking.
+pub struct FeeModel {
+    /// Sorted by min_volume; the first tier always starts at 0
+    tiers: Vec<FeeTier>,
+    fills: Mutex<VecDeque<(u64, Decimal)>>,
+    current_bps: AtomicI64,
+}
+
+impl FeeModel {
+    /// One fee regardless of volume.
+    pub fn flat(fee_bps: i64) -> Self {
+        Self::tiered(fee_bps, vec![], Decimal::ZERO, 0)
+    }
+
+    /// `base_bps` applies below the lowest tier. `seed_volume` is 30-day volume done before
+    /// start (counted as of `now_ms`, so it ages out with the window).
+    pub fn tiered(base_bps: i64, mut tiers: Vec<FeeTier>, seed_volume: Decimal, now_ms: u64) -> Self {
+        tiers.retain(|t| t.min_volume > Decimal::ZERO);
+        tiers.sort_by_key(|t| t.min_volume);
+        tiers.insert(0, FeeTier { min_volume: Decimal::ZERO, fee_bps: base_bps });
+        let mut fills = VecDeque::new();
+        if seed_volume > Decimal::ZERO {
+            fills.push_back((now_ms, seed_volume));
+        }
+        let m = Self { tiers, fills: Mutex::new(fills), current_bps: AtomicI64::new(base_bps) };
+        m.refresh(now_ms);
+        m
+    }
+
+    /// Fee for the current volume tier.
+    pub fn fee_bps(&self) -> i64 {
+        self.current_bps.load(Ordering::Relaxed)
+    }
+
+    /// Adds a fill's notional (USD) to the rolling volume.
+    pub fn record_fill(&self, notional: Decimal, ts_ms: u64) {
+        if notional <= Decimal::ZERO {
+            return;
+        }
+        self.fills.lock().unwrap_or_else(|e| e.into_inner()).push_back((ts_ms, notional));
+        self.refresh(ts_ms);
+    }
+
+    /// Drops fills older than the window and re-picks the tier; returns the 30-day volume.
+    pub fn refresh(&self, now_ms: u64) -> Decimal {
+        let mut fills = self.fills.lock().unwrap_or_else(|e| e.into_inner());
+        while fills.front().map(|(ts, _)| now_ms.saturating_sub(*ts) >= VOLUME_WINDOW_MS).unwrap_or(false) {
+            fills.pop_front();
+        }
+        let volume: Decimal = fills.iter().map(|(_, v)| *v).sum();
+        let bps = self.tiers.iter()
+            .rev()
+            .find(|t| volume >= t.min_volume)
+            .map(|t| t.fee_bps)
+            .unwrap_or(self.tiers[0].fee_bps);
+        self.current_bps.store(bps, Ordering::Relaxed);
+        volume
+    }
+}
+
+/// Parses `"100000:15,1000000:10"` (30-day volume USD : fee bps).
+pub fn parse_tiers(s: &str) -> Result<Vec<FeeTier>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let (vol, bps) = x.split_once(':').with_context(|| format!("fee tier {:?}: expected volume:bps", x))?;
+            Ok(FeeTier {
+                min_volume: vol.trim().parse().with_context(|| format!("fee tier {:?}: bad volume", x))?,
+                fee_bps: bps.trim().parse().with_context(|| format!("fee tier {:?}: bad bps", x))?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+
+    const DAY_MS: u64 = 24 * 3600 * 1000;
+
+    fn tiers() -> Vec<FeeTier> {
+        parse_tiers("100000:15, 1000000:10").unwrap()
+    }
+
+    #[test]
+    fn crossing_a_threshold_lowers_the_fee() {
+        let f = FeeModel::tiered(20, tiers(), dec!(99_000), 0);
+        assert_eq!(f.fee_bps(), 20);
+        f.record_fill(dec!(999), DAY_MS);
+        assert_eq!(f.fee_bps(), 20);
+        f.record_fill(dec!(1), DAY_MS);
+        assert_eq!(f.fee_bps(), 15);
+        f.record_fill(dec!(900_000), DAY_MS);
+        assert_eq!(f.fee_bps(), 10);
+    }
+
+    #[test]
+    fn volume_ages_out_of_the_window() {
+        let f = FeeModel::tiered(20, tiers(), dec!(150_000), 0);
+        assert_eq!(f.fee_bps(), 15);
+        assert_eq!(f.refresh(29 * DAY_MS), dec!(150_000));
+        assert_eq!(f.refresh(30 * DAY_MS), Decimal::ZERO);
+        assert_eq!(f.fee_bps(), 20);
+    }
+
+    #[test]
+    fn flat_ignores_volume() {
+        let f = FeeModel::flat(7);
+        f.record_fill(dec!(10_000_000), 0);
+        assert_eq!(f.fee_bps(), 7);
+    }
+
+    #[test]
+    fn tiers_parse_in_any_order() {
+        let f = FeeModel::tiered(20, parse_tiers("1000000:10,100000:15").unwrap(), dec!(200_000), 0);
+        assert_eq!(f.fee_bps(), 15);
+        assert!(parse_tiers("100000").is_err());
+        assert!(parse_tiers("x:10").is_err());
+        assert!(parse_tiers("").unwrap().is_empty());
+    }
+}
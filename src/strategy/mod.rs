@@ -1,4 +1,8 @@
+pub mod economics;
+pub mod fees;
 pub mod mod_;
+pub mod pricing;
+pub mod shadow;
 pub mod sum_arb;
 
-pub use mod_::Strategy;
+pub use mod_::{CycleSummary, MarketHit, SkipReason, Strategy, StrategyContext};
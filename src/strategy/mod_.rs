@@ -1,5 +1,245 @@
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use crate::history::PriceHistory;
+use crate::pm::market_meta::MarketMetaCache;
+use crate::pm::reference::ReferenceFeed;
 use crate::types::{GlobalSnapshot, OrderIntent};
 
+/// Cross-cycle state the main loop maintains and hands to every strategy.
+pub struct StrategyContext<'a> {
+    /// Recent top of book per token, already including the snapshot being evaluated
+    pub history: &'a PriceHistory,
+    /// Markets known to be resolved are skipped; None when the lookup is disabled
+    pub meta: Option<&'a MarketMetaCache>,
+    /// Independent prices legs are checked against; None disables the check
+    pub reference: Option<&'a dyn ReferenceFeed>,
+}
+
 pub trait Strategy: Send + Sync {
-    fn on_snapshot(&self, snap: &GlobalSnapshot) -> Vec<OrderIntent>;
+    fn on_snapshot(&self, snap: &GlobalSnapshot, ctx: &StrategyContext<'_>) -> (Vec<OrderIntent>, CycleSummary);
+}
+
+/// Per-cycle funnel counters plus the best edge seen, logged once per loop iteration.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CycleSummary {
+    /// now - snapshot fetch time when evaluation started
+    pub snapshot_age_ms: u64,
+    pub markets_evaluated: u64,
+    pub skipped_too_few_legs: u64,
+    pub skipped_too_many_legs: u64,
+    pub skipped_missing_quote: u64,
+    pub skipped_leg_spread: u64,
+    pub skipped_leg_spread_pct: u64,
+    pub skipped_leg_size: u64,
+    pub skipped_anomaly: u64,
+    pub skipped_partial_book: u64,
+    pub skipped_resolved: u64,
+    pub skipped_suspected_glitch: u64,
+    pub skipped_implausible_sum_ask: u64,
+    pub skipped_stale_book: u64,
+    pub skipped_reference_deviation: u64,
+    pub skipped_no_size: u64,
+    pub skipped_rounded_to_zero: u64,
+    pub skipped_below_min_order: u64,
+    pub below_edge: u64,
+    pub near_arb_hits: u64,
+    pub opportunities: u64,
+    /// Opportunities dropped by max_bundles_per_snapshot (lowest expected profit first)
+    pub capped: u64,
+    /// Opportunities trimmed to fit max_cycle_notional
+    pub budget_trimmed: u64,
+    /// Opportunities not emitted because max_cycle_notional was spent
+    pub budget_dropped: u64,
+    /// Opportunities trimmed to the collateral earlier bundles of the cycle left
+    pub collateral_trimmed: u64,
+    /// Opportunities not emitted because earlier bundles committed the collateral
+    pub collateral_dropped: u64,
+    pub intents: u64,
+    pub best: Option<BestEdge>,
+    /// Best edge per Gamma event, for markets tagged with one
+    pub best_by_event: std::collections::HashMap<String, BestEdge>,
+    /// Markets that were a near-arb or an opportunity this cycle
+    pub hits: Vec<MarketHit>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketHit {
+    pub market_id: String,
+    pub question: String,
+    pub near_arb: bool,
+    pub opportunity: bool,
+    /// Net edge and sum of asks of the quote
+    pub edge_bps: Decimal,
+    pub sum_ask: Decimal,
+}
+
+/// Why a market produced no bundle this cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkipReason {
+    /// Some leg had no book this cycle (kept in the snapshot for logging only)
+    PartialBook,
+    /// The venue reports the market closed or a winner set
+    Resolved,
+    /// Opportunity sum_ask far from the market's EMA (SUM_ASK_EMA_GUARD)
+    SuspectedGlitch,
+    /// Opportunity sum_ask per $ of payout below min_plausible_sum_ask (unless trust_extreme_arbs)
+    ImplausibleSumAsk,
+    /// Some leg's book was last updated by the venue longer ago than max_book_update_age
+    StaleBook,
+    /// Some leg sat further than max_reference_deviation_bps from the reference feed
+    ReferenceDeviation,
+    TooFewLegs,
+    TooManyLegs,
+    MissingQuote,
+    /// Leg spread above max_leg_spread (absolute)
+    LegSpread,
+    /// Leg spread above max_leg_spread_pct of the leg's mid
+    LegSpreadPct,
+    LegSize,
+    /// Leg count or prices outside sane bounds, or arithmetic overflow
+    Anomaly,
+    NoSize,
+    /// Size rounded down to the venue's precision came out zero
+    RoundedToZero,
+    /// Rounded size under the market's minimum order size or min_leg_size
+    BelowMinOrder,
+    BelowEdge,
+}
+
+impl SkipReason {
+    pub const ALL: [SkipReason; 17] = [
+        SkipReason::PartialBook,
+        SkipReason::Resolved,
+        SkipReason::SuspectedGlitch,
+        SkipReason::ImplausibleSumAsk,
+        SkipReason::StaleBook,
+        SkipReason::ReferenceDeviation,
+        SkipReason::TooFewLegs,
+        SkipReason::TooManyLegs,
+        SkipReason::MissingQuote,
+        SkipReason::LegSpread,
+        SkipReason::LegSpreadPct,
+        SkipReason::LegSize,
+        SkipReason::Anomaly,
+        SkipReason::NoSize,
+        SkipReason::RoundedToZero,
+        SkipReason::BelowMinOrder,
+        SkipReason::BelowEdge,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::PartialBook => "partial_book",
+            SkipReason::Resolved => "resolved",
+            SkipReason::SuspectedGlitch => "suspected_glitch",
+            SkipReason::ImplausibleSumAsk => "implausible_sum_ask",
+            SkipReason::StaleBook => "stale_book",
+            SkipReason::ReferenceDeviation => "reference_deviation",
+            SkipReason::TooFewLegs => "too_few_legs",
+            SkipReason::TooManyLegs => "too_many_legs",
+            SkipReason::MissingQuote => "missing_quote",
+            SkipReason::LegSpread => "leg_spread",
+            SkipReason::LegSpreadPct => "leg_spread_pct",
+            SkipReason::LegSize => "leg_size",
+            SkipReason::Anomaly => "anomaly",
+            SkipReason::NoSize => "no_size",
+            SkipReason::RoundedToZero => "rounded_to_zero",
+            SkipReason::BelowMinOrder => "below_min_order",
+            SkipReason::BelowEdge => "below_edge",
+        }
+    }
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BestEdge {
+    pub market_id: String,
+    pub question: String,
+    pub sum_ask: Decimal,
+    /// Net edge per $ of payout in bps; negative when the bundle costs more than it pays.
+    pub edge_bps: Decimal,
+}
+
+impl CycleSummary {
+    /// True when the cycle produced anything worth logging on its own.
+    pub fn is_interesting(&self) -> bool {
+        self.near_arb_hits > 0 || self.opportunities > 0
+    }
+
+    /// Markets skipped for `reason` this cycle.
+    pub fn skipped(&self, reason: SkipReason) -> u64 {
+        match reason {
+            SkipReason::PartialBook => self.skipped_partial_book,
+            SkipReason::Resolved => self.skipped_resolved,
+            SkipReason::SuspectedGlitch => self.skipped_suspected_glitch,
+            SkipReason::ImplausibleSumAsk => self.skipped_implausible_sum_ask,
+            SkipReason::StaleBook => self.skipped_stale_book,
+            SkipReason::ReferenceDeviation => self.skipped_reference_deviation,
+            SkipReason::TooFewLegs => self.skipped_too_few_legs,
+            SkipReason::TooManyLegs => self.skipped_too_many_legs,
+            SkipReason::MissingQuote => self.skipped_missing_quote,
+            SkipReason::LegSpread => self.skipped_leg_spread,
+            SkipReason::LegSpreadPct => self.skipped_leg_spread_pct,
+            SkipReason::LegSize => self.skipped_leg_size,
+            SkipReason::Anomaly => self.skipped_anomaly,
+            SkipReason::NoSize => self.skipped_no_size,
+            SkipReason::RoundedToZero => self.skipped_rounded_to_zero,
+            SkipReason::BelowMinOrder => self.skipped_below_min_order,
+            SkipReason::BelowEdge => self.below_edge,
+        }
+    }
+
+    pub fn record_skip(&mut self, reason: SkipReason) {
+        match reason {
+            SkipReason::PartialBook => self.skipped_partial_book += 1,
+            SkipReason::Resolved => self.skipped_resolved += 1,
+            SkipReason::SuspectedGlitch => self.skipped_suspected_glitch += 1,
+            SkipReason::ImplausibleSumAsk => self.skipped_implausible_sum_ask += 1,
+            SkipReason::StaleBook => self.skipped_stale_book += 1,
+            SkipReason::ReferenceDeviation => self.skipped_reference_deviation += 1,
+            SkipReason::TooFewLegs => self.skipped_too_few_legs += 1,
+            SkipReason::TooManyLegs => self.skipped_too_many_legs += 1,
+            SkipReason::MissingQuote => self.skipped_missing_quote += 1,
+            SkipReason::LegSpread => self.skipped_leg_spread += 1,
+            SkipReason::LegSpreadPct => self.skipped_leg_spread_pct += 1,
+            SkipReason::LegSize => self.skipped_leg_size += 1,
+            SkipReason::Anomaly => self.skipped_anomaly += 1,
+            SkipReason::NoSize => self.skipped_no_size += 1,
+            SkipReason::RoundedToZero => self.skipped_rounded_to_zero += 1,
+            SkipReason::BelowMinOrder => self.skipped_below_min_order += 1,
+            SkipReason::BelowEdge => self.below_edge += 1,
+        }
+    }
+
+    pub fn observe_edge(&mut self, market_id: &str, question: &str, event_id: Option<&str>, sum_ask: Decimal, edge_bps: Decimal) {
+        let edge = || BestEdge {
+            market_id: market_id.to_string(),
+            question: question.to_string(),
+            sum_ask,
+            edge_bps,
+        };
+        if self.best.as_ref().map(|b| edge_bps > b.edge_bps).unwrap_or(true) {
+            self.best = Some(edge());
+        }
+        if let Some(ev) = event_id {
+            if self.best_by_event.get(ev).map(|b| edge_bps > b.edge_bps).unwrap_or(true) {
+                self.best_by_event.insert(ev.to_string(), edge());
+            }
+        }
+    }
+
+    /// Events ranked by their best edge, highest first.
+    pub fn event_leaderboard(&self, n: usize) -> Vec<(&str, &BestEdge)> {
+        let mut v: Vec<(&str, &BestEdge)> = self.best_by_event.iter().map(|(k, b)| (k.as_str(), b)).collect();
+        v.sort_by(|a, b| b.1.edge_bps.cmp(&a.1.edge_bps).then_with(|| a.0.cmp(b.0)));
+        v.truncate(n);
+        v
+    }
 }
@@ -0,0 +1,173 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{MarketBook, OutcomeTop};
+
+/// Which `PricingModel` the strategy prices bundles with.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PricingKind {
+    /// Every leg fills at its best ask, up to the size shown there
+    #[default]
+    TopOfBook,
+    /// Legs walk the captured ask levels, so larger bundles pay the worse levels too
+    DepthWalk,
+}
+
+impl PricingKind {
+    pub fn model(self) -> Arc<dyn PricingModel> {
+        match self {
+            PricingKind::TopOfBook => Arc::new(TopOfBook),
+            PricingKind::DepthWalk => Arc::new(DepthWalk),
+        }
+    }
+}
+
+/// What buying a bundle costs, kept apart from the question of whether it is an arb.
+///
+/// Implementations only price single legs; the bundle is the sum of its legs since every
+/// leg is bought at the same size.
+pub trait PricingModel: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Most shares of `leg` this model can price; zero when the leg has no ask.
+    fn leg_capacity(&self, leg: &OutcomeTop) -> Decimal;
+
+    /// Cost in USD of buying `size` shares of `leg`, before fees; None beyond its capacity.
+    fn leg_cost(&self, leg: &OutcomeTop, size: Decimal) -> Option<Decimal>;
+
+    /// Limit price that fills `size` shares of `leg`: the worst price touched.
+    fn leg_limit(&self, leg: &OutcomeTop, size: Decimal) -> Option<Decimal>;
+
+    /// Cost in USD of `size` complete sets of `market`, before fees; None when any leg
+    /// cannot be priced at that size.
+    fn bundle_cost(&self, market: &MarketBook, size: Decimal) -> Option<Decimal> {
+        market.outcomes.iter().try_fold(Decimal::ZERO, |acc, o| Some(acc + self.leg_cost(o, size)?))
+    }
+}
+
+/// Cost = size x the best ask of every leg (the strategy's original pricing).
+pub struct TopOfBook;
+
+impl PricingModel for TopOfBook {
+    fn name(&self) -> &'static str {
+        "top_of_book"
+    }
+
+    fn leg_capacity(&self, leg: &OutcomeTop) -> Decimal {
+        match (leg.best_ask_px, leg.best_ask_sz) {
+            (Some(_), Some(sz)) => sz,
+            _ => Decimal::ZERO,
+        }
+    }
+
+    fn leg_cost(&self, leg: &OutcomeTop, size: Decimal) -> Option<Decimal> {
+        (size <= self.leg_capacity(leg)).then_some(leg.best_ask_px? * size)
+    }
+
+    fn leg_limit(&self, leg: &OutcomeTop, size: Decimal) -> Option<Decimal> {
+        (size <= self.leg_capacity(leg)).then_some(leg.best_ask_px?)
+    }
+}
+
+/// Walks `OutcomeTop::ask_levels` from the best price up. A leg captured without depth
+/// is priced as a single level at its best ask, i.e. like `TopOfBook`.
+pub struct DepthWalk;
+
+impl DepthWalk {
+    fn levels(leg: &OutcomeTop) -> Vec<(Decimal, Decimal)> {
+        if !leg.ask_levels.is_empty() {
+            return leg.ask_levels.iter().map(|l| (l.px, l.sz)).collect();
+        }
+        leg.best_ask_px.zip(leg.best_ask_sz).into_iter().collect()
+    }
+
+    /// (cost, worst price) of taking `size` off the levels; None when they run out.
+    fn walk(leg: &OutcomeTop, size: Decimal) -> Option<(Decimal, Decimal)> {
+        let mut left = size;
+        let mut cost = Decimal::ZERO;
+        let mut worst = None;
+        for (px, sz) in Self::levels(leg) {
+            if left <= Decimal::ZERO {
+                break;
+            }
+            let take = left.min(sz);
+            cost += take * px;
+            left -= take;
+            worst = Some(px);
+        }
+        (left <= Decimal::ZERO).then_some((cost, worst?))
+    }
+}
+
+impl PricingModel for DepthWalk {
+    fn name(&self) -> &'static str {
+        "depth_walk"
+    }
+
+    fn leg_capacity(&self, leg: &OutcomeTop) -> Decimal {
+        Self::levels(leg).iter().map(|(_, sz)| *sz).sum()
+    }
+
+    fn leg_cost(&self, leg: &OutcomeTop, size: Decimal) -> Option<Decimal> {
+        Self::walk(leg, size).map(|(cost, _)| cost)
+    }
+
+    fn leg_limit(&self, leg: &OutcomeTop, size: Decimal) -> Option<Decimal> {
+        Self::walk(leg, size).map(|(_, worst)| worst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::testutil::snapshot_builder::SnapshotBuilder;
+    use crate::types::BookLevel;
+
+    /// Two legs, each with 10 at the top and more a little higher up.
+    fn deep_market() -> MarketBook {
+        let mut m = SnapshotBuilder::new().leg_size(dec!(10)).market(0);
+        let books = [[(dec!(0.45), dec!(10)), (dec!(0.47), dec!(20))], [(dec!(0.50), dec!(10)), (dec!(0.52), dec!(30))]];
+        for (o, levels) in m.outcomes.iter_mut().zip(books) {
+            o.best_ask_px = Some(levels[0].0);
+            o.best_ask_sz = Some(levels[0].1);
+            o.ask_levels = levels.iter().map(|&(px, sz)| BookLevel { px, sz }).collect();
+        }
+        m
+    }
+
+    #[test]
+    fn models_agree_within_the_top_level() {
+        let m = deep_market();
+        assert_eq!(TopOfBook.bundle_cost(&m, dec!(10)), Some(dec!(9.50)));
+        assert_eq!(DepthWalk.bundle_cost(&m, dec!(10)), Some(dec!(9.50)));
+        assert_eq!(TopOfBook.leg_limit(&m.outcomes[0], dec!(10)), DepthWalk.leg_limit(&m.outcomes[0], dec!(10)));
+    }
+
+    #[test]
+    fn depth_walk_prices_past_the_top_level() {
+        let m = deep_market();
+        assert_eq!(TopOfBook.bundle_cost(&m, dec!(25)), None);
+        // 10 @ 0.45 + 15 @ 0.47 and 10 @ 0.50 + 15 @ 0.52
+        assert_eq!(DepthWalk.bundle_cost(&m, dec!(25)), Some(dec!(24.35)));
+        assert_eq!(DepthWalk.leg_limit(&m.outcomes[0], dec!(25)), Some(dec!(0.47)));
+        assert_eq!((TopOfBook.leg_capacity(&m.outcomes[0]), DepthWalk.leg_capacity(&m.outcomes[0])), (dec!(10), dec!(30)));
+        // the first leg runs out at 30
+        assert_eq!(DepthWalk.bundle_cost(&m, dec!(31)), None);
+    }
+
+    #[test]
+    fn depth_walk_without_levels_is_top_of_book() {
+        let mut m = deep_market();
+        for o in &mut m.outcomes {
+            o.ask_levels.clear();
+        }
+        for size in [dec!(5), dec!(10), dec!(11)] {
+            assert_eq!(DepthWalk.bundle_cost(&m, size), TopOfBook.bundle_cost(&m, size));
+        }
+    }
+}
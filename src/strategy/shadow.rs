@@ -0,0 +1,127 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use serde::Serialize;
+
+use crate::stats::Stats;
+use crate::types::{GlobalSnapshot, OrderIntent};
+
+use super::{Strategy, StrategyContext};
+
+/// Markets each side emitted a bundle for in one cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ShadowDiff {
+    pub primary_only: BTreeSet<String>,
+    pub shadow_only: BTreeSet<String>,
+    pub both: BTreeSet<String>,
+}
+
+impl ShadowDiff {
+    pub fn agrees(&self) -> bool {
+        self.primary_only.is_empty() && self.shadow_only.is_empty()
+    }
+}
+
+/// Compares two cycles' intents by the markets they bundle.
+pub fn diff_bundles(primary: &[OrderIntent], shadow: &[OrderIntent]) -> ShadowDiff {
+    let p: BTreeSet<String> = primary.iter().map(|i| i.market_id.clone()).collect();
+    let s: BTreeSet<String> = shadow.iter().map(|i| i.market_id.clone()).collect();
+    ShadowDiff {
+        primary_only: p.difference(&s).cloned().collect(),
+        shadow_only: s.difference(&p).cloned().collect(),
+        both: p.intersection(&s).cloned().collect(),
+    }
+}
+
+/// A candidate strategy evaluated on the primary's snapshots for A/B comparison. Its
+/// intents are only diffed against the primary's, never executed.
+pub struct ShadowStrategy {
+    strategy: Box<dyn Strategy>,
+    /// The primary's stats, where disagreements are counted
+    stats: Arc<Stats>,
+}
+
+impl ShadowStrategy {
+    /// `strategy` should carry its own `Stats` so its funnel does not mix with the primary's.
+    pub fn new(strategy: Box<dyn Strategy>, stats: Arc<Stats>) -> Self {
+        Self { strategy, stats }
+    }
+
+    /// Runs the shadow on `snap` and logs every market only one side bundled.
+    pub fn compare(&self, snap: &GlobalSnapshot, ctx: &StrategyContext<'_>, primary: &[OrderIntent]) -> ShadowDiff {
+        let shadow = {
+            // the shadow's own strategy logs carry the span, telling them apart from the primary's
+            let _span = tracing::info_span!("shadow").entered();
+            self.strategy.on_snapshot(snap, ctx).0
+        };
+        let diff = diff_bundles(primary, &shadow);
+        for market_id in &diff.primary_only {
+            tracing::info!(market_id = %market_id, seen_by = "primary", "shadow disagreement");
+        }
+        for market_id in &diff.shadow_only {
+            tracing::info!(market_id = %market_id, seen_by = "shadow", "shadow disagreement");
+        }
+        self.stats.add_shadow_diff(diff.primary_only.len() as u64, diff.shadow_only.len() as u64);
+        diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::history::PriceHistory;
+    use crate::testutil::snapshot_builder::SnapshotBuilder;
+    use crate::testutil::strategy::{run, sum_arb};
+
+    /// Two-leg markets mkt-k priced to each sum: 50, 200 and -200 bps of edge.
+    fn snapshot() -> GlobalSnapshot {
+        let mut snap = SnapshotBuilder::new().markets(3).legs(2).leg_size(dec!(100)).build();
+        for (m, sum) in snap.markets.iter_mut().zip([dec!(0.995), dec!(0.98), dec!(1.02)]) {
+            for o in &mut m.outcomes {
+                o.best_ask_px = Some(sum / Decimal::TWO);
+                o.best_bid_px = Some(sum / Decimal::TWO - dec!(0.01));
+            }
+        }
+        snap
+    }
+
+    fn with_edge(bps: i64) -> Box<dyn Strategy> {
+        let mut s = sum_arb();
+        s.min_edge_bps = bps;
+        Box::new(s)
+    }
+
+    fn set(ids: &[&str]) -> BTreeSet<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn stricter_shadow_misses_the_thin_arb() {
+        let snap = snapshot();
+        let history = PriceHistory::new(0, 0);
+        let ctx = StrategyContext { history: &history, meta: None, reference: None };
+        let stats = Stats::new(0);
+        let (primary, _) = run(with_edge(30).as_ref(), &snap);
+
+        let diff = ShadowStrategy::new(with_edge(100), stats.clone()).compare(&snap, &ctx, &primary);
+        assert_eq!(diff, ShadowDiff { primary_only: set(&["mkt-0"]), shadow_only: set(&[]), both: set(&["mkt-1"]) });
+        assert!(!diff.agrees());
+        let ss = stats.snapshot(0);
+        assert_eq!((ss.shadow_primary_only, ss.shadow_only), (1, 0));
+    }
+
+    #[test]
+    fn looser_shadow_sees_what_the_primary_skips() {
+        let snap = snapshot();
+        let (primary, _) = run(with_edge(100).as_ref(), &snap);
+        let (shadow, _) = run(with_edge(30).as_ref(), &snap);
+        let diff = diff_bundles(&primary, &shadow);
+        assert_eq!(diff, ShadowDiff { primary_only: set(&[]), shadow_only: set(&["mkt-0"]), both: set(&["mkt-1"]) });
+
+        // the same thresholds agree
+        assert!(diff_bundles(&primary, &run(with_edge(100).as_ref(), &snap).0).agrees());
+    }
+}
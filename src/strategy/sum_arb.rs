@@ -1,125 +1,1745 @@
-use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+use anyhow::Context;
+use rayon::prelude::*;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rust_decimal_macros::dec;
+use serde::Serialize;
 use uuid::Uuid;
 
+use crate::config::{ParsedSettings, Settings};
+use crate::history::PriceHistory;
 use crate::stats::Stats;
-use crate::types::{GlobalSnapshot, OrderIntent, Side};
-use super::Strategy;
+use crate::types::{GlobalSnapshot, IntentMeta, MarketBook, OrderIntent, OutcomeTop, Polarity, Side, TimeInForce};
+use super::economics::{BundleEconomics, ExitMode, LegCost};
+use super::fees::FeeModel;
+use super::pricing::PricingModel;
+use crate::pm::reference::ReferenceFeed;
+use super::{CycleSummary, MarketHit, SkipReason, Strategy, StrategyContext};
+
+/// `IntentMeta::strategy` of the bundles this strategy emits.
+pub const STRATEGY_NAME: &str = "sum_arb";
+
+/// Markets with more legs than this are treated as corrupt rather than summed.
+const MAX_BUNDLE_LEGS: usize = 64;
+
+/// How bundle sizes are cut to what the venue accepts. Always rounds down, so a bundle
+/// never asks for more than the displayed size that capped it.
+#[derive(Debug, Clone, Copy)]
+pub struct SizeRounding {
+    /// Decimal places the venue accepts on order sizes
+    pub decimals: u32,
+}
+
+impl SizeRounding {
+    pub fn round(&self, size: Decimal) -> Decimal {
+        size.round_dp_with_strategy(self.decimals, RoundingStrategy::ToZero)
+    }
+}
 
 #[derive(Clone)]
 pub struct SumArbStrategy {
     pub min_edge_bps: i64,
+    /// min_edge_bps override by leg count; markets with a count not listed use min_edge_bps
+    pub min_edge_bps_by_legs: HashMap<usize, i64>,
+    /// Expected fill slippage, added to min_edge for the execute decision only
+    pub slippage_buffer_bps: i64,
     pub warn_edge_bps: i64,
-    pub fee_bps: i64,
-    pub max_bundle_size: Decimal,
+    /// Taker fee schedule; the current tier's fee is used for every leg
+    pub fees: std::sync::Arc<FeeModel>,
+    /// Leg cost and fillable size at a bundle size; the edge check and leg limit prices
+    /// come from it
+    pub pricing: std::sync::Arc<dyn PricingModel>,
+    /// Shares per bundle
+    pub max_bundle_shares: Decimal,
+    /// Venue precision bundle sizes are rounded down to
+    pub size_rounding: SizeRounding,
+    /// Cost per bundle in USD (size * sum_ask); None disables the cap
+    pub max_bundle_notional: Option<Decimal>,
+    /// Cost of all bundles emitted in one cycle in USD, best expected profit first; once
+    /// spent, the bundle that crosses it is trimmed to the remainder and later ones are
+    /// counted but not emitted. None disables the budget
+    pub max_cycle_notional: Option<Decimal>,
     pub max_leg_spread: Option<Decimal>,
+    /// Max per-leg spread as a percentage of the leg's mid; when set together with
+    /// `max_leg_spread` a leg must pass both
+    pub max_leg_spread_pct: Option<Decimal>,
     pub min_leg_size: Option<Decimal>,
+    /// Collateral available to fund bundles (USD); None disables the cap
+    pub available_collateral: Option<Decimal>,
+    /// Kept aside from `available_collateral` and never committed to a bundle
+    pub collateral_reserve: Decimal,
+    /// Fixed cost per bundle in USD (gas / relayer for redemption)
+    pub bundle_fixed_cost: Decimal,
+    /// Redeem pays no exit fee; SellBack pays the taker fee again on the payout
+    pub exit_mode: ExitMode,
+    /// Markets with a leg whose venue-side book update is older than this are skipped;
+    /// None disables the check
+    pub max_book_update_age_ms: Option<i64>,
+    /// Whether a leg without a venue timestamp counts as stale for that check
+    pub unknown_book_age_stale: bool,
+    /// Skip markets with a leg this far from the context's reference price; None disables
+    pub max_reference_deviation_bps: Option<i64>,
+    /// Markets with fewer outcomes are skipped (at least 1 is always required)
+    pub min_legs: usize,
+    /// Markets with more outcomes are skipped; 0 means unlimited
+    pub max_legs: usize,
+    /// Bundles emitted per snapshot, best expected profit first; 0 means unlimited
+    pub max_bundles_per_snapshot: usize,
+    /// Markets whose every filter decision is logged each cycle
+    pub trace_market_ids: std::collections::HashSet<String>,
+    /// Log each leg of every opportunity on its own line, keyed by bundle_id
+    pub log_leg_detail: bool,
+    /// Snapshots with at least this many markets are quoted on the rayon pool; 0 keeps
+    /// evaluation serial
+    pub parallel_min_markets: usize,
+    /// Volatility scaling of the execute threshold: min_edge + k * volatility_bps, where
+    /// volatility_bps is the largest per-leg top-of-book move over `vol_lookback_ms`
+    /// (price units * 10000). None keeps the static threshold
+    pub vol_edge_k: Option<Decimal>,
+    pub vol_lookback_ms: i64,
+    /// Observations each leg needs in the lookback before scaling applies
+    pub vol_min_samples: usize,
+    /// Time in force set on every bundle leg
+    pub leg_tif: TimeInForce,
+    /// Post-only flag set on every bundle leg
+    pub leg_post_only: bool,
+    /// Smoothing of the per-market sum_ask EMA (weight of the newest cycle); None disables
+    /// tracking
+    pub sum_ask_ema_alpha: Option<Decimal>,
+    /// An opportunity whose sum_ask is further than this from the market's EMA (bps of the
+    /// EMA) is logged as a suspected glitch
+    pub sum_ask_ema_band_bps: i64,
+    /// Also skip such opportunities instead of only logging them
+    pub sum_ask_ema_guard: bool,
+    /// An opportunity whose sum_ask per $ of payout is below this is more likely missing
+    /// legs or bad data than a real arb, and is logged as implausible; None disables
+    pub min_plausible_sum_ask: Option<Decimal>,
+    /// Execute implausible opportunities anyway instead of skipping them
+    pub trust_extreme_arbs: bool,
+    /// sum_ask EMA per market id, updated once per cycle
+    pub sum_ask_ema: std::sync::Arc<std::sync::Mutex<HashMap<String, Decimal>>>,
     pub stats: std::sync::Arc<Stats>,
 }
 
+/// Bundle pricing for a market that passed every per-leg filter.
+#[derive(Debug, Clone, Serialize)]
+pub struct BundleQuote {
+    /// Sum of the best asks, whatever the pricing model
+    pub sum_ask: Decimal,
+    pub sum_bid: Decimal,
+    /// Bundle size after the thinnest ask, share, notional and collateral caps
+    pub size: Decimal,
+    /// Net edge per $ of payout in bps, rounded for display (see `econ` for exact values)
+    pub edge_bps: Decimal,
+    /// What one complete set pays: the market's redemption value when set, else 1 for YES
+    /// bundles and n - 1 for NO bundles
+    pub payoff: Decimal,
+    /// econ.net_profit less slippage_buffer_bps of the payout
+    pub buffered_profit: Decimal,
+    /// Market volatility score in bps; None when scaling is off or history is too short
+    pub vol_bps: Option<Decimal>,
+    /// Required net edge before the slippage buffer: min_edge_bps, plus k * vol_bps
+    /// when scaling applies
+    pub min_edge_bps: Decimal,
+    pub econ: BundleEconomics,
+}
+
 impl SumArbStrategy {
+    /// The strategy `s` describes, as the bot runs it.
+    pub fn from_settings(s: &Settings, parsed: &ParsedSettings, fees: std::sync::Arc<FeeModel>, stats: std::sync::Arc<Stats>) -> Self {
+        SumArbStrategy {
+            min_edge_bps: s.min_edge_bps,
+            min_edge_bps_by_legs: parsed.min_edge_bps_by_legs.clone(),
+            slippage_buffer_bps: s.slippage_buffer_bps,
+            warn_edge_bps: s.warn_edge_bps,
+            fees,
+            pricing: s.pricing_model.model(),
+            max_bundle_shares: parsed.max_bundle_shares,
+            size_rounding: SizeRounding { decimals: s.size_decimals },
+            max_bundle_notional: parsed.max_bundle_notional,
+            max_cycle_notional: parsed.max_cycle_notional,
+            max_leg_spread: parsed.max_leg_spread,
+            max_leg_spread_pct: parsed.max_leg_spread_pct,
+            min_leg_size: parsed.min_leg_size,
+            available_collateral: parsed.available_collateral,
+            collateral_reserve: parsed.collateral_reserve,
+            bundle_fixed_cost: parsed.bundle_fixed_cost,
+            exit_mode: s.bundle_exit_mode,
+            max_book_update_age_ms: (s.max_book_update_age_sec > 0).then_some(s.max_book_update_age_sec as i64 * 1000),
+            unknown_book_age_stale: s.unknown_book_age_stale,
+            max_reference_deviation_bps: (s.max_reference_deviation_bps > 0).then_some(s.max_reference_deviation_bps as i64),
+            min_legs: s.min_legs,
+            max_legs: s.max_legs,
+            max_bundles_per_snapshot: s.max_bundles_per_snapshot,
+            parallel_min_markets: s.strategy_parallel_min_markets,
+            vol_edge_k: parsed.vol_edge_k,
+            vol_lookback_ms: s.vol_lookback_ms as i64,
+            vol_min_samples: s.vol_min_samples,
+            sum_ask_ema_alpha: parsed.sum_ask_ema_alpha,
+            sum_ask_ema_band_bps: s.sum_ask_ema_band_bps,
+            sum_ask_ema_guard: s.sum_ask_ema_guard,
+            min_plausible_sum_ask: parsed.min_plausible_sum_ask,
+            trust_extreme_arbs: s.trust_extreme_arbs,
+            sum_ask_ema: Default::default(),
+            leg_tif: s.leg_tif,
+            leg_post_only: s.leg_post_only && s.leg_tif == TimeInForce::Gtc,
+            trace_market_ids: s.trace_market_ids.as_deref().unwrap_or("")
+                .split(',')
+                .map(|x| x.trim().to_string())
+                .filter(|x| !x.is_empty())
+                .collect(),
+            log_leg_detail: s.log_leg_detail,
+            stats,
+        }
+    }
+
     fn bps(bps: i64) -> Decimal {
         Decimal::from(bps) / dec!(10000)
     }
+
+    /// Static execute threshold for `m`: the override for its leg count, else min_edge_bps.
+    pub fn min_edge_for(&self, m: &MarketBook) -> i64 {
+        self.min_edge_bps_by_legs.get(&m.outcomes.len()).copied().unwrap_or(self.min_edge_bps)
+    }
+
+    /// Collateral bundles may commit: available minus the reserve. None when uncapped.
+    fn usable_collateral(&self) -> Option<Decimal> {
+        self.available_collateral.map(|a| (a - self.collateral_reserve).max(Decimal::ZERO))
+    }
+
+    /// Largest bundle size whose fee-inclusive cost fits in `cash`.
+    fn collateral_cap(&self, cash: Decimal, sum_ask: Decimal, fee: Decimal) -> Option<Decimal> {
+        let usable = (cash - self.bundle_fixed_cost).max(Decimal::ZERO);
+        let unit_cost = sum_ask * (dec!(1) + fee);
+        if unit_cost <= Decimal::ZERO {
+            return None;
+        }
+        Some(usable / unit_cost)
+    }
+
+    /// Per-leg filters: both sides quoted, spread and top-of-book size limits.
+    pub fn check_leg(&self, o: &OutcomeTop) -> Result<(), SkipReason> {
+        let (ask, bid) = match (o.best_ask_px, o.best_bid_px) {
+            (Some(a), Some(b)) => (a, b),
+            _ => return Err(SkipReason::MissingQuote),
+        };
+
+        if let Some(max_spread) = self.max_leg_spread {
+            if ask - bid > max_spread { return Err(SkipReason::LegSpread); }
+        }
+        if let Some(max_pct) = self.max_leg_spread_pct {
+            match o.spread_bps() {
+                Some(bps) if bps / dec!(100) <= max_pct => {}
+                _ => return Err(SkipReason::LegSpreadPct),
+            }
+        }
+        if let Some(min_sz) = self.min_leg_size {
+            let a_sz = o.best_ask_sz.unwrap_or(Decimal::ZERO);
+            let b_sz = o.best_bid_sz.unwrap_or(Decimal::ZERO);
+            if a_sz < min_sz || b_sz < min_sz { return Err(SkipReason::LegSize); }
+        }
+        Ok(())
+    }
+
+    /// Runs the market-level and per-leg filters and prices the bundle.
+    /// Does not apply the execute threshold; see `is_executable`.
+    pub fn quote(&self, m: &MarketBook) -> Result<BundleQuote, SkipReason> {
+        if m.partial { return Err(SkipReason::PartialBook); }
+        if m.outcomes.len() < self.min_legs.max(1) { return Err(SkipReason::TooFewLegs); }
+        // a single NO leg pays nothing
+        if m.polarity == Polarity::No && m.outcomes.len() < 2 { return Err(SkipReason::TooFewLegs); }
+        if self.max_legs > 0 && m.outcomes.len() > self.max_legs { return Err(SkipReason::TooManyLegs); }
+        for o in &m.outcomes {
+            self.check_leg(o)?;
+        }
+        if let Err(why) = sane_book(m) {
+            tracing::warn!(market_id = %m.market_id, question = %m.question, why, "anomalous book, skipping market");
+            return Err(SkipReason::Anomaly);
+        }
+
+        let fee = Self::bps(self.fees.fee_bps());
+
+        // sum_ask, sum_bid, bundle size cap
+        let mut sum_ask = dec!(0);
+        let mut sum_bid = dec!(0);
+        let mut buy_cap: Option<Decimal> = None;
+
+        for o in &m.outcomes {
+            let ask_sz = self.pricing.leg_capacity(o);
+            let ask_px = match o.best_ask_px {
+                Some(px) if ask_sz > Decimal::ZERO => px,
+                _ => { buy_cap = Some(Decimal::ZERO); break; }
+            };
+            let bid_px = o.best_bid_px.unwrap_or(Decimal::ZERO);
+
+            // sane_book bounds every price, so overflow here means corrupt input
+            sum_ask = sum_ask.checked_add(ask_px).ok_or(SkipReason::Anomaly)?;
+            sum_bid = sum_bid.checked_add(bid_px).ok_or(SkipReason::Anomaly)?;
+            buy_cap = Some(match buy_cap { None => ask_sz, Some(mm) => mm.min(ask_sz) });
+        }
+
+        let mut buy_cap = buy_cap.unwrap_or(Decimal::ZERO);
+        if let Some(n) = m.shared_by.filter(|n| *n > 1) {
+            // other markets quote the same book, so this one only counts on its share
+            buy_cap /= Decimal::from(n as u64);
+        }
+        let mut buy_cap = buy_cap.min(self.max_bundle_shares);
+        if let Some(notional) = self.max_bundle_notional {
+            if sum_ask > Decimal::ZERO {
+                buy_cap = buy_cap.min(notional / sum_ask);
+            }
+        }
+        if let Some(cash_cap) = self.usable_collateral().and_then(|c| self.collateral_cap(c, sum_ask, fee)) {
+            buy_cap = buy_cap.min(cash_cap);
+        }
+        if buy_cap <= Decimal::ZERO { return Err(SkipReason::NoSize); }
+        let buy_cap = self.rounded_size(m, buy_cap)?;
+
+        let econ = self.economics(m, buy_cap).ok_or(SkipReason::NoSize)?;
+        let buffered_profit = econ.net_profit - econ.payout * Self::bps(self.slippage_buffer_bps);
+        Ok(BundleQuote {
+            sum_ask,
+            sum_bid,
+            size: buy_cap,
+            edge_bps: econ.edge_bps.round_dp(1),
+            payoff: m.payoff(),
+            buffered_profit,
+            vol_bps: None,
+            min_edge_bps: Decimal::from(self.min_edge_for(m)),
+            econ,
+        })
+    }
+
+    /// Bundle economics for `m` at `size` shares per leg, each leg at its average fill
+    /// price under the pricing model. None when some leg cannot fill that size.
+    fn economics(&self, m: &MarketBook, size: Decimal) -> Option<BundleEconomics> {
+        let fee_bps = self.fees.fee_bps();
+        let legs: Vec<LegCost> = m.outcomes.iter()
+            .map(|o| Some(LegCost { price: self.pricing.leg_cost(o, size)? / size, size, fee_bps }))
+            .collect::<Option<_>>()?;
+        let exit_fee_bps = match self.exit_mode {
+            ExitMode::Redeem => 0,
+            ExitMode::SellBack => fee_bps,
+        };
+        BundleEconomics::compute(&legs, self.bundle_fixed_cost, m.payoff(), exit_fee_bps)
+    }
+
+    /// `size` rounded down to the venue's precision, or why the bundle cannot be sent at it.
+    fn rounded_size(&self, m: &MarketBook, size: Decimal) -> Result<Decimal, SkipReason> {
+        let size = self.size_rounding.round(size);
+        if size <= Decimal::ZERO {
+            return Err(SkipReason::RoundedToZero);
+        }
+        let min = m.min_order_size.into_iter().chain(self.min_leg_size).max();
+        if min.is_some_and(|min| size < min) {
+            return Err(SkipReason::BelowMinOrder);
+        }
+        Ok(size)
+    }
+
+    /// `q` re-priced at a smaller `size`, e.g. to fit the remaining cycle budget. None when
+    /// the smaller bundle no longer clears the execute threshold (the fixed cost weighs
+    /// more on fewer shares).
+    pub fn resize(&self, m: &MarketBook, q: &BundleQuote, size: Decimal) -> Option<BundleQuote> {
+        if size <= Decimal::ZERO {
+            return None;
+        }
+        let size = self.rounded_size(m, size).ok()?;
+        let econ = self.economics(m, size)?;
+        let resized = BundleQuote {
+            size,
+            edge_bps: econ.edge_bps.round_dp(1),
+            buffered_profit: econ.net_profit - econ.payout * Self::bps(self.slippage_buffer_bps),
+            econ,
+            ..q.clone()
+        };
+        self.is_executable(&resized).then_some(resized)
+    }
+
+    /// Raises `q.min_edge_bps` by k * the market's volatility score when every leg has
+    /// `vol_min_samples` observations in the lookback; otherwise leaves the static threshold.
+    pub fn scale_for_volatility(&self, m: &MarketBook, q: &mut BundleQuote, history: &PriceHistory, now_ms: i64) {
+        let Some(k) = self.vol_edge_k else { return };
+        let mut vol = Decimal::ZERO;
+        for o in &m.outcomes {
+            match history.get(&o.token_id).and_then(|h| h.max_move(self.vol_lookback_ms, now_ms)) {
+                Some((mv, n)) if n >= self.vol_min_samples => vol = vol.max(mv),
+                _ => return,
+            }
+        }
+        let vol_bps = vol * dec!(10000);
+        q.vol_bps = Some(vol_bps);
+        q.min_edge_bps = Decimal::from(self.min_edge_for(m)) + k * vol_bps;
+    }
+
+    /// True when some leg's venue-side book update is older than max_book_update_age_ms at
+    /// `now_ms`, or has no timestamp and unknown ages count as stale.
+    fn book_stale(&self, m: &MarketBook, now_ms: i64) -> bool {
+        let Some(max_age) = self.max_book_update_age_ms else { return false };
+        m.outcomes.iter().any(|o| match o.book_ts_ms {
+            Some(ts) => now_ms - ts > max_age,
+            None => self.unknown_book_age_stale,
+        })
+    }
+
+    /// True when some leg's mid (its ask when one-sided) sits further than
+    /// max_reference_deviation_bps from the feed's price for that token. Legs the feed has
+    /// no price for are not checked.
+    fn deviates_from_reference(&self, m: &MarketBook, feed: Option<&dyn ReferenceFeed>) -> bool {
+        let (Some(max_bps), Some(feed)) = (self.max_reference_deviation_bps, feed) else { return false };
+        m.outcomes.iter().any(|o| {
+            let reference = feed.reference_px(&o.token_id).filter(|r| *r > Decimal::ZERO);
+            let (Some(reference), Some(px)) = (reference, o.mid().or(o.best_ask_px)) else {
+                return false;
+            };
+            let dev_bps = (px - reference).abs() / reference * dec!(10000);
+            let deviates = dev_bps > Decimal::from(max_bps);
+            if deviates {
+                tracing::debug!(
+                    market_id = %m.market_id,
+                    token_id = %o.token_id,
+                    px = %px,
+                    reference_px = %reference,
+                    deviation_bps = %dev_bps.round_dp(1),
+                    "leg deviates from reference price, skipping market"
+                );
+            }
+            deviates
+        })
+    }
+
+    /// Folds `q.sum_ask` into the market's EMA and returns how far it sat from the previous
+    /// EMA in bps of that EMA when beyond `sum_ask_ema_band_bps`. The first sighting only
+    /// seeds the EMA.
+    fn ema_deviation(&self, emas: &mut HashMap<String, Decimal>, m: &MarketBook, q: &BundleQuote) -> Option<Decimal> {
+        let alpha = self.sum_ask_ema_alpha?;
+        let Some(ema) = emas.get(&m.market_id).copied() else {
+            emas.insert(m.market_id.clone(), q.sum_ask);
+            return None;
+        };
+        emas.insert(m.market_id.clone(), ema + alpha * (q.sum_ask - ema));
+        if ema <= Decimal::ZERO {
+            return None;
+        }
+        let dev_bps = (q.sum_ask - ema) / ema * dec!(10000);
+        (dev_bps.abs() > Decimal::from(self.sum_ask_ema_band_bps)).then_some(dev_bps)
+    }
+
+    /// What `trace` reports for one market: each leg's filter outcome ("ok" or the skip
+    /// reason), in leg order, then the decision ("opportunity" or the skip reason).
+    pub fn trace_reasons(&self, m: &MarketBook, res: &Result<BundleQuote, SkipReason>) -> Vec<&'static str> {
+        let mut reasons: Vec<&'static str> = m.outcomes.iter()
+            .map(|o| self.check_leg(o).err().map(|r| r.as_str()).unwrap_or("ok"))
+            .collect();
+        reasons.push(match res {
+            Err(reason) => reason.as_str(),
+            Ok(q) if self.is_executable(q) => "opportunity",
+            Ok(_) => SkipReason::BelowEdge.as_str(),
+        });
+        reasons
+    }
+
+    /// Logs every input and filter outcome for one market, for `trace_market_ids`.
+    fn trace(&self, m: &MarketBook, res: &Result<BundleQuote, SkipReason>) {
+        let reasons = self.trace_reasons(m, res);
+        let decision = reasons[m.outcomes.len()];
+        for (i, o) in m.outcomes.iter().enumerate() {
+            tracing::info!(
+                market_id = %m.market_id,
+                leg = i,
+                token_id = %o.token_id,
+                bid = ?o.best_bid_px,
+                bid_sz = ?o.best_bid_sz,
+                ask = ?o.best_ask_px,
+                ask_sz = ?o.best_ask_sz,
+                mid = ?o.mid(),
+                spread = ?o.spread(),
+                spread_bps = ?o.spread_bps().map(|b| b.round_dp(1)),
+                max_leg_spread = ?self.max_leg_spread,
+                max_leg_spread_pct = ?self.max_leg_spread_pct,
+                min_leg_size = ?self.min_leg_size,
+                check = reasons[i],
+                "trace: leg"
+            );
+        }
+        match res {
+            Ok(q) => tracing::info!(
+                market_id = %m.market_id,
+                polarity = ?m.polarity,
+                sum_ask = %q.sum_ask,
+                sum_bid = %q.sum_bid,
+                payoff = %q.payoff,
+                size = %q.size,
+                pricing = self.pricing.name(),
+                gross_cost = %q.econ.gross_cost.round_dp(4),
+                fee_bps = self.fees.fee_bps(),
+                net_edge_bps = %q.econ.edge_bps.round_dp(2),
+                min_edge_bps = %q.min_edge_bps.round_dp(1),
+                vol_bps = ?q.vol_bps.map(|v| v.round_dp(1)),
+                slippage_buffer_bps = self.slippage_buffer_bps,
+                near_arb = self.is_near_arb(q),
+                decision,
+                "trace: decision"
+            ),
+            Err(reason) => tracing::info!(market_id = %m.market_id, reason = %reason, decision, "trace: decision"),
+        }
+    }
+
+    /// Quotes every market, in input order. Quoting is pure, so it runs in parallel for
+    /// large snapshots; everything order-sensitive (logs, counters, ranking) stays in the
+    /// serial pass of `on_snapshot`.
+    pub fn quote_all(&self, markets: &[MarketBook]) -> Vec<Result<BundleQuote, SkipReason>> {
+        if self.parallel_min_markets > 0 && markets.len() >= self.parallel_min_markets {
+            markets.par_iter().map(|m| self.quote(m)).collect()
+        } else {
+            markets.iter().map(|m| self.quote(m)).collect()
+        }
+    }
+
+    pub fn is_near_arb(&self, q: &BundleQuote) -> bool {
+        q.sum_ask < q.payoff * (dec!(1) + Self::bps(self.warn_edge_bps))
+    }
+
+    /// Execute threshold: net edge (after leg fees and the fixed bundle cost) above
+    /// min_edge + slippage_buffer, where min_edge is the quote's (possibly
+    /// volatility-scaled) threshold. With no fixed cost and redeem exit this is
+    /// sum_ask * (1 + fee) < payoff * (1 - min_edge - slippage_buffer), where the payoff is
+    /// the market's redemption value when set, else 1 for YES bundles and n - 1 for NO
+    /// bundles. Near-arb checks use the raw edge.
+    pub fn is_executable(&self, q: &BundleQuote) -> bool {
+        q.econ.edge_bps > q.min_edge_bps + Decimal::from(self.slippage_buffer_bps)
+    }
+}
+
+/// Parses `"2:30,3:60,4:100"` (leg count : min edge bps).
+pub fn parse_edge_by_legs(s: &str) -> anyhow::Result<HashMap<usize, i64>> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|x| !x.is_empty())
+        .map(|x| {
+            let (legs, bps) = x.split_once(':').with_context(|| format!("edge by legs {:?}: expected legs:bps", x))?;
+            Ok((
+                legs.trim().parse().with_context(|| format!("edge by legs {:?}: bad leg count", x))?,
+                bps.trim().parse().with_context(|| format!("edge by legs {:?}: bad bps", x))?,
+            ))
+        })
+        .collect()
+}
+
+/// Collateral a bundle commits: leg cost, taker fees and the fixed cost. Exit fees come
+/// out of the payout, not collateral.
+fn cash_cost(q: &BundleQuote) -> Decimal {
+    q.econ.leg_cost + q.econ.fees + q.econ.fixed_cost
+}
+
+/// Age of the least recently updated leg book at `now_ms`, over legs with a venue
+/// timestamp; None when no leg has one.
+fn oldest_book_age_ms(m: &MarketBook, now_ms: i64) -> Option<i64> {
+    m.outcomes.iter().filter_map(|o| o.book_ts_ms).min().map(|ts| (now_ms - ts).max(0))
+}
+
+/// One value per leg, comma-separated in leg order, "-" where it is None.
+fn leg_list(m: &MarketBook, f: impl Fn(&OutcomeTop) -> Option<Decimal>) -> String {
+    m.outcomes.iter()
+        .map(|o| f(o).map(|v| v.normalize().to_string()).unwrap_or_else(|| "-".to_string()))
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// The per-leg breakdown of an opportunity: each ask and its share of sum_ask, so the
+/// bundle math can be checked from the logs.
+fn log_legs(m: &MarketBook, q: &BundleQuote, bundle_id: Uuid) {
+    for (i, o) in m.outcomes.iter().enumerate() {
+        let ask = o.best_ask_px.unwrap_or_default();
+        let share_pct = if q.sum_ask > Decimal::ZERO { ask / q.sum_ask * dec!(100) } else { Decimal::ZERO };
+        tracing::info!(
+            %bundle_id,
+            market_id = %m.market_id,
+            leg = i,
+            token_id = %o.token_id,
+            ask_px = %ask,
+            ask_size = %o.best_ask_sz.unwrap_or_default(),
+            bid_px = %o.best_bid_px.map(|b| b.to_string()).unwrap_or_else(|| "-".to_string()),
+            contribution = %ask,
+            share_pct = %share_pct.round_dp(2),
+            "opportunity leg"
+        );
+    }
+}
+
+/// Same market, leg prices and size give the same id, so a bundle re-detected after a
+/// restart can be recognised as already executed.
+fn bundle_id_for(m: &MarketBook, q: &BundleQuote) -> Uuid {
+    let mut key = format!("{}|{}", m.market_id, q.size.normalize());
+    for o in &m.outcomes {
+        key.push_str(&format!("|{}@{}", o.token_id, o.best_ask_px.unwrap_or_default().normalize()));
+    }
+    Uuid::new_v5(&Uuid::NAMESPACE_OID, key.as_bytes())
+}
+
+/// Bounds checked before summing: leg count, asks in (0, 1], bids in [0, 1].
+fn sane_book(m: &MarketBook) -> Result<(), &'static str> {
+    if m.outcomes.len() > MAX_BUNDLE_LEGS {
+        return Err("too many legs");
+    }
+    for o in &m.outcomes {
+        if let Some(a) = o.best_ask_px {
+            if a <= Decimal::ZERO || a > Decimal::ONE { return Err("ask outside (0, 1]"); }
+        }
+        if let Some(b) = o.best_bid_px {
+            if b < Decimal::ZERO || b > Decimal::ONE { return Err("bid outside [0, 1]"); }
+        }
+        if o.best_ask_sz.map(|s| s < Decimal::ZERO).unwrap_or(false)
+            || o.best_bid_sz.map(|s| s < Decimal::ZERO).unwrap_or(false)
+        {
+            return Err("negative size");
+        }
+    }
+    Ok(())
 }
 
 impl Strategy for SumArbStrategy {
-    fn on_snapshot(&self, snap: &GlobalSnapshot) -> Vec<OrderIntent> {
+    fn on_snapshot(&self, snap: &GlobalSnapshot, ctx: &StrategyContext<'_>) -> (Vec<OrderIntent>, CycleSummary) {
         let mut out: Vec<OrderIntent> = vec![];
-        let fee = Self::bps(self.fee_bps);
-        let min_edge = Self::bps(self.min_edge_bps);
-        let warn_edge = Self::bps(self.warn_edge_bps);
-
-        for m in &snap.markets {
-            if m.outcomes.is_empty() { continue; }
-
-            // Per-leg filters
-            let mut ok = true;
-            for o in &m.outcomes {
-                let (ask, bid) = match (o.best_ask_px, o.best_bid_px) {
-                    (Some(a), Some(b)) => (a, b),
-                    _ => { ok = false; break; }
-                };
-
-                if let Some(max_spread) = self.max_leg_spread {
-                    if ask - bid > max_spread { ok = false; break; }
+        let mut summary = CycleSummary::default();
+        let mut candidates: Vec<(&MarketBook, BundleQuote)> = vec![];
+        let age_ms = (chrono::Utc::now().timestamp_millis() - snap.ts_ms).max(0) as u64;
+        summary.snapshot_age_ms = age_ms;
+        self.stats.record_snapshot_age(age_ms);
+
+        let quotes = self.quote_all(&snap.markets);
+        let mut emas = self.sum_ask_ema.lock().unwrap_or_else(|e| e.into_inner());
+        for (m, mut res) in snap.markets.iter().zip(quotes) {
+            summary.markets_evaluated += 1;
+            if ctx.meta.map(|c| c.is_resolved(&m.market_id)).unwrap_or(false) {
+                res = Err(SkipReason::Resolved);
+            }
+            if res.is_ok() && self.book_stale(m, snap.ts_ms) {
+                res = Err(SkipReason::StaleBook);
+            }
+            if res.is_ok() && self.deviates_from_reference(m, ctx.reference) {
+                res = Err(SkipReason::ReferenceDeviation);
+            }
+            if let Ok(q) = &mut res {
+                self.scale_for_volatility(m, q, ctx.history, snap.ts_ms);
+            }
+            if let Ok(q) = &res {
+                if let Some(dev_bps) = self.ema_deviation(&mut emas, m, q) {
+                    if self.is_executable(q) {
+                        tracing::warn!(
+                            market_id = %m.market_id,
+                            question = %m.question,
+                            sum_ask = %q.sum_ask,
+                            deviation_bps = %dev_bps.round_dp(1),
+                            band_bps = self.sum_ask_ema_band_bps,
+                            skipped = self.sum_ask_ema_guard,
+                            "suspected glitch: sum_ask far from its EMA"
+                        );
+                        if self.sum_ask_ema_guard {
+                            res = Err(SkipReason::SuspectedGlitch);
+                        }
+                    }
                 }
-                if let Some(min_sz) = self.min_leg_size {
-                    let a_sz = o.best_ask_sz.unwrap_or(Decimal::ZERO);
-                    let b_sz = o.best_bid_sz.unwrap_or(Decimal::ZERO);
-                    if a_sz < min_sz || b_sz < min_sz { ok = false; break; }
+            }
+            if let (Ok(q), Some(floor)) = (&res, self.min_plausible_sum_ask) {
+                let payoff = m.payoff();
+                if self.is_executable(q) && payoff > Decimal::ZERO && q.sum_ask / payoff < floor {
+                    tracing::warn!(
+                        market_id = %m.market_id,
+                        question = %m.question,
+                        sum_ask = %q.sum_ask,
+                        payoff = %payoff,
+                        min_plausible_sum_ask = %floor,
+                        legs = m.outcomes.len(),
+                        skipped = !self.trust_extreme_arbs,
+                        "implausible arb: sum_ask too low to be real, likely missing legs or bad data"
+                    );
+                    if !self.trust_extreme_arbs {
+                        res = Err(SkipReason::ImplausibleSumAsk);
+                    }
                 }
             }
-            if !ok { continue; }
-
-            // sum_ask, sum_bid, bundle size cap
-            let mut sum_ask = dec!(0);
-            let mut sum_bid = dec!(0);
-            let mut buy_cap: Option<Decimal> = None;
-
-            for o in &m.outcomes {
-                let (ask_px, ask_sz) = match (o.best_ask_px, o.best_ask_sz) {
-                    (Some(px), Some(sz)) => (px, sz),
-                    _ => { buy_cap = Some(Decimal::ZERO); break; }
-                };
-                let bid_px = o.best_bid_px.unwrap_or(Decimal::ZERO);
-
-                sum_ask += ask_px;
-                sum_bid += bid_px;
-                buy_cap = Some(match buy_cap { None => ask_sz, Some(mm) => mm.min(ask_sz) });
+            let traced = self.trace_market_ids.contains(&m.market_id);
+            if traced {
+                self.trace(m, &res);
             }
-
-            let buy_cap = buy_cap.unwrap_or(Decimal::ZERO).min(self.max_bundle_size);
-            if buy_cap <= Decimal::ZERO { continue; }
+            let q = match res {
+                Ok(q) => q,
+                Err(reason) => { summary.record_skip(reason); continue; }
+            };
+            summary.observe_edge(&m.market_id, &m.question, m.event_id.as_deref(), q.sum_ask, q.edge_bps);
 
             // Near-arb warning
-            if sum_ask < dec!(1) + warn_edge {
+            let near_arb = self.is_near_arb(&q);
+            let opportunity = self.is_executable(&q);
+            if near_arb || opportunity {
+                summary.hits.push(MarketHit {
+                    market_id: m.market_id.clone(),
+                    question: m.question.clone(),
+                    near_arb,
+                    opportunity,
+                    edge_bps: q.econ.edge_bps,
+                    sum_ask: q.sum_ask,
+                });
+            }
+            if near_arb {
                 self.stats.inc_near_arb();
+                self.stats.inc_category_near_arb(m.category.as_deref());
+                summary.near_arb_hits += 1;
                 tracing::warn!(
                     market_id = %m.market_id,
                     question = %m.question,
-                    sum_ask = %sum_ask,
-                    sum_bid = %sum_bid,
-                    spread = %(sum_ask - sum_bid),
-                    size = %buy_cap,
+                    sum_ask = %q.sum_ask,
+                    sum_bid = %q.sum_bid,
+                    spread = %(q.sum_ask - q.sum_bid),
+                    size = %q.size,
                     legs = m.outcomes.len(),
+                    min_edge_bps = %q.min_edge_bps.round_dp(1),
+                    vol_bps = ?q.vol_bps.map(|v| v.round_dp(1)),
+                    leg_mids = %leg_list(m, |o| o.mid()),
+                    leg_spreads = %leg_list(m, |o| o.spread()),
+                    leg_spread_bps = %leg_list(m, |o| o.spread_bps().map(|b| b.round_dp(1))),
+                    oldest_book_age_ms = ?oldest_book_age_ms(m, snap.ts_ms),
+                    snapshot_age_ms = age_ms,
                     "near-arb: bundle pricing close to 1"
                 );
             }
 
             // Execute threshold
-            if !(sum_ask * (dec!(1) + fee) < dec!(1) - min_edge) {
+            if !opportunity {
+                summary.record_skip(SkipReason::BelowEdge);
                 continue;
             }
 
             self.stats.inc_opportunity();
+            self.stats.inc_category_opportunity(m.category.as_deref());
+            summary.opportunities += 1;
+            candidates.push((m, q));
+        }
+
+        // markets gone from the snapshot for good would otherwise accumulate
+        if emas.len() > 2 * snap.markets.len() {
+            let live: std::collections::HashSet<&str> = snap.markets.iter().map(|m| m.market_id.as_str()).collect();
+            emas.retain(|id, _| live.contains(id.as_str()));
+        }
+        drop(emas);
 
-            let bundle_id = Uuid::new_v4();
+        // rank by expected profit (edge * size) and keep the best max_bundles_per_snapshot
+        candidates.sort_by_key(|(_, q)| std::cmp::Reverse(q.econ.net_profit));
+        if self.max_bundles_per_snapshot > 0 && candidates.len() > self.max_bundles_per_snapshot {
+            let dropped = candidates.split_off(self.max_bundles_per_snapshot);
+            summary.capped = dropped.len() as u64;
+            tracing::info!(
+                kept = candidates.len(),
+                dropped = dropped.len(),
+                min_kept_profit = %candidates.last().map(|(_, q)| q.econ.net_profit.round_dp(4)).unwrap_or_default(),
+                "max_bundles_per_snapshot reached, lowest-profit opportunities dropped"
+            );
+        }
+
+        // notional and collateral are shared by every bundle of the cycle, best first
+        let mut budget = self.max_cycle_notional;
+        let mut cash = self.usable_collateral();
+        let fee = Self::bps(self.fees.fee_bps());
+        for (m, mut q) in candidates {
+            if let Some(left) = budget.as_mut() {
+                let notional = q.size * q.sum_ask;
+                if notional > *left {
+                    let trimmed = if q.sum_ask > Decimal::ZERO { self.resize(m, &q, *left / q.sum_ask) } else { None };
+                    match trimmed {
+                        Some(t) => {
+                            tracing::info!(
+                                market_id = %m.market_id,
+                                size = %q.size,
+                                trimmed_size = %t.size,
+                                budget_left = %left.round_dp(4),
+                                "max_cycle_notional reached, bundle trimmed"
+                            );
+                            summary.budget_trimmed += 1;
+                            q = t;
+                        }
+                        None => {
+                            tracing::info!(
+                                market_id = %m.market_id,
+                                notional = %notional.round_dp(4),
+                                budget_left = %left.round_dp(4),
+                                "max_cycle_notional reached, opportunity not emitted"
+                            );
+                            summary.budget_dropped += 1;
+                            continue;
+                        }
+                    }
+                }
+            }
+            if let Some(left) = cash {
+                if cash_cost(&q) > left {
+                    let trimmed = self.collateral_cap(left, q.sum_ask, fee)
+                        .and_then(|size| self.resize(m, &q, size))
+                        .filter(|t| cash_cost(t) <= left);
+                    match trimmed {
+                        Some(t) => {
+                            tracing::info!(
+                                market_id = %m.market_id,
+                                size = %q.size,
+                                trimmed_size = %t.size,
+                                collateral_left = %left.round_dp(4),
+                                "available collateral committed, bundle trimmed"
+                            );
+                            summary.collateral_trimmed += 1;
+                            q = t;
+                        }
+                        None => {
+                            tracing::info!(
+                                market_id = %m.market_id,
+                                cost = %cash_cost(&q).round_dp(4),
+                                collateral_left = %left.round_dp(4),
+                                "available collateral committed, opportunity not emitted"
+                            );
+                            summary.collateral_dropped += 1;
+                            continue;
+                        }
+                    }
+                }
+            }
+            if let Some(left) = budget.as_mut() {
+                *left -= q.size * q.sum_ask;
+            }
+            if let Some(left) = cash.as_mut() {
+                *left -= cash_cost(&q);
+            }
+            let bundle_id = bundle_id_for(m, &q);
             tracing::info!(
                 market_id = %m.market_id,
                 question = %m.question,
-                sum_ask = %sum_ask,
-                size = %buy_cap,
+                sum_ask = %q.sum_ask,
+                size = %q.size,
                 legs = m.outcomes.len(),
+                polarity = ?m.polarity,
+                gross_cost = %q.econ.gross_cost.round_dp(4),
+                net_profit = %q.econ.net_profit.round_dp(4),
+                buffered_profit = %q.buffered_profit.round_dp(4),
+                edge_bps = %q.edge_bps,
+                min_edge_bps = %q.min_edge_bps.round_dp(1),
+                vol_bps = ?q.vol_bps.map(|v| v.round_dp(1)),
+                breakeven_size = %q.econ.breakeven_size.map(|v| v.round_dp(4).to_string()).unwrap_or_default(),
+                leg_mids = %leg_list(m, |o| o.mid()),
+                leg_spreads = %leg_list(m, |o| o.spread()),
+                leg_spread_bps = %leg_list(m, |o| o.spread_bps().map(|b| b.round_dp(1))),
+                oldest_book_age_ms = ?oldest_book_age_ms(m, snap.ts_ms),
+                snapshot_age_ms = age_ms,
                 "opportunity: BUY_BUNDLE"
             );
+            if self.log_leg_detail {
+                log_legs(m, &q, bundle_id);
+            }
 
-            for o in &m.outcomes {
-                if let Some(px) = o.best_ask_px {
+            for (leg_index, o) in m.outcomes.iter().enumerate() {
+                if let Some(px) = self.pricing.leg_limit(o, q.size) {
+                    let meta = IntentMeta {
+                        strategy: STRATEGY_NAME.to_string(),
+                        edge_bps: q.edge_bps,
+                        sum_ask: q.sum_ask,
+                        sum_bid: q.sum_bid,
+                        net_profit: q.econ.net_profit,
+                        snapshot_ts_ms: snap.ts_ms,
+                        leg_index,
+                        leg_count: m.outcomes.len(),
+                    };
                     out.push(OrderIntent {
                         market_id: m.market_id.clone(),
                         token_id: o.token_id.clone(),
                         side: Side::Buy,
                         price: px,
-                        size: buy_cap,
-                        reason: format!("BUY_BUNDLE sum_ask={} size={}", sum_ask, buy_cap),
+                        size: q.size,
+                        reason: meta.summary(q.size),
                         bundle_id,
+                        tif: self.leg_tif,
+                        post_only: self.leg_post_only,
+                        avail_size: Some(self.pricing.leg_capacity(o)),
+                        meta,
                     });
                 }
             }
         }
 
         self.stats.add_intents(out.len() as u64);
-        out
+        for reason in SkipReason::ALL {
+            self.stats.add_skipped(reason.as_str(), summary.skipped(reason));
+        }
+        summary.intents = out.len() as u64;
+        (out, summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::strategy::fees::FeeTier;
+    use crate::testutil::snapshot_builder::{outcome, SnapshotBuilder};
+    use crate::testutil::strategy::{run, sum_arb};
+    use crate::types::TokenId;
+
+    /// Bundle sizes of `intents`, one per bundle, in emission order.
+    fn bundle_sizes(intents: &[OrderIntent]) -> Vec<Decimal> {
+        let mut seen = std::collections::HashSet::new();
+        intents.iter().filter(|i| seen.insert(i.bundle_id)).map(|i| i.size).collect()
+    }
+
+    fn market(asks: &[Decimal], size: Decimal) -> MarketBook {
+        let mut m = SnapshotBuilder::new().legs(asks.len()).leg_size(size).market(0);
+        for (o, a) in m.outcomes.iter_mut().zip(asks) {
+            o.best_ask_px = Some(*a);
+            o.best_bid_px = Some((*a - dec!(0.01)).max(Decimal::ZERO));
+        }
+        m
+    }
+
+    /// A snapshot of two-leg markets `mkt-k` priced to each sum in `sums`.
+    fn snapshot(sums: &[Decimal], size: Decimal) -> GlobalSnapshot {
+        let mut snap = SnapshotBuilder::new().markets(0).build();
+        for (k, sum) in sums.iter().enumerate() {
+            let mut m = market(&[*sum / dec!(2), *sum / dec!(2)], size);
+            m.market_id = format!("mkt-{}", k);
+            snap.markets.push(m);
+        }
+        snap
+    }
+
+    fn bundle_markets(intents: &[OrderIntent]) -> Vec<&str> {
+        let mut seen = std::collections::HashSet::new();
+        intents.iter().filter(|i| seen.insert(i.bundle_id)).map(|i| i.market_id.as_str()).collect()
+    }
+
+    #[test]
+    fn snapshot_cap_keeps_the_most_profitable_bundles() {
+        let mut s = sum_arb();
+        s.max_bundles_per_snapshot = 2;
+        // profits 10, 5, 15, 3 and 7 on 100 shares
+        let snap = snapshot(&[dec!(0.90), dec!(0.95), dec!(0.85), dec!(0.97), dec!(0.93)], dec!(100));
+        let (intents, summary) = run(&s, &snap);
+        assert_eq!(bundle_markets(&intents), ["mkt-2", "mkt-0"]);
+        assert_eq!((summary.opportunities, summary.capped), (5, 3));
+    }
+
+    #[test]
+    fn snapshot_cap_ranks_by_profit_not_edge() {
+        let mut s = sum_arb();
+        s.max_bundles_per_snapshot = 1;
+        // the deeper edge on 10 shares makes 1.5, the shallower one on 100 makes 5
+        let mut snap = snapshot(&[dec!(0.85), dec!(0.95)], dec!(100));
+        for o in &mut snap.markets[0].outcomes {
+            o.best_ask_sz = Some(dec!(10));
+        }
+        let (intents, _) = run(&s, &snap);
+        assert_eq!(bundle_markets(&intents), ["mkt-1"]);
+    }
+
+    fn no_basket(asks: &[Decimal], size: Decimal) -> MarketBook {
+        let mut m = market(asks, size);
+        m.polarity = Polarity::No;
+        m
+    }
+
+    #[test]
+    fn binary_no_basket_pays_one() {
+        let s = sum_arb();
+        let q = s.quote(&no_basket(&[dec!(0.48), dec!(0.49)], dec!(100))).unwrap();
+        assert_eq!((q.payoff, q.sum_ask), (dec!(1), dec!(0.97)));
+        assert_eq!((q.econ.payout, q.econ.net_profit), (dec!(100), dec!(3)));
+        assert_eq!(q.econ.edge_bps, dec!(300));
+        assert!(s.is_executable(&q));
+    }
+
+    #[test]
+    fn five_way_no_basket_pays_four() {
+        let s = sum_arb();
+        let q = s.quote(&no_basket(&[dec!(0.78); 5], dec!(100))).unwrap();
+        // 3.90 against a payoff of 4: 0.10 a set, 25 bps of the payout
+        assert_eq!((q.payoff, q.sum_ask), (dec!(4), dec!(3.90)));
+        assert_eq!((q.econ.payout, q.econ.net_profit), (dec!(400), dec!(10)));
+        assert_eq!(q.econ.edge_bps, dec!(250));
+        assert!(s.is_executable(&q));
+
+        // the same asks read as YES legs would be nowhere near an arb
+        let yes = s.quote(&market(&[dec!(0.78); 5], dec!(100))).unwrap();
+        assert!(!s.is_executable(&yes));
+    }
+
+    #[test]
+    fn no_basket_edge_is_measured_against_its_payoff() {
+        let s = sum_arb();
+        // 3.99 of 4 is 25 bps, under the 30 bps threshold
+        let q = s.quote(&no_basket(&[dec!(0.798); 5], dec!(100))).unwrap();
+        assert_eq!(q.econ.edge_bps, dec!(25));
+        assert!(!s.is_executable(&q));
+        assert!(s.is_near_arb(&q));
+    }
+
+    #[test]
+    fn single_no_leg_is_too_few() {
+        let mut s = sum_arb();
+        s.min_legs = 1;
+        assert_eq!(s.quote(&no_basket(&[dec!(0.5)], dec!(10))).unwrap_err(), SkipReason::TooFewLegs);
+    }
+
+    #[test]
+    fn trace_reports_each_leg_then_the_decision() {
+        let mut s = sum_arb();
+        s.max_leg_spread = Some(dec!(0.03));
+        let mut m = market(&[dec!(0.45), dec!(0.47), dec!(0.05)], dec!(100));
+        // the second leg quotes 0.42 / 0.47: 0.05 wide
+        m.outcomes[1].best_bid_px = Some(dec!(0.42));
+        // the third has no bid at all
+        m.outcomes[2].best_bid_px = None;
+        let res = s.quote(&m);
+        assert_eq!(s.trace_reasons(&m, &res), ["ok", "leg_spread", "missing_quote", "leg_spread"]);
+
+        s.trace_market_ids.insert(m.market_id.clone());
+        let snap = GlobalSnapshot { ts_ms: 0, markets: vec![m], coverage: 1.0 };
+        let (intents, summary) = run(&s, &snap);
+        assert!(intents.is_empty());
+        assert_eq!(summary.skipped_leg_spread, 1);
+    }
+
+    #[test]
+    fn trace_decision_for_quoted_markets() {
+        let s = sum_arb();
+        let arb = market(&[dec!(0.45), dec!(0.47)], dec!(100));
+        assert_eq!(s.trace_reasons(&arb, &s.quote(&arb)), ["ok", "ok", "opportunity"]);
+        let fair = market(&[dec!(0.50), dec!(0.51)], dec!(100));
+        assert_eq!(s.trace_reasons(&fair, &s.quote(&fair)), ["ok", "ok", "below_edge"]);
+    }
+
+    #[test]
+    fn parallel_quoting_matches_the_serial_path() {
+        let mut snap = SnapshotBuilder::new().markets(2_000).legs(3).near_arb_fraction(0.1).build();
+        // vary sizes so ranking matters, and knock out some quotes so skips are mixed in
+        for (k, m) in snap.markets.iter_mut().enumerate() {
+            m.outcomes[k % 3].best_ask_sz = Some(Decimal::from(10 + k % 97));
+            if k % 13 == 0 {
+                m.outcomes[0].best_bid_px = None;
+            }
+        }
+        let mut serial = sum_arb();
+        serial.max_bundles_per_snapshot = 50;
+        let mut parallel = serial.clone();
+        parallel.parallel_min_markets = 1;
+
+        let (serial_intents, serial_summary) = run(&serial, &snap);
+        let (parallel_intents, mut parallel_summary) = run(&parallel, &snap);
+        // measured against the wall clock
+        parallel_summary.snapshot_age_ms = serial_summary.snapshot_age_ms;
+        assert_eq!(serial_intents.len(), 50 * 3);
+        assert_eq!(serde_json::to_value(&parallel_intents).unwrap(), serde_json::to_value(&serial_intents).unwrap());
+        assert_eq!(serde_json::to_value(&parallel_summary).unwrap(), serde_json::to_value(&serial_summary).unwrap());
+    }
+
+    #[test]
+    fn a_lower_fee_tier_admits_a_rejected_opportunity() {
+        let mut s = sum_arb();
+        let fees = Arc::new(FeeModel::tiered(100, vec![FeeTier { min_volume: dec!(10_000), fee_bps: 20 }], Decimal::ZERO, 0));
+        s.fees = fees.clone();
+        let m = market(&[dec!(0.49), dec!(0.50)], dec!(100));
+
+        // 0.99 plus 1% in fees leaves about 1 bp
+        let q = s.quote(&m).unwrap();
+        assert!(!s.is_executable(&q), "edge {}", q.econ.edge_bps);
+
+        fees.record_fill(dec!(10_000), 1);
+        let q = s.quote(&m).unwrap();
+        assert!(s.is_executable(&q), "edge {}", q.econ.edge_bps);
+        let (intents, summary) = run(&s, &GlobalSnapshot { ts_ms: 0, markets: vec![m], coverage: 1.0 });
+        assert_eq!((intents.len(), summary.opportunities), (2, 1));
+    }
+
+    /// Two even legs summing to `sum_ask`, 100 shares.
+    fn pair(sum_ask: Decimal) -> MarketBook {
+        market(&[sum_ask / dec!(2), sum_ask / dec!(2)], dec!(100))
+    }
+
+    #[test]
+    fn slippage_buffer_adds_to_the_execute_threshold() {
+        let mut s = sum_arb();
+        s.slippage_buffer_bps = 20;
+        // 30 bps threshold + 20 bps buffer: 50 bps exactly is not enough
+        let at = s.quote(&pair(dec!(0.995))).unwrap();
+        assert_eq!(at.econ.edge_bps, dec!(50));
+        assert!(!s.is_executable(&at));
+        let over = s.quote(&pair(dec!(0.9948))).unwrap();
+        assert_eq!(over.econ.edge_bps, dec!(52));
+        assert!(s.is_executable(&over));
+        // without the buffer 50 bps clears 30
+        s.slippage_buffer_bps = 0;
+        assert!(s.is_executable(&at));
+    }
+
+    #[test]
+    fn slippage_buffer_is_net_of_fees() {
+        let mut s = sum_arb();
+        s.fees = Arc::new(FeeModel::flat(100));
+        // 0.98 * 1.01 = 0.9898: 102 bps after fees
+        let q = s.quote(&pair(dec!(0.98))).unwrap();
+        assert_eq!(q.econ.edge_bps, dec!(102));
+        s.slippage_buffer_bps = 71;
+        assert!(s.is_executable(&q));
+        s.slippage_buffer_bps = 72;
+        assert!(!s.is_executable(&q));
+    }
+
+    #[test]
+    fn slippage_buffer_leaves_monitoring_on_the_raw_edge() {
+        let mut s = sum_arb();
+        s.slippage_buffer_bps = 500;
+        let q = s.quote(&pair(dec!(0.99))).unwrap();
+        assert!(!s.is_executable(&q));
+        assert!(s.is_near_arb(&q));
+        assert_eq!(q.edge_bps, dec!(100));
+        // gross profit 1.00, less 5% of the 100 payout
+        assert_eq!(q.econ.net_profit, dec!(1.00));
+        assert_eq!(q.buffered_profit, dec!(-4.00));
+
+        let (_, summary) = run(&s, &GlobalSnapshot { ts_ms: 0, markets: vec![pair(dec!(0.99))], coverage: 1.0 });
+        assert_eq!((summary.near_arb_hits, summary.opportunities), (1, 0));
+        assert_eq!(summary.best.unwrap().edge_bps, dec!(100));
+    }
+
+    #[test]
+    fn snapshot_age_is_measured_from_its_fetch_time() {
+        let s = sum_arb();
+        let now = chrono::Utc::now().timestamp_millis();
+        let aged = |ms: i64| SnapshotBuilder::new().markets(1).ts_ms(now - ms).build();
+
+        let (_, summary) = run(&s, &aged(3_000));
+        // allow for the clock moving while the test runs
+        assert!((3_000..3_500).contains(&summary.snapshot_age_ms), "{}", summary.snapshot_age_ms);
+        run(&s, &aged(700));
+        // fetched "after" now: clock skew reads as zero, not a huge age
+        assert_eq!(run(&s, &aged(-60_000)).1.snapshot_age_ms, 0);
+
+        let stats = s.stats.snapshot(0);
+        assert!(stats.max_snapshot_age_ms >= 3_000);
+        assert_eq!(stats.snapshot_age_hist, [1, 0, 0, 1, 0, 1]);
+    }
+
+    #[test]
+    fn intents_carry_the_configured_time_in_force() {
+        let snap = GlobalSnapshot { ts_ms: 0, markets: vec![pair(dec!(0.96))], coverage: 1.0 };
+        let (intents, _) = run(&sum_arb(), &snap);
+        assert!(intents.iter().all(|i| i.tif == TimeInForce::Fok && !i.post_only));
+
+        let mut s = sum_arb();
+        s.leg_tif = TimeInForce::Gtc;
+        s.leg_post_only = true;
+        let (intents, _) = run(&s, &snap);
+        assert_eq!(intents.len(), 2);
+        assert!(intents.iter().all(|i| i.tif == TimeInForce::Gtc && i.post_only));
+    }
+
+    #[test]
+    fn post_only_needs_a_resting_order() {
+        let stats = Stats::new(0);
+        let build = |tif: &str| {
+            let s = crate::testutil::settings::settings(serde_json::json!({ "leg_tif": tif, "leg_post_only": true }));
+            SumArbStrategy::from_settings(&s, &s.parsed().unwrap(), Arc::new(FeeModel::flat(0)), stats.clone())
+        };
+        assert_eq!((build("IOC").leg_tif, build("IOC").leg_post_only), (TimeInForce::Ioc, false));
+        assert_eq!((build("GTC").leg_tif, build("GTC").leg_post_only), (TimeInForce::Gtc, true));
+    }
+
+    /// History of `m`'s tokens over `samples` snapshots 1s apart ending at `now_ms`, its
+    /// first leg's ask swinging by `swing` every other one.
+    fn swinging_history(m: &MarketBook, samples: i64, swing: Decimal, now_ms: i64) -> PriceHistory {
+        let mut history = PriceHistory::new(100, 10);
+        for k in 0..samples {
+            let mut past = m.clone();
+            if k % 2 == 1 {
+                past.outcomes[0].best_ask_px = past.outcomes[0].best_ask_px.map(|a| a + swing);
+            }
+            history.record(&GlobalSnapshot { ts_ms: now_ms - (samples - 1 - k) * 1_000, markets: vec![past], coverage: 1.0 });
+        }
+        history
+    }
+
+    fn executable_with(s: &SumArbStrategy, m: &MarketBook, history: &PriceHistory, now_ms: i64) -> (bool, BundleQuote) {
+        let mut q = s.quote(m).unwrap();
+        s.scale_for_volatility(m, &mut q, history, now_ms);
+        (s.is_executable(&q), q)
+    }
+
+    #[test]
+    fn volatile_legs_raise_the_threshold() {
+        let mut s = sum_arb();
+        s.vol_edge_k = Some(dec!(1));
+        // 100 bps of edge clears the static 30
+        let m = pair(dec!(0.99));
+        let calm = swinging_history(&m, 5, Decimal::ZERO, 10_000);
+        let (ok, q) = executable_with(&s, &m, &calm, 10_000);
+        assert_eq!((q.vol_bps, q.min_edge_bps), (Some(dec!(0)), dec!(30)));
+        assert!(ok);
+
+        // a 1c swing is 100 bps of volatility: 30 + 1 * 100 = 130 needed
+        let whipping = swinging_history(&m, 5, dec!(0.01), 10_000);
+        let (ok, q) = executable_with(&s, &m, &whipping, 10_000);
+        assert_eq!((q.vol_bps, q.min_edge_bps), (Some(dec!(100)), dec!(130)));
+        assert!(!ok);
+
+        s.vol_edge_k = Some(dec!(0.5));
+        let (ok, q) = executable_with(&s, &m, &whipping, 10_000);
+        assert_eq!(q.min_edge_bps, dec!(80));
+        assert!(ok);
+    }
+
+    #[test]
+    fn short_history_keeps_the_static_threshold() {
+        let mut s = sum_arb();
+        s.vol_edge_k = Some(dec!(1));
+        let m = pair(dec!(0.99));
+        // four samples, five needed
+        let (ok, q) = executable_with(&s, &m, &swinging_history(&m, 4, dec!(0.01), 10_000), 10_000);
+        assert_eq!((q.vol_bps, q.min_edge_bps), (None, dec!(30)));
+        assert!(ok);
+        // samples older than the lookback do not count
+        let (_, q) = executable_with(&s, &m, &swinging_history(&m, 5, dec!(0.01), 10_000), 100_000);
+        assert_eq!(q.vol_bps, None);
+    }
+
+    #[test]
+    fn scaling_applies_in_on_snapshot() {
+        let mut s = sum_arb();
+        s.vol_edge_k = Some(dec!(1));
+        let m = pair(dec!(0.99));
+        let history = swinging_history(&m, 5, dec!(0.01), 10_000);
+        let ctx = StrategyContext { history: &history, meta: None, reference: None };
+        let snap = GlobalSnapshot { ts_ms: 10_000, markets: vec![m], coverage: 1.0 };
+        let (intents, summary) = s.on_snapshot(&snap, &ctx);
+        assert!(intents.is_empty());
+        assert_eq!((summary.near_arb_hits, summary.opportunities), (1, 0));
+    }
+
+    fn ema_guarded(guard: bool) -> SumArbStrategy {
+        let mut s = sum_arb();
+        s.sum_ask_ema_alpha = Some(dec!(0.2));
+        s.sum_ask_ema_band_bps = 200;
+        s.sum_ask_ema_guard = guard;
+        s
+    }
+
+    fn cycle(s: &SumArbStrategy, sum_ask: Decimal) -> (Vec<OrderIntent>, CycleSummary) {
+        run(s, &GlobalSnapshot { ts_ms: 0, markets: vec![pair(sum_ask)], coverage: 1.0 })
+    }
+
+    #[test]
+    fn single_cycle_dip_is_flagged_as_a_glitch() {
+        let s = ema_guarded(true);
+        for _ in 0..3 {
+            assert_eq!(cycle(&s, dec!(1.00)).1.skipped(SkipReason::SuspectedGlitch), 0);
+        }
+        // 0.90 against an EMA of 1.00 is 1000 bps off, far outside the 200 bps band
+        let (intents, summary) = cycle(&s, dec!(0.90));
+        assert!(intents.is_empty());
+        assert_eq!(summary.skipped(SkipReason::SuspectedGlitch), 1);
+        // the dip only moved the EMA a fifth of the way
+        assert_eq!(s.sum_ask_ema.lock().unwrap()["mkt-0"], dec!(0.98));
+        assert_eq!(cycle(&s, dec!(1.00)).1.skipped(SkipReason::SuspectedGlitch), 0);
+    }
+
+    #[test]
+    fn unguarded_glitch_is_only_logged() {
+        let s = ema_guarded(false);
+        cycle(&s, dec!(1.00));
+        let (intents, summary) = cycle(&s, dec!(0.90));
+        assert!(!intents.is_empty());
+        assert_eq!(summary.skipped(SkipReason::SuspectedGlitch), 0);
+    }
+
+    #[test]
+    fn first_sighting_only_seeds_the_ema() {
+        let s = ema_guarded(true);
+        let (intents, _) = cycle(&s, dec!(0.90));
+        assert!(!intents.is_empty());
+        assert_eq!(s.sum_ask_ema.lock().unwrap()["mkt-0"], dec!(0.90));
+    }
+
+    /// 0.03 / 0.07: 4c wide but 80% of its 0.05 mid.
+    fn cheap_leg() -> OutcomeTop {
+        outcome("cheap", dec!(0.03), dec!(0.07), dec!(10))
+    }
+
+    /// 0.40 / 0.50: 10c wide but only 22% of its 0.45 mid.
+    fn wide_leg() -> OutcomeTop {
+        outcome("wide", dec!(0.40), dec!(0.50), dec!(10))
+    }
+
+    fn spread_limits(abs: Option<Decimal>, pct: Option<Decimal>) -> SumArbStrategy {
+        let mut s = sum_arb();
+        s.max_leg_spread = abs;
+        s.max_leg_spread_pct = pct;
+        s
+    }
+
+    #[test]
+    fn absolute_spread_limit_alone() {
+        let s = spread_limits(Some(dec!(0.05)), None);
+        assert_eq!(s.check_leg(&cheap_leg()), Ok(()));
+        assert_eq!(s.check_leg(&wide_leg()), Err(SkipReason::LegSpread));
+    }
+
+    #[test]
+    fn percentage_spread_limit_alone() {
+        let s = spread_limits(None, Some(dec!(50)));
+        assert_eq!(s.check_leg(&cheap_leg()), Err(SkipReason::LegSpreadPct));
+        assert_eq!(s.check_leg(&wide_leg()), Ok(()));
+    }
+
+    #[test]
+    fn both_spread_limits_must_pass() {
+        let s = spread_limits(Some(dec!(0.05)), Some(dec!(50)));
+        assert_eq!(s.check_leg(&cheap_leg()), Err(SkipReason::LegSpreadPct));
+        assert_eq!(s.check_leg(&wide_leg()), Err(SkipReason::LegSpread));
+        assert_eq!(s.check_leg(&outcome("tight", dec!(0.44), dec!(0.45), dec!(10))), Ok(()));
+
+        let unlimited = spread_limits(None, None);
+        assert_eq!(unlimited.check_leg(&cheap_leg()), Ok(()));
+        assert_eq!(unlimited.check_leg(&wide_leg()), Ok(()));
+    }
+
+    #[test]
+    fn spread_skips_are_counted_by_limit() {
+        let s = spread_limits(Some(dec!(0.05)), Some(dec!(50)));
+        let mut snap = SnapshotBuilder::new().markets(2).build();
+        snap.markets[0].outcomes[0] = cheap_leg();
+        snap.markets[1].outcomes[0] = wide_leg();
+        let (_, summary) = run(&s, &snap);
+        assert_eq!((summary.skipped(SkipReason::LegSpread), summary.skipped(SkipReason::LegSpreadPct)), (1, 1));
+    }
+
+    #[test]
+    fn redeem_admits_what_sell_back_rejects() {
+        let mut s = sum_arb();
+        s.fees = Arc::new(FeeModel::flat(50));
+        let m = pair(dec!(0.99));
+
+        // 100 bps of gross edge less 50 bps of fees on 0.99 of legs
+        let redeem = s.quote(&m).unwrap();
+        assert_eq!((redeem.econ.exit_fees, redeem.edge_bps), (dec!(0), dec!(50.5)));
+        assert!(s.is_executable(&redeem));
+
+        // selling the set back pays another 50 bps on the payout
+        s.exit_mode = ExitMode::SellBack;
+        let sell_back = s.quote(&m).unwrap();
+        assert_eq!((sell_back.econ.exit_fees, sell_back.edge_bps), (dec!(0.5), dec!(0.5)));
+        assert!(!s.is_executable(&sell_back));
+        let snap = GlobalSnapshot { ts_ms: 0, markets: vec![m], coverage: 1.0 };
+        assert!(run(&s, &snap).0.is_empty());
+    }
+
+    /// A writer appending to a shared buffer, for reading back what was logged.
+    #[derive(Clone, Default)]
+    struct Captured(Arc<std::sync::Mutex<Vec<u8>>>);
+
+    impl std::io::Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// Lines logged while `s` runs on `snap`.
+    fn logged_lines(s: &SumArbStrategy, snap: &GlobalSnapshot) -> Vec<String> {
+        let buf = Captured::default();
+        let writer = buf.clone();
+        let subscriber = tracing_subscriber::fmt().with_ansi(false).with_writer(move || writer.clone()).finish();
+        tracing::subscriber::with_default(subscriber, || run(s, snap));
+        let out = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        out.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn leg_detail_is_logged_per_leg_when_enabled() {
+        let snap = GlobalSnapshot { ts_ms: 0, markets: vec![market(&[dec!(0.45), dec!(0.50)], dec!(100))], coverage: 1.0 };
+        let mut s = sum_arb();
+        let legs = |lines: Vec<String>| lines.into_iter().filter(|l| l.contains("opportunity leg")).collect::<Vec<_>>();
+        assert!(legs(logged_lines(&s, &snap)).is_empty());
+
+        s.log_leg_detail = true;
+        let lines = legs(logged_lines(&s, &snap));
+        assert_eq!(lines.len(), 2);
+        for (i, (token, ask)) in [("tok-0-0", "0.45"), ("tok-0-1", "0.50")].into_iter().enumerate() {
+            let line = &lines[i];
+            for field in [
+                format!("leg={}", i),
+                format!("token_id={}", token),
+                format!("ask_px={}", ask),
+                "ask_size=100".to_string(),
+                format!("contribution={}", ask),
+                "market_id=mkt-0".to_string(),
+                "bundle_id=".to_string(),
+            ] {
+                assert!(line.contains(&field), "{} missing from {}", field, line);
+            }
+        }
+        // 0.45 of 0.95
+        assert!(lines[0].contains("share_pct=47.37"), "{}", lines[0]);
+    }
+
+    #[test]
+    fn three_way_market_is_held_to_the_higher_threshold() {
+        let mut s = sum_arb();
+        s.min_edge_bps_by_legs = parse_edge_by_legs("2:30, 3:60").unwrap();
+        // 50 bps of edge on both
+        let binary = pair(dec!(0.995));
+        let three_way = market(&[dec!(0.33), dec!(0.33), dec!(0.335)], dec!(100));
+        let (b, t) = (s.quote(&binary).unwrap(), s.quote(&three_way).unwrap());
+        assert_eq!((b.edge_bps, t.edge_bps), (dec!(50), dec!(50)));
+        assert_eq!((b.min_edge_bps, t.min_edge_bps), (dec!(30), dec!(60)));
+        assert!(s.is_executable(&b));
+        assert!(!s.is_executable(&t));
+
+        // leg counts missing from the map use min_edge_bps
+        let four_way = market(&[dec!(0.25), dec!(0.25), dec!(0.25), dec!(0.245)], dec!(100));
+        assert_eq!(s.quote(&four_way).unwrap().min_edge_bps, Decimal::from(s.min_edge_bps));
+    }
+
+    #[test]
+    fn edge_by_legs_parses_and_rejects_garbage() {
+        let map = parse_edge_by_legs(" 2:30,3:60 ,4:100,").unwrap();
+        assert_eq!(map, HashMap::from([(2, 30), (3, 60), (4, 100)]));
+        assert!(parse_edge_by_legs("").unwrap().is_empty());
+        for bad in ["2=30", "two:30", "2:thirty"] {
+            assert!(parse_edge_by_legs(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn each_skip_reason_is_tallied() {
+        let builder = SnapshotBuilder::new().legs(2).leg_size(dec!(100));
+        let mut markets: Vec<MarketBook> = (0..10).map(|i| builder.market(i)).collect();
+        markets[0].partial = true;
+        markets[1].outcomes.truncate(1);
+        markets[2].outcomes = builder.clone().legs(4).market(2).outcomes;
+        markets[3].outcomes[0].best_ask_px = None;
+        markets[4].outcomes[0] = outcome("wide", dec!(0.30), dec!(0.40), dec!(100));
+        markets[5].outcomes[0] = outcome("cheap", dec!(0.02), dec!(0.05), dec!(100));
+        markets[6].outcomes[0] = outcome("over", dec!(1.49), dec!(1.50), dec!(100));
+        markets[7].outcomes[0].best_ask_sz = Some(dec!(0));
+        // markets[8] keeps the builder's 1.04 sum: below the edge
+        markets[9] = pair(dec!(0.98));
+        markets[9].market_id = "mkt-9".into();
+
+        let mut s = sum_arb();
+        (s.min_legs, s.max_legs) = (2, 3);
+        (s.max_leg_spread, s.max_leg_spread_pct) = (Some(dec!(0.05)), Some(dec!(50)));
+        let (intents, summary) = run(&s, &GlobalSnapshot { ts_ms: 0, markets: markets.clone(), coverage: 1.0 });
+        assert_eq!(bundle_markets(&intents), ["mkt-9"]);
+        let expected = [
+            SkipReason::PartialBook,
+            SkipReason::TooFewLegs,
+            SkipReason::TooManyLegs,
+            SkipReason::MissingQuote,
+            SkipReason::LegSpread,
+            SkipReason::LegSpreadPct,
+            SkipReason::Anomaly,
+            SkipReason::NoSize,
+            SkipReason::BelowEdge,
+        ];
+        for reason in SkipReason::ALL {
+            assert_eq!(summary.skipped(reason), expected.contains(&reason) as u64, "{}", reason.as_str());
+        }
+        let by_reason = s.stats.snapshot(0).skipped_by_reason;
+        assert_eq!(by_reason.len(), expected.len());
+        assert!(expected.iter().all(|r| by_reason[r.as_str()] == 1));
+
+        // both sides of a leg must show min_leg_size
+        s.min_leg_size = Some(dec!(200));
+        let (_, summary) = run(&s, &GlobalSnapshot { ts_ms: 0, markets: vec![markets[9].clone()], coverage: 1.0 });
+        assert_eq!(summary.skipped(SkipReason::LegSize), 1);
+    }
+
+    fn rounded(decimals: u32, leg_size: Decimal) -> (Vec<OrderIntent>, CycleSummary) {
+        let mut s = sum_arb();
+        s.size_rounding = SizeRounding { decimals };
+        let m = market(&[dec!(0.45), dec!(0.47)], leg_size);
+        run(&s, &GlobalSnapshot { ts_ms: 0, markets: vec![m], coverage: 1.0 })
+    }
+
+    #[test]
+    fn sizes_round_down_to_each_venue_precision() {
+        assert_eq!(SizeRounding { decimals: 2 }.round(dec!(3.33333)), dec!(3.33));
+        assert_eq!(SizeRounding { decimals: 0 }.round(dec!(3.33333)), dec!(3));
+        // down, never up past the displayed size
+        assert_eq!(SizeRounding { decimals: 2 }.round(dec!(3.339)), dec!(3.33));
+
+        assert_eq!(bundle_sizes(&rounded(2, dec!(3.33333)).0), [dec!(3.33)]);
+        assert_eq!(bundle_sizes(&rounded(0, dec!(3.33333)).0), [dec!(3)]);
+    }
+
+    #[test]
+    fn a_size_rounding_to_zero_drops_the_bundle() {
+        let (intents, summary) = rounded(0, dec!(0.4));
+        assert!(intents.is_empty());
+        assert_eq!(summary.skipped(SkipReason::RoundedToZero), 1);
+        // the same size survives a finer venue
+        assert_eq!(bundle_sizes(&rounded(2, dec!(0.4)).0), [dec!(0.4)]);
+    }
+
+    #[test]
+    fn rounded_size_is_checked_against_the_minimum_order() {
+        let mut s = sum_arb();
+        s.size_rounding = SizeRounding { decimals: 0 };
+        let mut m = market(&[dec!(0.45), dec!(0.47)], dec!(5.9));
+        m.min_order_size = Some(dec!(5.5));
+        // 5.9 would pass, the 5 it rounds to does not
+        assert_eq!(s.quote(&m).unwrap_err(), SkipReason::BelowMinOrder);
+        m.min_order_size = Some(dec!(5));
+        assert_eq!(s.quote(&m).unwrap().size, dec!(5));
+    }
+
+    struct FixedReference(HashMap<TokenId, Decimal>);
+
+    impl ReferenceFeed for FixedReference {
+        fn reference_px(&self, token_id: &TokenId) -> Option<Decimal> {
+            self.0.get(token_id).copied()
+        }
+    }
+
+    /// One cycle of `pair(0.98)` (legs quoted 0.48 / 0.49) against `reference`.
+    fn against_reference(max_bps: Option<i64>, reference: &[(&str, Decimal)]) -> (Vec<OrderIntent>, CycleSummary) {
+        let mut s = sum_arb();
+        s.max_reference_deviation_bps = max_bps;
+        let feed = FixedReference(reference.iter().map(|(t, px)| (TokenId::from(*t), *px)).collect());
+        let history = crate::history::PriceHistory::new(0, 0);
+        let ctx = StrategyContext { history: &history, meta: None, reference: Some(&feed) };
+        s.on_snapshot(&GlobalSnapshot { ts_ms: 0, markets: vec![pair(dec!(0.98))], coverage: 1.0 }, &ctx)
+    }
+
+    #[test]
+    fn a_deviating_leg_skips_the_market() {
+        // the first leg's 0.485 mid is 1917 bps off a 0.60 reference
+        let (intents, summary) = against_reference(Some(200), &[("tok-0-0", dec!(0.60)), ("tok-0-1", dec!(0.485))]);
+        assert!(intents.is_empty());
+        assert_eq!(summary.skipped(SkipReason::ReferenceDeviation), 1);
+    }
+
+    #[test]
+    fn legs_near_or_without_a_reference_pass() {
+        // 0.485 against 0.49 is 102 bps off
+        let (intents, _) = against_reference(Some(200), &[("tok-0-0", dec!(0.49))]);
+        assert!(!intents.is_empty());
+        // a zero reference is no reference
+        let (intents, _) = against_reference(Some(200), &[("tok-0-0", dec!(0))]);
+        assert!(!intents.is_empty());
+        // no limit configured: the check is off
+        let (intents, summary) = against_reference(None, &[("tok-0-0", dec!(0.60))]);
+        assert!(!intents.is_empty());
+        assert_eq!(summary.skipped(SkipReason::ReferenceDeviation), 0);
+    }
+
+    #[test]
+    fn parallel_equals_serial_at_any_strategy_parallelism() {
+        let mut snap = SnapshotBuilder::new().markets(600).near_arb_fraction(0.2).build();
+        for (k, m) in snap.markets.iter_mut().enumerate() {
+            m.outcomes[k % 2].best_ask_sz = Some(Decimal::from(5 + k % 41));
+        }
+        let serial = sum_arb();
+        let (serial_intents, _) = run(&serial, &snap);
+        let serial_stats = serial.stats.snapshot(0);
+        assert!(!serial_intents.is_empty());
+
+        for threads in [1, 2, 8] {
+            let mut parallel = sum_arb();
+            parallel.parallel_min_markets = 1;
+            let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+            let (intents, _) = pool.install(|| run(&parallel, &snap));
+            assert_eq!(serde_json::to_value(&intents).unwrap(), serde_json::to_value(&serial_intents).unwrap(), "{} threads", threads);
+            // shared counters come out the same however the quoting was split
+            let stats = parallel.stats.snapshot(0);
+            assert_eq!((stats.opportunities, stats.intents_emitted), (serial_stats.opportunities, serial_stats.intents_emitted));
+            assert_eq!(stats.skipped_by_reason, serial_stats.skipped_by_reason);
+        }
+    }
+
+    fn redeeming(sum_ask: Decimal, value: Option<Decimal>) -> MarketBook {
+        let mut m = pair(sum_ask);
+        m.redemption_value = value;
+        m
+    }
+
+    #[test]
+    fn unit_redemption_value_changes_nothing() {
+        let s = sum_arb();
+        let default = s.quote(&redeeming(dec!(0.98), None)).unwrap();
+        let unit = s.quote(&redeeming(dec!(0.98), Some(dec!(1)))).unwrap();
+        assert_eq!((unit.payoff, unit.edge_bps, unit.econ.payout), (default.payoff, default.edge_bps, default.econ.payout));
+        assert_eq!(unit.edge_bps, dec!(200));
+        assert!(s.is_executable(&unit));
+    }
+
+    #[test]
+    fn non_unit_redemption_value_scales_the_edge() {
+        let s = sum_arb();
+        // a set redeeming for 0.90 bought at 0.98 loses money
+        let short = s.quote(&redeeming(dec!(0.98), Some(dec!(0.90)))).unwrap();
+        assert!(short.edge_bps < dec!(0));
+        assert!(!s.is_executable(&short));
+        // one redeeming for 2: (2 - 1.50) / 2 of the payout
+        let double = s.quote(&redeeming(dec!(1.50), Some(dec!(2)))).unwrap();
+        assert_eq!((double.payoff, double.edge_bps), (dec!(2), dec!(2500)));
+        assert_eq!(double.econ.payout, dec!(200));
+        assert!(s.is_executable(&double));
+        // 1.50 would be no arb at all on a unit market
+        assert!(!s.is_executable(&s.quote(&redeeming(dec!(1.50), None)).unwrap()));
+    }
+
+    #[test]
+    fn implausibly_low_sum_is_held_unless_trusted() {
+        let mut s = sum_arb();
+        s.min_plausible_sum_ask = Some(dec!(0.7));
+        // 0.80 is a deep but plausible arb, 0.30 looks like missing legs
+        let snap = snapshot(&[dec!(0.80), dec!(0.30)], dec!(100));
+
+        let (intents, summary) = run(&s, &snap);
+        assert_eq!(bundle_markets(&intents), ["mkt-0"]);
+        assert_eq!(summary.skipped(SkipReason::ImplausibleSumAsk), 1);
+
+        s.trust_extreme_arbs = true;
+        let (intents, summary) = run(&s, &snap);
+        assert_eq!(bundle_markets(&intents).len(), 2);
+        assert_eq!(summary.skipped(SkipReason::ImplausibleSumAsk), 0);
+    }
+
+    #[test]
+    fn leg_bounds_are_inclusive() {
+        let mut s = sum_arb();
+        s.min_legs = 2;
+        s.max_legs = 3;
+        let legs = |n: usize| s.quote(&market(&vec![dec!(0.2); n], dec!(10))).map(|q| q.sum_ask);
+        assert_eq!(legs(1), Err(SkipReason::TooFewLegs));
+        assert_eq!(legs(2), Ok(dec!(0.4)));
+        assert_eq!(legs(3), Ok(dec!(0.6)));
+        assert_eq!(legs(4), Err(SkipReason::TooManyLegs));
+    }
+
+    #[test]
+    fn prices_outside_the_unit_interval_are_anomalies() {
+        let s = sum_arb();
+        assert_eq!(s.quote(&market(&[dec!(1.5), dec!(0.2)], dec!(10))).unwrap_err(), SkipReason::Anomaly);
+        assert_eq!(s.quote(&market(&[dec!(-0.1), dec!(0.2)], dec!(10))).unwrap_err(), SkipReason::Anomaly);
+        let mut neg_bid = market(&[dec!(0.4), dec!(0.5)], dec!(10));
+        neg_bid.outcomes[0].best_bid_px = Some(dec!(-1));
+        assert_eq!(s.quote(&neg_bid).unwrap_err(), SkipReason::Anomaly);
+        let mut neg_size = market(&[dec!(0.4), dec!(0.5)], dec!(10));
+        neg_size.outcomes[1].best_ask_sz = Some(dec!(-5));
+        assert_eq!(s.quote(&neg_size).unwrap_err(), SkipReason::Anomaly);
+    }
+
+    #[test]
+    fn absurd_leg_counts_are_anomalies() {
+        let m = market(&vec![dec!(0.01); MAX_BUNDLE_LEGS + 1], dec!(10));
+        assert_eq!(sum_arb().quote(&m).unwrap_err(), SkipReason::Anomaly);
+    }
+
+    #[test]
+    fn extreme_sizes_and_prices_do_not_overflow() {
+        let mut s = sum_arb();
+        s.max_bundle_shares = Decimal::MAX;
+        s.fees = std::sync::Arc::new(FeeModel::flat(200));
+        s.bundle_fixed_cost = dec!(1);
+        for asks in [vec![dec!(0.45), dec!(0.47)], vec![dec!(1), dec!(1)], vec![Decimal::new(1, 28), Decimal::new(1, 28)]] {
+            let m = market(&asks, Decimal::MAX);
+            let snap = GlobalSnapshot { ts_ms: 0, markets: vec![m.clone()], coverage: 1.0 };
+            // whatever the verdict, it is reached without a panic
+            let _ = s.quote(&m);
+            let _ = run(&s, &snap);
+        }
+    }
+
+    #[test]
+    fn collateral_is_the_binding_cap() {
+        let snap = SnapshotBuilder::new().markets(1).regular_sum_ask(dec!(0.90)).leg_size(dec!(100)).build();
+        let mut s = sum_arb();
+        s.available_collateral = Some(dec!(50));
+        s.collateral_reserve = dec!(5);
+        let (intents, _) = run(&s, &snap);
+        // (50 - 5) / 0.90 = 50 shares, under both the 100 on the book and max_bundle_shares
+        assert_eq!(bundle_sizes(&intents), [dec!(50)]);
+    }
+
+    #[test]
+    fn collateral_cap_includes_fees_and_fixed_cost() {
+        let snap = SnapshotBuilder::new().markets(1).regular_sum_ask(dec!(0.80)).leg_size(dec!(100)).build();
+        let mut s = sum_arb();
+        s.fees = std::sync::Arc::new(FeeModel::flat(100));
+        s.bundle_fixed_cost = dec!(1);
+        s.available_collateral = Some(dec!(41.4));
+        let (intents, _) = run(&s, &snap);
+        // (41.4 - 1) / (0.80 * 1.01) = 50
+        assert_eq!(bundle_sizes(&intents), [dec!(50)]);
+    }
+
+    #[test]
+    fn bundles_of_a_cycle_share_the_collateral() {
+        let snap = SnapshotBuilder::new().markets(3).regular_sum_ask(dec!(0.90)).leg_size(dec!(100)).build();
+        let mut s = sum_arb();
+        s.available_collateral = Some(dec!(100));
+        let (intents, summary) = run(&s, &snap);
+
+        // 90 for the first full bundle leaves 10: the second is trimmed to 11.11 shares and
+        // nothing is left for the third
+        assert_eq!(bundle_sizes(&intents), [dec!(100), dec!(11.11)]);
+        assert_eq!((summary.collateral_trimmed, summary.collateral_dropped), (1, 1));
+        let committed: Decimal = bundle_sizes(&intents).iter().map(|size| size * dec!(0.90)).sum();
+        assert!(committed <= dec!(100), "committed {}", committed);
+    }
+
+    #[test]
+    fn third_opportunity_is_trimmed_to_the_remaining_budget() {
+        let mut s = sum_arb();
+        s.max_cycle_notional = Some(dec!(230));
+        // best first: 90 and 92 of notional leave 48 for the 0.94 bundle
+        let (intents, summary) = run(&s, &snapshot(&[dec!(0.94), dec!(0.90), dec!(0.92)], dec!(100)));
+        assert_eq!(bundle_markets(&intents), ["mkt-1", "mkt-2", "mkt-0"]);
+        // 48 / 0.94 = 51.06 shares, rounded down to 2 decimals
+        assert_eq!(bundle_sizes(&intents), [dec!(100), dec!(100), dec!(51.06)]);
+        assert_eq!((summary.budget_trimmed, summary.budget_dropped), (1, 0));
+        let spent: Decimal = intents.iter().map(|i| i.price * i.size).sum();
+        assert!(spent <= dec!(230), "spent {}", spent);
+    }
+
+    #[test]
+    fn an_exhausted_budget_drops_the_rest() {
+        let mut s = sum_arb();
+        s.max_cycle_notional = Some(dec!(180));
+        let (intents, summary) = run(&s, &snapshot(&[dec!(0.90), dec!(0.90), dec!(0.92)], dec!(100)));
+        assert_eq!(bundle_sizes(&intents), [dec!(100), dec!(100)]);
+        // recorded as opportunities, not emitted
+        assert_eq!((summary.opportunities, summary.budget_dropped), (3, 1));
+    }
+
+    #[test]
+    fn bundle_notional_caps_each_opportunity() {
+        let mut s = sum_arb();
+        s.max_bundle_notional = Some(dec!(45));
+        let (intents, _) = run(&s, &snapshot(&[dec!(0.90), dec!(0.92)], dec!(100)));
+        // 45 / 0.90 = 50 and 45 / 0.92 = 48.91
+        assert_eq!(bundle_sizes(&intents), [dec!(50), dec!(48.91)]);
+    }
+
+    #[test]
+    fn collateral_and_cycle_notional_apply_together() {
+        let snap = SnapshotBuilder::new().markets(2).regular_sum_ask(dec!(0.90)).leg_size(dec!(100)).build();
+        let mut s = sum_arb();
+        s.max_cycle_notional = Some(dec!(45));
+        s.available_collateral = Some(dec!(1000));
+        let (intents, summary) = run(&s, &snap);
+        assert_eq!(bundle_sizes(&intents), [dec!(50)]);
+        assert_eq!((summary.budget_trimmed, summary.budget_dropped), (1, 1));
+        assert_eq!((summary.collateral_trimmed, summary.collateral_dropped), (0, 0));
     }
 }
@@ -0,0 +1,41 @@
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::types::{IntentMeta, OrderIntent, Side, TimeInForce, TokenId};
+
+/// Stable bundle id `n`, so tests can name bundles.
+pub fn bundle(n: u128) -> Uuid {
+    Uuid::from_u128(n)
+}
+
+/// A FOK buy of `size` `token_id` at `price` in `bundle_id`.
+pub fn buy(market_id: &str, token_id: &str, price: Decimal, size: Decimal, bundle_id: Uuid) -> OrderIntent {
+    let meta = IntentMeta { strategy: "test".into(), ..Default::default() };
+    OrderIntent {
+        market_id: market_id.to_string(),
+        token_id: TokenId::from(token_id),
+        side: Side::Buy,
+        price,
+        size,
+        reason: meta.summary(size),
+        bundle_id,
+        tif: TimeInForce::Fok,
+        post_only: false,
+        avail_size: None,
+        meta,
+    }
+}
+
+/// The legs of one bundle on `market_id`, buying `size` of each `(token, price)`.
+pub fn bundle_legs(market_id: &str, legs: &[(&str, Decimal)], size: Decimal, bundle_id: Uuid) -> Vec<OrderIntent> {
+    legs.iter()
+        .enumerate()
+        .map(|(k, (token, price))| {
+            let mut i = buy(market_id, token, *price, size, bundle_id);
+            i.meta.leg_index = k;
+            i.meta.leg_count = legs.len();
+            i.meta.sum_ask = legs.iter().map(|l| l.1).sum();
+            i
+        })
+        .collect()
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use crate::http::{HttpClient, RetryPolicy};
+use crate::jitter::Jitter;
+use crate::pm::market_data::MarketDef;
+use crate::types::{Polarity, TokenId};
+
+/// A plain YES market listing `token_ids`, with no event, category or venue metadata.
+pub fn market_def(market_id: &str, token_ids: &[&str]) -> MarketDef {
+    MarketDef {
+        market_id: market_id.to_string(),
+        question: format!("{}?", market_id),
+        polarity: Polarity::Yes,
+        token_ids: token_ids.iter().map(|t| TokenId::from(*t)).collect(),
+        event_id: None,
+        event_title: None,
+        category: None,
+        tags: vec![],
+        min_order_size: None,
+        liquidity: None,
+        volume_24h: None,
+        redemption_value: None,
+        shared_by: None,
+    }
+}
+
+/// A client retrying `max_retries` times with 1ms backoff and seeded jitter, so tests
+/// against a local server run fast and the same way every time.
+pub fn http_client(max_retries: u32) -> HttpClient {
+    HttpClient::new(2_000, RetryPolicy {
+        max_retries,
+        backoff_ms: 1,
+        max_backoff_ms: 5,
+        jitter_pct: 0,
+        jitter: Arc::new(Jitter::seeded(7)),
+    })
+    .expect("build http client")
+}
+
+/// A venue order book body as /book and /books return it; levels are (price, size).
+pub fn book_json(token_id: &str, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> serde_json::Value {
+    let levels = |l: &[(&str, &str)]| l.iter().map(|(p, s)| serde_json::json!({ "price": p, "size": s })).collect::<Vec<_>>();
+    serde_json::json!({
+        "market": "0xtest",
+        "asset_id": token_id,
+        "bids": levels(bids),
+        "asks": levels(asks),
+    })
+}
+
+/// An open, order-book-enabled market as GET /markets lists it; outcomes are
+/// (token_id, label).
+pub fn market_json(condition_id: &str, outcomes: &[(&str, &str)]) -> serde_json::Value {
+    serde_json::json!({
+        "enable_order_book": true,
+        "accepting_orders": true,
+        "closed": false,
+        "condition_id": condition_id,
+        "question": format!("{}?", condition_id),
+        "tokens": outcomes.iter()
+            .map(|(id, label)| serde_json::json!({ "token_id": id, "outcome": label }))
+            .collect::<Vec<_>>(),
+    })
+}
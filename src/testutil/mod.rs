@@ -0,0 +1,10 @@
+pub mod intents;
+pub mod markets;
+pub mod settings;
+pub mod snapshot_builder;
+pub mod strategy;
+
+/// A fresh path under the system temp dir ending in `name`; nothing is created.
+pub fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("pm-arb-test-{}-{}", uuid::Uuid::new_v4(), name))
+}
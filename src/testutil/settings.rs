@@ -0,0 +1,23 @@
+use crate::config::Settings;
+
+/// Settings with the required fields at their .env.example values and serde defaults
+/// elsewhere, then `overrides` (lowercase field names) applied on top.
+pub fn settings(overrides: serde_json::Value) -> Settings {
+    let mut v = serde_json::json!({
+        "clob_host": "http://127.0.0.1:1",
+        "poll_ms": 1000,
+        "max_markets": 200,
+        "markets_refresh_sec": 0,
+        "books_chunk_size": 100,
+        "books_concurrency": 1,
+        "fee_bps": 0,
+        "min_edge_bps": 30,
+        "warn_edge_bps": 150,
+        "stats_log_sec": 60,
+        "max_bundle_shares": "100",
+    });
+    if let (Some(base), serde_json::Value::Object(extra)) = (v.as_object_mut(), overrides) {
+        base.extend(extra);
+    }
+    serde_json::from_value(v).expect("test settings")
+}
@@ -0,0 +1,128 @@
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::types::{GlobalSnapshot, MarketBook, OutcomeTop, Polarity, TokenId};
+
+/// One outcome's top of book with the same size on both sides.
+pub fn outcome(token_id: &str, bid: Decimal, ask: Decimal, size: Decimal) -> OutcomeTop {
+    OutcomeTop {
+        token_id: TokenId::from(token_id),
+        best_bid_px: Some(bid),
+        best_bid_sz: Some(size),
+        best_ask_px: Some(ask),
+        best_ask_sz: Some(size),
+        ask_levels: vec![],
+        book_ts_ms: None,
+    }
+}
+
+/// Builds deterministic synthetic snapshots.
+///
+/// Regular markets price their legs so `sum_ask` is `regular_sum_ask` (over 1, no arb);
+/// a `near_arb_fraction` share of markets, spread evenly, price at `near_arb_sum_ask`.
+#[derive(Debug, Clone)]
+pub struct SnapshotBuilder {
+    markets: usize,
+    legs: usize,
+    near_arb_fraction: f64,
+    regular_sum_ask: Decimal,
+    near_arb_sum_ask: Decimal,
+    leg_spread: Decimal,
+    leg_size: Decimal,
+    ts_ms: i64,
+}
+
+impl Default for SnapshotBuilder {
+    fn default() -> Self {
+        Self {
+            markets: 100,
+            legs: 2,
+            near_arb_fraction: 0.0,
+            regular_sum_ask: dec!(1.04),
+            near_arb_sum_ask: dec!(0.98),
+            leg_spread: dec!(0.01),
+            leg_size: dec!(100),
+            ts_ms: 0,
+        }
+    }
+}
+
+impl SnapshotBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn markets(mut self, n: usize) -> Self {
+        self.markets = n;
+        self
+    }
+
+    pub fn legs(mut self, n: usize) -> Self {
+        self.legs = n.max(1);
+        self
+    }
+
+    pub fn near_arb_fraction(mut self, f: f64) -> Self {
+        self.near_arb_fraction = f.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn regular_sum_ask(mut self, v: Decimal) -> Self {
+        self.regular_sum_ask = v;
+        self
+    }
+
+    pub fn near_arb_sum_ask(mut self, v: Decimal) -> Self {
+        self.near_arb_sum_ask = v;
+        self
+    }
+
+    pub fn leg_spread(mut self, v: Decimal) -> Self {
+        self.leg_spread = v;
+        self
+    }
+
+    pub fn leg_size(mut self, v: Decimal) -> Self {
+        self.leg_size = v;
+        self
+    }
+
+    pub fn ts_ms(mut self, ts: i64) -> Self {
+        self.ts_ms = ts;
+        self
+    }
+
+    fn is_near_arb(&self, i: usize) -> bool {
+        let f = self.near_arb_fraction;
+        ((i + 1) as f64 * f).floor() > (i as f64 * f).floor()
+    }
+
+    pub fn market(&self, i: usize) -> MarketBook {
+        let sum_ask = if self.is_near_arb(i) { self.near_arb_sum_ask } else { self.regular_sum_ask };
+        let ask = sum_ask / Decimal::from(self.legs as u64);
+        let bid = (ask - self.leg_spread).max(Decimal::ZERO);
+        let outcomes = (0..self.legs)
+            .map(|j| outcome(&format!("tok-{}-{}", i, j), bid, ask, self.leg_size))
+            .collect();
+        MarketBook {
+            market_id: format!("mkt-{}", i),
+            question: format!("Synthetic market {}?", i),
+            polarity: Polarity::Yes,
+            event_id: None,
+            category: None,
+            min_order_size: None,
+            redemption_value: None,
+            shared_by: None,
+            outcomes,
+            partial: false,
+        }
+    }
+
+    pub fn build(&self) -> GlobalSnapshot {
+        GlobalSnapshot {
+            ts_ms: self.ts_ms,
+            markets: (0..self.markets).map(|i| self.market(i)).collect(),
+            coverage: 1.0,
+        }
+    }
+}
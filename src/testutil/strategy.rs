@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::history::PriceHistory;
+use crate::stats::Stats;
+use crate::strategy::fees::FeeModel;
+use crate::strategy::pricing::TopOfBook;
+use crate::strategy::sum_arb::{SizeRounding, SumArbStrategy};
+use crate::strategy::{CycleSummary, Strategy, StrategyContext};
+use crate::types::{GlobalSnapshot, OrderIntent, TimeInForce};
+
+/// A sum-arb strategy with no fees, a 30 bps threshold, 100-share bundles sized to 2
+/// decimals and every optional guard off; tests switch on what they exercise.
+pub fn sum_arb() -> SumArbStrategy {
+    SumArbStrategy {
+        min_edge_bps: 30,
+        min_edge_bps_by_legs: Default::default(),
+        slippage_buffer_bps: 0,
+        warn_edge_bps: 150,
+        fees: Arc::new(FeeModel::flat(0)),
+        pricing: Arc::new(TopOfBook),
+        max_bundle_shares: dec!(100),
+        size_rounding: SizeRounding { decimals: 2 },
+        max_bundle_notional: None,
+        max_cycle_notional: None,
+        max_leg_spread: None,
+        max_leg_spread_pct: None,
+        min_leg_size: None,
+        available_collateral: None,
+        collateral_reserve: Decimal::ZERO,
+        bundle_fixed_cost: Decimal::ZERO,
+        exit_mode: Default::default(),
+        max_book_update_age_ms: None,
+        unknown_book_age_stale: false,
+        max_reference_deviation_bps: None,
+        min_legs: 2,
+        max_legs: 0,
+        max_bundles_per_snapshot: 0,
+        trace_market_ids: Default::default(),
+        log_leg_detail: false,
+        parallel_min_markets: 0,
+        vol_edge_k: None,
+        vol_lookback_ms: 60_000,
+        vol_min_samples: 5,
+        leg_tif: TimeInForce::Fok,
+        leg_post_only: false,
+        sum_ask_ema_alpha: None,
+        sum_ask_ema_band_bps: 300,
+        sum_ask_ema_guard: false,
+        min_plausible_sum_ask: None,
+        trust_extreme_arbs: false,
+        sum_ask_ema: Default::default(),
+        stats: Stats::new(0),
+    }
+}
+
+/// One pass of `strategy` over `snap` with empty history and no metadata or reference.
+pub fn run(strategy: &dyn Strategy, snap: &GlobalSnapshot) -> (Vec<OrderIntent>, CycleSummary) {
+    let history = PriceHistory::new(0, 0);
+    let ctx = StrategyContext { history: &history, meta: None, reference: None };
+    strategy.on_snapshot(snap, &ctx)
+}
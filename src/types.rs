@@ -1,42 +1,457 @@
+use std::sync::Arc;
+
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Decimals as JSON strings whatever rust_decimal's serde features are (serde-float, which
+/// any crate in the build can turn on, writes floats and rounds recorded prices). Reads
+/// strings and numbers both, so recordings written either way still load.
+pub mod decimal_str {
+    use std::fmt;
+
+    use rust_decimal::Decimal;
+    use serde::de::{self, Visitor};
+    use serde::{Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(d: &Decimal, s: S) -> Result<S::Ok, S::Error> {
+        s.collect_str(d)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Decimal, D::Error> {
+        d.deserialize_any(DecimalVisitor)
+    }
+
+    /// Strings parse exactly (more digits than a Decimal holds is an error, not rounding);
+    /// floats are whatever the number already was once JSON decoded it.
+    struct DecimalVisitor;
+
+    impl Visitor<'_> for DecimalVisitor {
+        type Value = Decimal;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a decimal as a string or number")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Decimal, E> {
+            Decimal::from_str_exact(v.trim()).map_err(|e| E::custom(format!("invalid decimal {:?}: {}", v, e)))
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Decimal, E> {
+            Ok(Decimal::from(v))
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Decimal, E> {
+            Ok(Decimal::from(v))
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<Decimal, E> {
+            Decimal::try_from(v).map_err(|e| E::custom(format!("invalid decimal {}: {}", v, e)))
+        }
+    }
+
+    pub mod option {
+        use rust_decimal::Decimal;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        #[derive(Deserialize)]
+        struct Exact(#[serde(with = "super")] Decimal);
+
+        pub fn serialize<S: Serializer>(d: &Option<Decimal>, s: S) -> Result<S::Ok, S::Error> {
+            match d {
+                Some(d) => s.serialize_some(&d.to_string()),
+                None => s.serialize_none(),
+            }
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Option<Decimal>, D::Error> {
+            Ok(Option::<Exact>::deserialize(d)?.map(|e| e.0))
+        }
+    }
+}
+
+/// Outcome token id, interned once at market refresh: clones share one allocation, so
+/// per-cycle maps, books and intents never copy the string.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct TokenId(Arc<str>);
+
+impl TokenId {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for TokenId {
+    fn from(s: &str) -> Self {
+        TokenId(Arc::from(s))
+    }
+}
+
+impl From<String> for TokenId {
+    fn from(s: String) -> Self {
+        TokenId(Arc::from(s))
+    }
+}
+
+impl std::borrow::Borrow<str> for TokenId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Display for TokenId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OutcomeTop {
-    pub token_id: String,
+    pub token_id: TokenId,
+    #[serde(default, with = "decimal_str::option")]
     pub best_bid_px: Option<Decimal>,
+    #[serde(default, with = "decimal_str::option")]
     pub best_bid_sz: Option<Decimal>,
+    #[serde(default, with = "decimal_str::option")]
     pub best_ask_px: Option<Decimal>,
+    #[serde(default, with = "decimal_str::option")]
     pub best_ask_sz: Option<Decimal>,
+    /// Ask levels from the best price up, only when the source captures depth
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ask_levels: Vec<BookLevel>,
+    /// When the venue last updated this book (ms), as opposed to when we fetched it;
+    /// None when the response carried no timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub book_ts_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BookLevel {
+    #[serde(with = "decimal_str")]
+    pub px: Decimal,
+    #[serde(with = "decimal_str")]
+    pub sz: Decimal,
+}
+
+impl OutcomeTop {
+    /// (bid + ask) / 2; None unless both sides are quoted.
+    pub fn mid(&self) -> Option<Decimal> {
+        Some((self.best_bid_px? + self.best_ask_px?) / Decimal::TWO)
+    }
+
+    /// ask - bid; None unless both sides are quoted.
+    pub fn spread(&self) -> Option<Decimal> {
+        Some(self.best_ask_px? - self.best_bid_px?)
+    }
+
+    /// Spread in bps of the mid; None when one-sided or the mid is not positive.
+    pub fn spread_bps(&self) -> Option<Decimal> {
+        let mid = self.mid().filter(|m| *m > Decimal::ZERO)?;
+        Some(self.spread()? / mid * Decimal::from(10000))
+    }
+
+    pub fn gauge(&self) -> LegGauge {
+        LegGauge {
+            token_id: self.token_id.clone(),
+            mid: self.mid(),
+            spread: self.spread(),
+            spread_bps: self.spread_bps().map(|b| b.round_dp(1)),
+        }
+    }
+}
+
+/// Mid and spread of one leg, as reported in logs, events and the API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LegGauge {
+    pub token_id: TokenId,
+    pub mid: Option<Decimal>,
+    pub spread: Option<Decimal>,
+    pub spread_bps: Option<Decimal>,
+}
+
+/// What the tokens of a bundle represent, and so what a complete set pays.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Polarity {
+    /// One token per mutually exclusive outcome; exactly one pays, so a set pays 1
+    #[default]
+    Yes,
+    /// The NO token of each of n mutually exclusive outcomes; all but one pay, so a set pays n - 1
+    No,
+}
+
+impl Polarity {
+    /// Payout per share of a complete set of `legs` tokens.
+    pub fn payoff(&self, legs: usize) -> Decimal {
+        match self {
+            Polarity::Yes => Decimal::ONE,
+            Polarity::No => Decimal::from(legs.saturating_sub(1) as u64),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketBook {
     pub market_id: String,
     pub question: String,
+    #[serde(default)]
+    pub polarity: Polarity,
+    #[serde(default)]
+    pub event_id: Option<String>,
+    /// Venue category, when known; see `category_bucket`
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Venue minimum order size per leg in shares, when known
+    #[serde(default, with = "decimal_str::option")]
+    pub min_order_size: Option<Decimal>,
+    /// What a complete set pays, when it is not the polarity's usual payoff
+    #[serde(default, with = "decimal_str::option")]
+    pub redemption_value: Option<Decimal>,
+    /// Most markets sharing one of this market's token books, when some token is shared;
+    /// the strategy sizes against 1 / shared_by of the book
+    #[serde(default)]
+    pub shared_by: Option<usize>,
     pub outcomes: Vec<OutcomeTop>,
+    /// Some tokens had no book this cycle, so `outcomes` is incomplete. Kept for
+    /// visibility only: never eligible for execution
+    #[serde(default)]
+    pub partial: bool,
+}
+
+impl MarketBook {
+    /// Payout per share of a complete set: the redemption value when set, else the
+    /// polarity's payoff over the legs.
+    pub fn payoff(&self) -> Decimal {
+        self.redemption_value.unwrap_or_else(|| self.polarity.payoff(self.outcomes.len()))
+    }
+}
+
+/// Bucket a market is filtered and counted under: its category lowercased, or
+/// "uncategorized" when the venue gave none.
+pub fn category_bucket(category: Option<&str>) -> String {
+    match category.map(str::trim).filter(|c| !c.is_empty()) {
+        Some(c) => c.to_lowercase(),
+        None => "uncategorized".to_string(),
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GlobalSnapshot {
     pub ts_ms: i64,
     pub markets: Vec<MarketBook>,
+    /// Share of the requested tokens the venue returned a book for this cycle (0..=1)
+    #[serde(default = "full_coverage")]
+    pub coverage: f64,
+}
+
+fn full_coverage() -> f64 {
+    1.0
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Side {
     Buy,
     Sell,
 }
 
-#[derive(Debug, Clone)]
+/// How long an order may rest on the book, named as in the venue's order types.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum TimeInForce {
+    /// Good till cancelled: rests until filled or cancelled
+    Gtc,
+    /// Immediate or cancel: fills what it can now, the rest is cancelled
+    Ioc,
+    /// Fill or kill: fills completely now or not at all. Bundle legs default to this so a
+    /// leg never rests and leaves one-sided exposure
+    #[default]
+    Fok,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderIntent {
     pub market_id: String,
-    pub token_id: String,
+    pub token_id: TokenId,
     pub side: Side,
     pub price: Decimal,
     pub size: Decimal,
+    /// Human summary generated from `meta`; parse `meta` instead
     pub reason: String,
     pub bundle_id: Uuid,
+    #[serde(default)]
+    pub tif: TimeInForce,
+    /// Reject rather than take liquidity; only meaningful with Gtc
+    #[serde(default)]
+    pub post_only: bool,
+    /// Shares the strategy saw available for this leg; orders scarcest-first submission
+    #[serde(default)]
+    pub avail_size: Option<Decimal>,
+    #[serde(default)]
+    pub meta: IntentMeta,
+}
+
+/// Why an intent was emitted, as structured fields for downstream consumers.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IntentMeta {
+    /// Strategy that emitted the bundle
+    pub strategy: String,
+    /// Net edge in bps at detection
+    pub edge_bps: Decimal,
+    pub sum_ask: Decimal,
+    pub sum_bid: Decimal,
+    /// Expected net profit of the whole bundle, after fees and fixed cost
+    #[serde(default)]
+    pub net_profit: Decimal,
+    /// Fetch time of the snapshot the bundle was detected on
+    pub snapshot_ts_ms: i64,
+    /// This leg's position among the market's outcomes, and their count
+    pub leg_index: usize,
+    pub leg_count: usize,
+}
+
+impl IntentMeta {
+    /// The `reason` string for a buy of `size` shares.
+    pub fn summary(&self, size: Decimal) -> String {
+        format!(
+            "BUY_BUNDLE sum_ask={} size={} edge_bps={} leg={}/{} strategy={}",
+            self.sum_ask,
+            size,
+            self.edge_bps,
+            self.leg_index + 1,
+            self.leg_count,
+            self.strategy
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use rust_decimal_macros::dec;
+
+    use super::*;
+    use crate::testutil::snapshot_builder::outcome;
+
+    #[test]
+    fn no_set_of_n_pays_n_minus_one() {
+        assert_eq!(Polarity::Yes.payoff(5), Decimal::ONE);
+        assert_eq!(Polarity::No.payoff(2), Decimal::ONE);
+        assert_eq!(Polarity::No.payoff(5), Decimal::from(4));
+        assert_eq!(Polarity::No.payoff(0), Decimal::ZERO);
+    }
+
+    fn intent(tif: TimeInForce, post_only: bool) -> OrderIntent {
+        let meta = IntentMeta { strategy: "sum_arb".into(), leg_count: 2, ..Default::default() };
+        OrderIntent {
+            market_id: "m".into(),
+            token_id: TokenId::from("1"),
+            side: Side::Buy,
+            price: Decimal::from_str("0.47").unwrap(),
+            size: Decimal::from_str("12.50").unwrap(),
+            reason: meta.summary(Decimal::from(10)),
+            bundle_id: uuid::Uuid::from_u128(7),
+            tif,
+            post_only,
+            avail_size: None,
+            meta,
+        }
+    }
+
+    #[test]
+    fn intent_round_trips_with_tif_and_post_only() {
+        for (tif, post_only, name) in [(TimeInForce::Gtc, true, "GTC"), (TimeInForce::Ioc, false, "IOC"), (TimeInForce::Fok, false, "FOK")] {
+            let json = serde_json::to_value(intent(tif, post_only)).unwrap();
+            assert_eq!((&json["tif"], &json["post_only"]), (&serde_json::json!(name), &serde_json::json!(post_only)));
+            let back: OrderIntent = serde_json::from_value(json.clone()).unwrap();
+            assert_eq!((back.tif, back.post_only), (tif, post_only));
+            assert_eq!(serde_json::to_value(back).unwrap(), json);
+        }
+    }
+
+    #[test]
+    fn intents_without_tif_read_as_fill_or_kill() {
+        let mut json = serde_json::to_value(intent(TimeInForce::Gtc, true)).unwrap();
+        let obj = json.as_object_mut().unwrap();
+        obj.remove("tif");
+        obj.remove("post_only");
+        let back: OrderIntent = serde_json::from_value(json).unwrap();
+        assert_eq!((back.tif, back.post_only), (TimeInForce::Fok, false));
+    }
+
+    fn top(px: &str) -> OutcomeTop {
+        OutcomeTop {
+            token_id: TokenId::from("t1"),
+            best_bid_px: None,
+            best_bid_sz: Some(Decimal::from(250)),
+            best_ask_px: Some(Decimal::from_str(px).unwrap()),
+            best_ask_sz: Some(Decimal::from_str("12.50").unwrap()),
+            ask_levels: vec![BookLevel { px: Decimal::from_str(px).unwrap(), sz: Decimal::from_str("0.000001").unwrap() }],
+            book_ts_ms: None,
+        }
+    }
+
+    #[test]
+    fn decimal_str_round_trips_bit_exact() {
+        // more digits than an f64 carries, so any float step on the way would show
+        let t = top("0.1234567890123456789");
+        let json = serde_json::to_string(&t).unwrap();
+        assert!(json.contains(r#""best_ask_px":"0.1234567890123456789""#), "{}", json);
+        assert!(json.contains(r#""best_bid_px":null"#), "{}", json);
+
+        let back: OutcomeTop = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, t);
+        let (px, sz) = (back.best_ask_px.unwrap(), back.best_ask_sz.unwrap());
+        assert_eq!(px.serialize(), t.best_ask_px.unwrap().serialize());
+        // scale survives too: 12.50 stays 12.50, not 12.5
+        assert_eq!(sz.to_string(), "12.50");
+        assert_eq!(back.ask_levels[0].sz.to_string(), "0.000001");
+    }
+
+    #[test]
+    fn decimal_str_reads_numbers_and_missing_fields() {
+        let t: OutcomeTop = serde_json::from_str(r#"{"token_id":"t1","best_bid_px":0.47,"best_ask_sz":100}"#).unwrap();
+        assert_eq!(t.best_bid_px, Some(Decimal::from_str("0.47").unwrap()));
+        assert_eq!(t.best_ask_sz, Some(Decimal::from(100)));
+        assert_eq!(t.best_ask_px, None);
+
+        let l: BookLevel = serde_json::from_str(r#"{"px":"0.5","sz":-3}"#).unwrap();
+        assert_eq!((l.px, l.sz), (Decimal::from_str("0.5").unwrap(), Decimal::from(-3)));
+    }
+
+    #[test]
+    fn decimal_str_rejects_garbage_and_inexact_strings() {
+        assert!(serde_json::from_str::<BookLevel>(r#"{"px":"abc","sz":"1"}"#).is_err());
+        assert!(serde_json::from_str::<BookLevel>(r#"{"px":true,"sz":"1"}"#).is_err());
+        // 30 fractional digits do not fit a Decimal; exact parsing refuses to round them
+        assert!(serde_json::from_str::<BookLevel>(r#"{"px":"0.123456789012345678901234567890","sz":"1"}"#).is_err());
+    }
+
+    #[test]
+    fn mid_and_spread_of_a_two_sided_leg() {
+        let o = outcome("t", dec!(0.40), dec!(0.50), dec!(10));
+        assert_eq!((o.mid(), o.spread()), (Some(dec!(0.45)), Some(dec!(0.10))));
+        // 0.10 / 0.45 = 2222.2 bps
+        assert_eq!(o.gauge().spread_bps, Some(dec!(2222.2)));
+    }
+
+    #[test]
+    fn one_sided_leg_has_no_gauges() {
+        for (bid, ask) in [(None, Some(dec!(0.5))), (Some(dec!(0.5)), None), (None, None)] {
+            let mut o = outcome("t", dec!(0), dec!(0), dec!(10));
+            (o.best_bid_px, o.best_ask_px) = (bid, ask);
+            assert_eq!((o.mid(), o.spread(), o.spread_bps()), (None, None, None));
+        }
+    }
+
+    #[test]
+    fn zero_mid_has_no_spread_bps() {
+        let o = outcome("t", dec!(0), dec!(0), dec!(10));
+        assert_eq!((o.mid(), o.spread()), (Some(dec!(0)), Some(dec!(0))));
+        assert_eq!(o.spread_bps(), None);
+        // a crossed book can average to zero too
+        let crossed = outcome("t", dec!(0.01), dec!(-0.01), dec!(10));
+        assert_eq!(crossed.spread_bps(), None);
+    }
 }
@@ -0,0 +1,101 @@
+//! End to end over a mocked CLOB: market refresh, book fetch, snapshot assembly and one
+//! strategy pass, against recorded /markets and /books fixtures.
+
+use std::sync::Arc;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use polymarket_arb_bot::config::Settings;
+use polymarket_arb_bot::history::PriceHistory;
+use polymarket_arb_bot::http::HttpClient;
+use polymarket_arb_bot::jitter::Jitter;
+use polymarket_arb_bot::pm::market_data::MarketData;
+use polymarket_arb_bot::stats::Stats;
+use polymarket_arb_bot::strategy::fees::FeeModel;
+use polymarket_arb_bot::strategy::sum_arb::SumArbStrategy;
+use polymarket_arb_bot::strategy::{Strategy, StrategyContext};
+use polymarket_arb_bot::types::Side;
+
+const MARKETS: &str = include_str!("fixtures/polymarket/markets.json");
+const BOOKS: &str = include_str!("fixtures/polymarket/books.json");
+
+const ARB_MARKET: &str = "0xa1b2c3d4e5f60718293a4b5c6d7e8f9001122334455667788990aabbccddeeff";
+const NEAR_ARB_MARKET: &str = "0xb2c3d4e5f60718293a4b5c6d7e8f9001122334455667788990aabbccddeeff00";
+
+async fn clob() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/markets"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(MARKETS, "application/json"))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/books"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(BOOKS, "application/json"))
+        .mount(&server)
+        .await;
+    server
+}
+
+/// The settings .env.example ships, pointed at `host`, with no fees.
+fn settings(host: &str) -> Settings {
+    serde_json::from_value(serde_json::json!({
+        "clob_host": host,
+        "poll_ms": 1000,
+        "max_markets": 200,
+        "markets_refresh_sec": 0,
+        "books_chunk_size": 100,
+        "books_concurrency": 1,
+        "fee_bps": 0,
+        "min_edge_bps": 30,
+        "warn_edge_bps": 150,
+        "stats_log_sec": 60,
+        "max_bundle_shares": "100",
+        "http_max_retries": 0,
+    }))
+    .expect("test settings")
+}
+
+#[tokio::test]
+async fn one_arb_emits_a_bundle_and_a_near_arb_is_only_counted() {
+    let server = clob().await;
+    let s = settings(&server.uri());
+    let parsed = s.parsed().unwrap();
+    let stats = Stats::new(0);
+    let http = HttpClient::from_settings(&s, Arc::new(Jitter::seeded(1))).unwrap();
+    let md = MarketData::from_settings(&s, http, false, stats.clone());
+
+    let markets = md.fetch_open_markets(s.max_markets).await.unwrap();
+    // the closed market is filtered at refresh
+    assert_eq!(markets.len(), 3);
+
+    let snap = md.snapshot_for_markets(&markets).await.unwrap();
+    assert_eq!(snap.markets.len(), 3);
+    assert_eq!(snap.coverage, 1.0);
+
+    let strat = SumArbStrategy::from_settings(&s, &parsed, Arc::new(FeeModel::flat(s.fee_bps)), stats.clone());
+    let history = PriceHistory::new(s.history_depth, s.history_evict_cycles);
+    let ctx = StrategyContext { history: &history, meta: None, reference: None };
+    let (intents, summary) = strat.on_snapshot(&snap, &ctx);
+
+    assert_eq!(summary.markets_evaluated, 3);
+    assert_eq!(summary.opportunities, 1);
+    // the arb is a near-arb too; the 0.9985 market is one without being executable
+    assert_eq!(summary.near_arb_hits, 2);
+    // the near-arb and the fairly priced market
+    assert_eq!(summary.below_edge, 2);
+    assert_eq!(stats.snapshot(0).near_arb_hits, 2);
+
+    assert_eq!(intents.len(), 2, "{:#?}", intents);
+    assert!(intents.iter().all(|i| i.market_id == ARB_MARKET), "near-arb {} emitted intents", NEAR_ARB_MARKET);
+    assert!(intents.iter().all(|i| i.bundle_id == intents[0].bundle_id && i.side == Side::Buy));
+    let prices: Vec<Decimal> = intents.iter().map(|i| i.price).collect();
+    assert_eq!(prices, [dec!(0.45), dec!(0.47)]);
+    // the thinnest best ask (60 at 0.45) bounds the bundle
+    assert!(intents.iter().all(|i| i.size == dec!(60)));
+    assert_eq!(intents[0].meta.sum_ask, dec!(0.92));
+    assert_eq!(intents[0].meta.net_profit, dec!(4.80));
+}